@@ -1,4 +1,4 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, io::Read};
 
 use clap::Parser;
 use crossterm::{
@@ -7,7 +7,8 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ctflgrdifflib::{
-    compute_diff_with_format, FormatError, FunctionName, IntoDiffResult, MatchDirection,
+    compute_diff_from_bytes_with_formats, compute_diff_with_formats, FormatError, FunctionLocation,
+    FunctionName, IntoDiffResult, MatchDirection,
 };
 use tui::{
     backend::CrosstermBackend,
@@ -21,20 +22,71 @@ use tui::{
 #[command(author, version, about, long_about = None)]
 struct Args {
     /// The function name to compare
-    #[arg(short, long)]
+    #[arg(short, long, visible_alias = "left-fn")]
     name: Option<String>,
     /// If the function is different named in the the two files (e.g., when name mangling includes type information), this is the name of the function in the right-hand file
-    #[arg(long)]
+    #[arg(long, visible_alias = "right-fn")]
     right_name: Option<String>,
 
     /// The file format to parse
     #[arg(short, long)]
     format: String,
+    /// If the right-hand file is in a different format (e.g., machine code compiled from LLVM IR), the format of the right-hand file; instructions are then only compared by their broad class (arithmetic, memory, branch, etc.)
+    #[arg(long)]
+    right_format: Option<String>,
+    /// If the left-hand file contains several architectures (e.g., a universal MachO binary), the one to compare (e.g., `arm64e` or `x86_64h`)
+    #[arg(long)]
+    left_arch: Option<String>,
+    /// If the right-hand file contains several architectures, the one to compare; the same file can be given twice to compare two of its architectures
+    #[arg(long)]
+    right_arch: Option<String>,
+    /// Show the source file and line of each instruction, if the files have debugging information; this can also be toggled by pressing `l`
+    #[arg(short, long)]
+    locations: bool,
+    /// The file to compare; a directory, a glob, or a response file (e.g., `@objects.txt`) can be given to compare many files at once, or `-` to read standard input
     left_file: String,
-    right_file: String,
+    /// The file to compare against; if omitted, two functions in the left-hand file are compared
+    right_file: Option<String>,
+}
+/// Read the inputs into memory when either is standard input (`-`)
+///
+/// Standard input can only be read once, so it is shared if both sides use it.
+fn read_inputs(left: &str, right: &str) -> Result<(Vec<u8>, Vec<u8>), FormatError> {
+    let mut stdin = Vec::new();
+    if left == "-" || right == "-" {
+        std::io::stdin().read_to_end(&mut stdin).map_err(|e| {
+            FormatError::ParseError(
+                if left == "-" {
+                    FunctionLocation::Left
+                } else {
+                    FunctionLocation::Right
+                },
+                e.to_string(),
+            )
+        })?;
+    }
+    let read = |file: &str, location| {
+        if file == "-" {
+            Ok(stdin.clone())
+        } else {
+            std::fs::read(file).map_err(|e| FormatError::ParseError(location, e.to_string()))
+        }
+    };
+    Ok((
+        read(left, FunctionLocation::Left)?,
+        read(right, FunctionLocation::Right)?,
+    ))
 }
 fn main() {
     let args = Args::parse();
+    let right_file = match args.right_file {
+        Some(right_file) => right_file,
+        None if args.right_name.is_some() => args.left_file.clone(),
+        None => {
+            eprintln!("Only one file is supplied. Use --right-fn to select a function in it to compare against.");
+            std::process::exit(4);
+        }
+    };
     let function_name = match (args.name, args.right_name) {
         (Some(left), Some(right)) => FunctionName::Different(left, right),
         (Some(left), None) => FunctionName::Same(left),
@@ -47,175 +99,233 @@ fn main() {
         }
     };
 
-    std::process::exit(
-        match compute_diff_with_format::<ConsoleOutput>(
+    let right_format = args.right_format.as_deref().unwrap_or(args.format.as_str());
+    let result = if args.left_file == "-" || right_file == "-" {
+        read_inputs(&args.left_file, &right_file).and_then(|(left, right)| {
+            compute_diff_from_bytes_with_formats::<ConsoleOutput>(
+                args.format.as_str(),
+                args.left_arch.as_deref(),
+                &left,
+                right_format,
+                args.right_arch.as_deref(),
+                &right,
+                function_name,
+            )
+        })
+    } else {
+        compute_diff_with_formats::<ConsoleOutput>(
             args.format.as_str(),
-            args.left_file,
-            args.right_file,
+            args.left_arch.as_deref(),
+            &args.left_file,
+            right_format,
+            args.right_arch.as_deref(),
+            &right_file,
             function_name,
-        ) {
-            Err(FormatError::BadFormat) => {
-                eprintln!("Can't parse “{}” files. Sorry.", args.format.as_str());
-                2
-            }
-            Err(FormatError::NoMatch(location)) => {
-                eprintln!("Cannot find function in {} file", location.name());
-                3
-            }
-            Err(FormatError::ParseError(location, e)) => {
-                eprintln!("Failed to parse {} file: {}", location.name(), e);
-                3
+        )
+    };
+
+    std::process::exit(match result {
+        Err(FormatError::BadFormat) => {
+            match args.right_format.as_deref() {
+                Some(right_format) if right_format != args.format => eprintln!(
+                    "Can't parse “{}” or “{}” files. Sorry.",
+                    args.format.as_str(),
+                    right_format
+                ),
+                _ => eprintln!("Can't parse “{}” files. Sorry.", args.format.as_str()),
             }
-            Ok((has_diff, diffs)) => {
-                if diffs.is_empty() {
-                    0
-                } else {
-                    let titles = Tabs::new(
-                        diffs
-                            .iter()
-                            .map(|output| Spans::from(vec![Span::raw(&output.0)]))
-                            .collect(),
-                    )
-                    .block(Block::default().title("Function").borders(Borders::ALL));
-                    let mut table_state = TableState::default();
-                    let mut active_tab = 0;
-                    let mut stdout = std::io::stdout();
-                    match enable_raw_mode()
-                        .and_then(|_| execute!(stdout, EnterAlternateScreen, EnableMouseCapture))
-                        .and_then(|_| Terminal::new(CrosstermBackend::new(stdout)))
-                    {
-                        Ok(mut terminal) => {
-                            loop {
-                                let mut page = 10;
-                                terminal
-                                    .draw(|rect| {
-                                        let size = rect.size();
-                                        let chunks = Layout::default()
-                                            .direction(Direction::Vertical)
-                                            .margin(2)
-                                            .constraints(
-                                                [Constraint::Length(3), Constraint::Min(20)]
-                                                    .as_ref(),
-                                            )
-                                            .split(size);
-                                        page = chunks[1].height;
-                                        rect.render_widget(
-                                            titles.clone().select(active_tab),
-                                            chunks[0],
-                                        );
-                                        rect.render_stateful_widget(
-                                            diffs[active_tab].1.clone(),
-                                            chunks[1],
-                                            &mut table_state,
-                                        );
-                                    })
-                                    .unwrap();
+            2
+        }
+        Err(FormatError::NoMatch(location)) => {
+            eprintln!("Cannot find function in {} file", location.name());
+            3
+        }
+        Err(FormatError::Ambiguous(location, name, candidates)) => {
+            eprintln!(
+                "{} is defined more than once in {} file; select one of {}",
+                name,
+                location.name(),
+                candidates.join(", ")
+            );
+            3
+        }
+        Err(FormatError::ParseError(location, e)) => {
+            eprintln!("Failed to parse {} file: {}", location.name(), e);
+            3
+        }
+        Ok((has_diff, diffs)) => {
+            if diffs.is_empty() {
+                0
+            } else {
+                let titles = Tabs::new(
+                    diffs
+                        .iter()
+                        .map(|output| Spans::from(vec![Span::raw(&output.0)]))
+                        .collect(),
+                )
+                .block(Block::default().title("Function").borders(Borders::ALL));
+                let mut table_state = TableState::default();
+                let mut active_tab = 0;
+                let mut show_locations = args.locations;
+                let mut stdout = std::io::stdout();
+                match enable_raw_mode()
+                    .and_then(|_| execute!(stdout, EnterAlternateScreen, EnableMouseCapture))
+                    .and_then(|_| Terminal::new(CrosstermBackend::new(stdout)))
+                {
+                    Ok(mut terminal) => {
+                        loop {
+                            let mut page = 10;
+                            terminal
+                                .draw(|rect| {
+                                    let size = rect.size();
+                                    let chunks = Layout::default()
+                                        .direction(Direction::Vertical)
+                                        .margin(2)
+                                        .constraints(
+                                            [Constraint::Length(3), Constraint::Min(20)].as_ref(),
+                                        )
+                                        .split(size);
+                                    page = chunks[1].height;
+                                    rect.render_widget(
+                                        titles.clone().select(active_tab),
+                                        chunks[0],
+                                    );
+                                    rect.render_stateful_widget(
+                                        if show_locations {
+                                            diffs[active_tab].2.clone()
+                                        } else {
+                                            diffs[active_tab].1.clone()
+                                        },
+                                        chunks[1],
+                                        &mut table_state,
+                                    );
+                                })
+                                .unwrap();
 
-                                if let Event::Key(key) =
-                                    crossterm::event::read().expect("Failed to read from terminal")
-                                {
-                                    match key.code {
-                                        KeyCode::Esc | KeyCode::Char('x') | KeyCode::Char('q') => {
-                                            break
-                                        }
-                                        KeyCode::Right => {
-                                            if active_tab < diffs.len() - 1 {
-                                                active_tab += 1;
-                                                table_state.select(Some(0));
-                                            }
-                                        }
-                                        KeyCode::Left => {
-                                            if active_tab > 0 {
-                                                active_tab -= 1;
-                                                table_state.select(Some(0));
-                                            }
-                                        }
-                                        KeyCode::Down => {
-                                            table_state.select(Some(
-                                                table_state
-                                                    .selected()
-                                                    .unwrap_or(0)
-                                                    .saturating_add(1),
-                                            ));
-                                        }
-                                        KeyCode::Up => {
-                                            table_state.select(Some(
-                                                table_state
-                                                    .selected()
-                                                    .unwrap_or(0)
-                                                    .saturating_sub(1),
-                                            ));
-                                        }
-                                        KeyCode::PageDown => {
-                                            table_state.select(Some(
-                                                table_state
-                                                    .selected()
-                                                    .unwrap_or(0)
-                                                    .saturating_add(page as usize),
-                                            ));
-                                        }
-                                        KeyCode::PageUp => {
-                                            table_state.select(Some(
-                                                table_state
-                                                    .selected()
-                                                    .unwrap_or(0)
-                                                    .saturating_sub(page as usize),
-                                            ));
-                                        }
-                                        _ => {}
+                            if let Event::Key(key) =
+                                crossterm::event::read().expect("Failed to read from terminal")
+                            {
+                                match key.code {
+                                    KeyCode::Esc | KeyCode::Char('x') | KeyCode::Char('q') => break,
+                                    KeyCode::Char('l') => {
+                                        show_locations = !show_locations;
+                                    }
+                                    KeyCode::Right if active_tab < diffs.len() - 1 => {
+                                        active_tab += 1;
+                                        table_state.select(Some(0));
+                                    }
+                                    KeyCode::Left if active_tab > 0 => {
+                                        active_tab -= 1;
+                                        table_state.select(Some(0));
+                                    }
+                                    KeyCode::Down => {
+                                        table_state.select(Some(
+                                            table_state.selected().unwrap_or(0).saturating_add(1),
+                                        ));
                                     }
+                                    KeyCode::Up => {
+                                        table_state.select(Some(
+                                            table_state.selected().unwrap_or(0).saturating_sub(1),
+                                        ));
+                                    }
+                                    KeyCode::PageDown => {
+                                        table_state.select(Some(
+                                            table_state
+                                                .selected()
+                                                .unwrap_or(0)
+                                                .saturating_add(page as usize),
+                                        ));
+                                    }
+                                    KeyCode::PageUp => {
+                                        table_state.select(Some(
+                                            table_state
+                                                .selected()
+                                                .unwrap_or(0)
+                                                .saturating_sub(page as usize),
+                                        ));
+                                    }
+                                    _ => {}
                                 }
                             }
-                            if let Err(e) = disable_raw_mode()
-                                .and_then(|_| {
-                                    execute!(
-                                        terminal.backend_mut(),
-                                        LeaveAlternateScreen,
-                                        DisableMouseCapture
-                                    )
-                                })
-                                .and_then(|_| terminal.show_cursor())
-                            {
-                                eprintln!("Failed to reset terminal: {}", e);
-                            }
-                            if has_diff {
-                                1
-                            } else {
-                                0
-                            }
                         }
-                        Err(e) => {
-                            eprintln!("Failed to initialize terminal: {}", e);
-                            100
+                        if let Err(e) = disable_raw_mode()
+                            .and_then(|_| {
+                                execute!(
+                                    terminal.backend_mut(),
+                                    LeaveAlternateScreen,
+                                    DisableMouseCapture
+                                )
+                            })
+                            .and_then(|_| terminal.show_cursor())
+                        {
+                            eprintln!("Failed to reset terminal: {}", e);
+                        }
+                        if has_diff {
+                            1
+                        } else {
+                            0
                         }
                     }
+                    Err(e) => {
+                        eprintln!("Failed to initialize terminal: {}", e);
+                        100
+                    }
                 }
             }
-        },
-    );
+        }
+    });
 }
 
-struct ConsoleOutput(String, Table<'static>);
+/// The output for a function: the title and the table without and with source locations
+struct ConsoleOutput(String, Table<'static>, Table<'static>);
 impl IntoDiffResult for ConsoleOutput {
-    type Row = Row<'static>;
+    type Row = (Row<'static>, Row<'static>);
 
     fn block_row(left: Cow<str>, right: Cow<str>) -> Self::Row {
-        Row::new([left.to_string(), right.to_string()].into_iter())
-            .style(Style::default().fg(Color::LightBlue))
+        let style = Style::default().fg(Color::LightBlue);
+        (
+            Row::new([left.to_string(), right.to_string()]).style(style),
+            Row::new([
+                left.to_string(),
+                String::new(),
+                right.to_string(),
+                String::new(),
+            ])
+            .style(style),
+        )
     }
 
     fn row(left: Cow<str>, right: Cow<str>, kind: MatchDirection) -> Self::Row {
-        Row::new([left.to_string(), right.to_string()].into_iter()).style(Style::default().bg(
-            match kind {
-                MatchDirection::Align(true) => Color::Black,
-                MatchDirection::Align(false) => Color::Blue,
-                MatchDirection::GapLeft => Color::Cyan,
-                MatchDirection::GapRight => Color::Magenta,
-            },
-        ))
+        Self::row_with_location(left, right, kind, None, None)
+    }
+
+    fn row_with_location(
+        left: Cow<str>,
+        right: Cow<str>,
+        kind: MatchDirection,
+        left_location: Option<Cow<str>>,
+        right_location: Option<Cow<str>>,
+    ) -> Self::Row {
+        let style = Style::default().bg(match kind {
+            MatchDirection::Align(true) => Color::Black,
+            MatchDirection::Align(false) => Color::Blue,
+            MatchDirection::GapLeft => Color::Cyan,
+            MatchDirection::GapRight => Color::Magenta,
+        });
+        (
+            Row::new([left.to_string(), right.to_string()]).style(style),
+            Row::new([
+                left.to_string(),
+                left_location.unwrap_or_default().to_string(),
+                right.to_string(),
+                right_location.unwrap_or_default().to_string(),
+            ])
+            .style(style),
+        )
     }
 
     fn function(left_name: Cow<str>, right_name: Cow<str>, rows: Vec<Self::Row>) -> Self {
+        let (rows, location_rows): (Vec<_>, Vec<_>) = rows.into_iter().unzip();
         ConsoleOutput(
             if left_name.as_ref() == right_name.as_ref() {
                 left_name.to_string()
@@ -224,12 +334,30 @@ impl IntoDiffResult for ConsoleOutput {
             },
             Table::new(rows)
                 .header(
-                    Row::new([left_name.to_string(), right_name.to_string()].into_iter())
+                    Row::new([left_name.to_string(), right_name.to_string()])
                         .style(Style::default().fg(Color::White)),
                 )
                 .widths(&[Constraint::Percentage(50), Constraint::Percentage(50)])
                 .style(Style::default().fg(Color::White))
                 .highlight_symbol(">>"),
+            Table::new(location_rows)
+                .header(
+                    Row::new([
+                        left_name.to_string(),
+                        "Source".to_string(),
+                        right_name.to_string(),
+                        "Source".to_string(),
+                    ])
+                    .style(Style::default().fg(Color::White)),
+                )
+                .widths(&[
+                    Constraint::Percentage(35),
+                    Constraint::Percentage(15),
+                    Constraint::Percentage(35),
+                    Constraint::Percentage(15),
+                ])
+                .style(Style::default().fg(Color::White))
+                .highlight_symbol(">>"),
         )
     }
 }
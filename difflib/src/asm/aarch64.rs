@@ -0,0 +1,168 @@
+use super::OperandKind;
+use crate::InstructionClass;
+
+/// AArch64 (ARMv8) assembly
+pub struct AArch64;
+impl super::Dialect for AArch64 {
+    const COMMENTS: &'static [&'static str] = &["//", ";"];
+    const PREFIXES: &'static [&'static str] = &[];
+    const GAP: i32 = 2;
+
+    fn is_flow_control(mnemonic: &str) -> bool {
+        matches!(
+            mnemonic,
+            "b" | "bl" | "blr" | "br" | "cbnz" | "cbz" | "ret" | "tbnz" | "tbz"
+        ) || mnemonic.starts_with("b.")
+    }
+
+    fn operand_kind(operand: &str) -> OperandKind {
+        if operand.starts_with('#') {
+            OperandKind::Immediate
+        } else if operand.starts_with('[') {
+            OperandKind::Memory
+        } else if matches!(operand, "sp" | "wsp" | "xzr" | "wzr" | "lr" | "fp")
+            || operand.starts_with('{')
+            || operand
+                .strip_prefix(['b', 'd', 'h', 'q', 's', 'v', 'w', 'x', 'z', 'p'])
+                .map(|n| {
+                    let n = n.split('.').next().unwrap_or(n);
+                    !n.is_empty() && n.chars().all(|c| c.is_ascii_digit())
+                })
+                .unwrap_or(false)
+        {
+            OperandKind::Register
+        } else if operand.starts_with("lsl")
+            || operand.starts_with("lsr")
+            || operand.starts_with("asr")
+            || operand.starts_with("ror")
+            || operand.starts_with("uxt")
+            || operand.starts_with("sxt")
+        {
+            // Shifts and extends
+            OperandKind::Immediate
+        } else {
+            OperandKind::Symbol
+        }
+    }
+
+    fn base_mnemonic(mnemonic: &str) -> &str {
+        // Conditional branches and the flag-setting forms of arithmetic are grouped with their basic operation
+        if mnemonic.starts_with("b.") {
+            "b"
+        } else {
+            match mnemonic {
+                "adds" => "add",
+                "subs" => "sub",
+                "ands" => "and",
+                "ldur" | "ldrb" | "ldrh" | "ldrsb" | "ldrsh" | "ldrsw" => "ldr",
+                "stur" | "strb" | "strh" => "str",
+                _ => mnemonic,
+            }
+        }
+    }
+
+    fn class(mnemonic: &str, operands: &str) -> InstructionClass {
+        // SIMD registers (v0) and SVE registers (z0, p0) mark vector operations
+        let vector = operands
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .any(|operand| {
+                operand
+                    .strip_prefix(['v', 'z'])
+                    .map(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+                    .unwrap_or(false)
+            });
+        if matches!(mnemonic, "bl" | "blr" | "blraa" | "blrab") {
+            InstructionClass::Call
+        } else if Self::is_flow_control(mnemonic) || matches!(mnemonic, "br" | "eret") {
+            InstructionClass::Branch
+        } else if mnemonic.starts_with("ld")
+            || mnemonic.starts_with("st")
+            || mnemonic.starts_with("prfm")
+            || mnemonic.starts_with("cas")
+            || mnemonic.starts_with("swp")
+            || matches!(mnemonic, "mov" | "movz" | "movn" | "movk")
+        {
+            InstructionClass::Memory
+        } else if vector {
+            InstructionClass::Vector
+        } else if matches!(
+            mnemonic,
+            "cmp" | "cmn" | "tst" | "ccmp" | "ccmn" | "cset" | "csetm"
+        ) || mnemonic.starts_with("fcmp")
+            || mnemonic.starts_with("fccmp")
+        {
+            InstructionClass::Compare
+        } else if mnemonic.starts_with('f') || mnemonic.ends_with("cvtf") {
+            InstructionClass::Float
+        } else if matches!(
+            mnemonic,
+            "and"
+                | "ands"
+                | "orr"
+                | "orn"
+                | "eor"
+                | "eon"
+                | "bic"
+                | "bics"
+                | "mvn"
+                | "lsl"
+                | "lsr"
+                | "asr"
+                | "ror"
+                | "ubfx"
+                | "sbfx"
+                | "ubfiz"
+                | "sbfiz"
+                | "bfi"
+                | "bfxil"
+                | "extr"
+                | "clz"
+                | "cls"
+                | "rbit"
+                | "rev"
+                | "rev16"
+                | "rev32"
+                | "csel"
+                | "csinv"
+        ) {
+            InstructionClass::Logic
+        } else if matches!(
+            Self::base_mnemonic(mnemonic),
+            "add"
+                | "sub"
+                | "adc"
+                | "adcs"
+                | "sbc"
+                | "sbcs"
+                | "neg"
+                | "negs"
+                | "mul"
+                | "madd"
+                | "msub"
+                | "mneg"
+                | "smull"
+                | "umull"
+                | "smulh"
+                | "umulh"
+                | "smaddl"
+                | "umaddl"
+                | "sdiv"
+                | "udiv"
+                | "sxtb"
+                | "sxth"
+                | "sxtw"
+                | "uxtb"
+                | "uxth"
+                | "adr"
+                | "adrp"
+                | "csinc"
+                | "csneg"
+                | "cinc"
+                | "cneg"
+        ) {
+            InstructionClass::Arith
+        } else {
+            InstructionClass::Other
+        }
+    }
+}
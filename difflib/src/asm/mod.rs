@@ -0,0 +1,610 @@
+pub mod aarch64;
+pub mod x86_64;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::marker::PhantomData;
+
+/// Assembly text as produced by `cc -S` (GNU as or LLVM MC syntax) or by `objdump -d`
+pub struct Assembly<D: Dialect> {
+    funcs: BTreeMap<String, AssemblyFunction<D>>,
+}
+
+pub struct AssemblyFunction<D: Dialect> {
+    blocks: Vec<AssemblyBlock<D>>,
+    name: String,
+}
+pub struct AssemblyBlock<D: Dialect> {
+    name: String,
+    instructions: Vec<AssemblyInstruction<D>>,
+    terminator: Option<AssemblyInstruction<D>>,
+}
+pub struct AssemblyInstruction<D: Dialect> {
+    mnemonic: String,
+    operands: Vec<String>,
+    dialect: PhantomData<D>,
+}
+
+/// The general category of an operand, used to compare instructions without comparing the exact registers or values used
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OperandKind {
+    Immediate,
+    Memory,
+    Register,
+    Symbol,
+}
+
+/// The syntax rules for a particular instruction set
+pub trait Dialect: Sized + Sync {
+    /// The strings that start a comment that runs to the end of the line
+    const COMMENTS: &'static [&'static str];
+    /// Mnemonics that are prefixes to another instruction (_e.g._, `lock`) and should be kept with it
+    const PREFIXES: &'static [&'static str];
+    const GAP: i32;
+    /// Determine if this instruction ends a basic block
+    fn is_flow_control(mnemonic: &str) -> bool;
+    /// Classify an operand
+    fn operand_kind(operand: &str) -> OperandKind;
+    /// Reduce a mnemonic to its base operation (_e.g._, remove operand size suffixes) so that closely related instructions can be partially matched
+    fn base_mnemonic(mnemonic: &str) -> &str;
+    /// Classify an instruction from its mnemonic and the text of its operands
+    fn class(mnemonic: &str, operands: &str) -> crate::InstructionClass;
+}
+
+impl<D: Dialect> crate::Program for Assembly<D> {
+    const GAP: i32 = D::GAP;
+
+    type ParseError = std::io::Error;
+
+    type ParseOptions = ();
+
+    type Function = AssemblyFunction<D>;
+
+    fn parse(
+        file: impl AsRef<std::path::Path>,
+        _options: Self::ParseOptions,
+    ) -> Result<Self, Self::ParseError> {
+        let text = std::fs::read_to_string(file)?;
+        Ok(Assembly {
+            funcs: parse_text(&text),
+        })
+    }
+
+    fn parse_bytes(bytes: &[u8], _options: Self::ParseOptions) -> Result<Self, Self::ParseError> {
+        let text = std::str::from_utf8(bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        Ok(Assembly {
+            funcs: parse_text(text),
+        })
+    }
+
+    fn get(&self, name: &str) -> Option<&Self::Function> {
+        self.funcs.get(name)
+    }
+
+    fn functions<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Self::Function> + 'a> {
+        Box::new(self.funcs.values())
+    }
+}
+impl<D: Dialect> crate::Function for AssemblyFunction<D> {
+    type BasicBlock = AssemblyBlock<D>;
+
+    fn blocks<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Self::BasicBlock> + 'a> {
+        Box::new(self.blocks.iter())
+    }
+
+    fn name<'a>(&'a self) -> Cow<'a, str> {
+        Cow::Borrowed(&self.name)
+    }
+}
+impl<D: Dialect> crate::BasicBlock for AssemblyBlock<D> {
+    type Instruction = AssemblyInstruction<D>;
+
+    type Terminator = Option<AssemblyInstruction<D>>;
+
+    fn get(&self, index: usize) -> &Self::Instruction {
+        &self.instructions[index]
+    }
+
+    fn len(&self) -> usize {
+        self.instructions.len()
+    }
+
+    fn name<'a>(&'a self) -> Cow<'a, str> {
+        Cow::Borrowed(&self.name)
+    }
+
+    fn terminator(&self) -> &Self::Terminator {
+        &self.terminator
+    }
+}
+impl<D: Dialect> crate::Instruction for AssemblyInstruction<D> {
+    const EQUIVALENT: i32 = 4;
+    fn score(&self, other: &Self) -> i32 {
+        if self.mnemonic == other.mnemonic {
+            if self.operands.len() == other.operands.len()
+                && self
+                    .operands
+                    .iter()
+                    .zip(other.operands.iter())
+                    .all(|(l, r)| D::operand_kind(l) == D::operand_kind(r))
+            {
+                4
+            } else {
+                3
+            }
+        } else if D::base_mnemonic(&self.mnemonic) == D::base_mnemonic(&other.mnemonic) {
+            2
+        } else {
+            0
+        }
+    }
+
+    fn render<'a>(&self) -> Cow<'a, str> {
+        if self.operands.is_empty() {
+            Cow::Owned(self.mnemonic.clone())
+        } else {
+            Cow::Owned(format!("{} {}", self.mnemonic, self.operands.join(", ")))
+        }
+    }
+
+    fn class(&self) -> crate::InstructionClass {
+        D::class(&self.mnemonic, &self.operands.join(", "))
+    }
+}
+
+/// A line of interest inside a function body
+enum Line<D: Dialect> {
+    Label(String),
+    Instruction(Option<u64>, AssemblyInstruction<D>),
+}
+
+struct FunctionBuilder<D: Dialect> {
+    name: String,
+    lines: Vec<Line<D>>,
+}
+impl<D: Dialect> FunctionBuilder<D> {
+    fn finish(self, targets: &BTreeSet<u64>) -> AssemblyFunction<D> {
+        fn flush<D: Dialect>(
+            blocks: &mut Vec<AssemblyBlock<D>>,
+            name: &mut Option<String>,
+            instructions: &mut Vec<AssemblyInstruction<D>>,
+            terminator: Option<AssemblyInstruction<D>>,
+        ) {
+            if instructions.is_empty() && terminator.is_none() {
+                return;
+            }
+            let id = blocks.len();
+            blocks.push(AssemblyBlock {
+                name: name.take().unwrap_or_else(|| format!("{}", id)),
+                instructions: std::mem::take(instructions),
+                terminator,
+            });
+        }
+        let mut blocks = Vec::new();
+        let mut name = None;
+        let mut instructions = Vec::new();
+        for line in self.lines {
+            match line {
+                Line::Label(label) => {
+                    flush(&mut blocks, &mut name, &mut instructions, None);
+                    name = Some(label);
+                }
+                Line::Instruction(address, instruction) => {
+                    if let Some(address) = address.filter(|a| targets.contains(a)) {
+                        flush(&mut blocks, &mut name, &mut instructions, None);
+                        name = Some(format!("{:x}", address));
+                    }
+                    if D::is_flow_control(&instruction.mnemonic) {
+                        flush(&mut blocks, &mut name, &mut instructions, Some(instruction));
+                    } else {
+                        instructions.push(instruction);
+                    }
+                }
+            }
+        }
+        // A chunk of assembly with no terminal flow control
+        flush(&mut blocks, &mut name, &mut instructions, None);
+        AssemblyFunction {
+            blocks,
+            name: crate::demangle(&self.name),
+        }
+    }
+}
+
+fn parse_text<D: Dialect>(text: &str) -> BTreeMap<String, AssemblyFunction<D>> {
+    // Functions are declared with `.type` before their labels appear, so collect the declarations first. If there are none (e.g., MachO assembly), any non-local label is treated as a function.
+    let declared: BTreeSet<&str> = text
+        .lines()
+        .filter_map(|line| {
+            let rest = strip_comment::<D>(line).trim().strip_prefix(".type")?;
+            let (name, kind) = rest.split_once(',')?;
+            let kind = kind.trim();
+            if kind == "@function" || kind == "%function" || kind == "STT_FUNC" {
+                Some(name.trim())
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    let mut funcs = BTreeMap::new();
+    let mut current: Option<FunctionBuilder<D>> = None;
+    // Branch targets are only known for disassembly listings, where instructions have addresses
+    let mut targets = BTreeSet::new();
+    let mut finish = |current: &mut Option<FunctionBuilder<D>>, targets: &mut BTreeSet<u64>| {
+        if let Some(builder) = current.take() {
+            let func = builder.finish(targets);
+            funcs.insert(func.name.clone(), func);
+        }
+        targets.clear();
+    };
+    for line in text.lines() {
+        if let Some(name) = parse_objdump_header(line) {
+            finish(&mut current, &mut targets);
+            current = Some(FunctionBuilder {
+                name: name.to_string(),
+                lines: Vec::new(),
+            });
+            continue;
+        }
+        if line.starts_with("Disassembly of section") {
+            finish(&mut current, &mut targets);
+            continue;
+        }
+        if let Some((address, body)) = parse_objdump_line(line) {
+            if let Some(builder) = current.as_mut() {
+                if let Some(instruction) = parse_instruction::<D>(strip_comment::<D>(body)) {
+                    if D::is_flow_control(&instruction.mnemonic) {
+                        // The target is the last operand (_e.g._, `cbz x0, 40 <sum+0x40>`), followed by objdump's symbolic annotation
+                        targets.extend(
+                            instruction
+                                .operands
+                                .last()
+                                .and_then(|op| op.split_whitespace().next())
+                                .and_then(|op| u64::from_str_radix(op, 16).ok()),
+                        );
+                    }
+                    builder
+                        .lines
+                        .push(Line::Instruction(Some(address), instruction));
+                }
+            }
+            continue;
+        }
+
+        let mut rest = strip_comment::<D>(line).trim();
+        while let Some((label, tail)) = split_label(rest) {
+            rest = tail.trim();
+            let is_function = if declared.is_empty() {
+                !is_local_label(label)
+            } else {
+                declared.contains(label)
+            };
+            if is_function {
+                finish(&mut current, &mut targets);
+                current = Some(FunctionBuilder {
+                    name: label.to_string(),
+                    lines: Vec::new(),
+                });
+            } else if let Some(builder) = current.as_mut() {
+                builder.lines.push(Line::Label(label.to_string()));
+            }
+        }
+        if rest.is_empty() {
+            continue;
+        }
+        if rest.starts_with('.') {
+            if rest.starts_with(".cfi_endproc") || rest.starts_with(".size") {
+                finish(&mut current, &mut targets);
+            }
+            continue;
+        }
+        if let Some(builder) = current.as_mut() {
+            if let Some(instruction) = parse_instruction::<D>(rest) {
+                builder.lines.push(Line::Instruction(None, instruction));
+            }
+        }
+    }
+    finish(&mut current, &mut targets);
+    funcs
+}
+
+fn strip_comment<D: Dialect>(line: &str) -> &str {
+    D::COMMENTS
+        .iter()
+        .filter_map(|comment| line.find(comment))
+        .min()
+        .map(|end| &line[..end])
+        .unwrap_or(line)
+}
+
+/// Match a function header in an `objdump -d` listing: `0000000000001139 <foo>:`
+fn parse_objdump_header(line: &str) -> Option<&str> {
+    let (address, rest) = line.split_once(' ')?;
+    if address.is_empty() || !address.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    rest.strip_prefix('<')?.strip_suffix(">:")
+}
+
+/// Match an instruction in an `objdump -d` listing: `    1139:\t55                   \tpush   %rbp`
+///
+/// Lines that only contain a continuation of the instruction bytes are skipped
+fn parse_objdump_line(line: &str) -> Option<(u64, &str)> {
+    if !line.starts_with(' ') {
+        return None;
+    }
+    let (address, rest) = line.split_once(":\t")?;
+    let address = u64::from_str_radix(address.trim(), 16).ok()?;
+    let body = match rest.split_once('\t') {
+        Some((_, body)) => body,
+        // Either the listing was produced without raw bytes or this is a continuation of the previous instruction's bytes
+        None if rest
+            .split_whitespace()
+            .all(|b| b.len() == 2 && b.chars().all(|c| c.is_ascii_hexdigit())) =>
+        {
+            ""
+        }
+        None => rest,
+    };
+    Some((address, body.trim()))
+}
+
+/// Split a leading `label:` from a line
+fn split_label(line: &str) -> Option<(&str, &str)> {
+    let (label, rest) = if let Some(quoted) = line.strip_prefix('"') {
+        let (label, rest) = quoted.split_once('"')?;
+        (label, rest.strip_prefix(':')?)
+    } else {
+        let (label, rest) = line.split_once(':')?;
+        if label.is_empty()
+            || !label
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '.' || c == '$' || c == '@')
+        {
+            return None;
+        }
+        (label, rest)
+    };
+    Some((label, rest))
+}
+
+/// Check if a label is assembler-local (a block label rather than a function)
+fn is_local_label(label: &str) -> bool {
+    label.starts_with(".L")
+        || label.starts_with('L')
+        || label.starts_with("l_")
+        || label.chars().all(|c| c.is_ascii_digit())
+}
+
+fn parse_instruction<D: Dialect>(text: &str) -> Option<AssemblyInstruction<D>> {
+    let mut rest = text.trim();
+    let mut mnemonic = String::new();
+    loop {
+        let (word, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        if word.is_empty() {
+            break;
+        }
+        if !mnemonic.is_empty() {
+            mnemonic.push(' ');
+        }
+        mnemonic.push_str(word);
+        rest = tail.trim();
+        if !D::PREFIXES.contains(&word) {
+            break;
+        }
+    }
+    if mnemonic.is_empty() {
+        return None;
+    }
+    let mut operands = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (index, c) in rest.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                operands.push(rest[start..index].trim().to_string());
+                start = index + 1;
+            }
+            _ => (),
+        }
+    }
+    let last = rest[start..].trim();
+    if !last.is_empty() {
+        operands.push(last.to_string());
+    }
+    Some(AssemblyInstruction {
+        mnemonic: mnemonic.to_lowercase(),
+        operands,
+        dialect: PhantomData,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{aarch64::AArch64, x86_64::X86_64, Assembly};
+    use crate::{BasicBlock, Function, Instruction, Program};
+
+    fn parse<D: super::Dialect>(file: &str) -> Assembly<D> {
+        Assembly::parse(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("testdata")
+                .join(file),
+            (),
+        )
+        .unwrap()
+    }
+
+    /// The blocks of a function, with the rendered instructions in each, the terminator last
+    fn rendered<D: super::Dialect>(
+        program: &Assembly<D>,
+        name: &str,
+    ) -> Vec<(String, Vec<String>)> {
+        program
+            .get(name)
+            .expect("function not found")
+            .blocks()
+            .map(|block| {
+                (
+                    block.name().into_owned(),
+                    (0..block.len())
+                        .map(|index| block.get(index).render().into_owned())
+                        .chain(std::iter::once(block.terminator().render().into_owned()))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn compiler_output() {
+        let program = parse::<X86_64>("x86_64-loop.s");
+        let names: Vec<_> = program
+            .functions()
+            .map(|function| function.name().into_owned())
+            .collect();
+        assert_eq!(names, ["clamp", "sum"]);
+        let sum = rendered(&program, "sum");
+        let blocks: Vec<_> = sum.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(blocks, ["0", "1", ".L3", ".L1", ".L4"]);
+        assert_eq!(sum[0].1, ["testl %esi, %esi", "jle .L4"]);
+        assert_eq!(sum[2].1.last().unwrap(), "jne .L3");
+        // Directives after the last instruction are not part of the function
+        assert_eq!(
+            rendered(&program, "clamp"),
+            [(
+                "0".to_string(),
+                vec![
+                    "testl %edi, %edi".to_string(),
+                    "movl $0, %eax".to_string(),
+                    "cmovns %edi, %eax".to_string(),
+                    "ret".to_string(),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn disassembly_matches_compiler_output() {
+        let compiled = parse::<X86_64>("x86_64-loop.s");
+        let listing = parse::<X86_64>("x86_64-loop.objdump");
+        // Branch targets split blocks in a listing, the same as labels in the compiler output
+        let blocks: Vec<_> = rendered(&listing, "sum")
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(blocks, ["0", "1", "13", "1e", "21"]);
+        for name in ["sum", "clamp"] {
+            let compiled = compiled.get(name).unwrap();
+            let listing = listing.get(name).unwrap();
+            assert_eq!(compiled.blocks().count(), listing.blocks().count());
+            for (compiled, listing) in compiled.blocks().zip(listing.blocks()) {
+                assert_eq!(compiled.len(), listing.len());
+                // The mnemonics only differ by operand size suffixes
+                for index in 0..compiled.len() {
+                    assert!(compiled.get(index).score(listing.get(index)) >= 2);
+                }
+                assert!(compiled.terminator().score(listing.terminator()) >= 2);
+            }
+        }
+    }
+
+    #[test]
+    fn aarch64_operands() {
+        let program = parse::<AArch64>("aarch64-sq.s");
+        let twice = rendered(&program, "twice");
+        assert_eq!(
+            twice,
+            [
+                (
+                    "0".to_string(),
+                    vec!["str x30, [sp, #-16]!".to_string(), "bl sq".to_string()]
+                ),
+                (
+                    "1".to_string(),
+                    vec![
+                        "add w0, w0, w0".to_string(),
+                        "ldr x30, [sp], #16".to_string(),
+                        "ret".to_string()
+                    ]
+                )
+            ]
+        );
+        let instruction = |text| super::parse_instruction::<AArch64>(text).unwrap();
+        let add = instruction("add w0, w0, w0");
+        assert_eq!(add.score(&instruction("add w1, w2, w3")), 4);
+        assert_eq!(add.score(&instruction("add w1, w2, #1")), 3);
+        assert_eq!(add.score(&instruction("adds w1, w2, w3")), 2);
+        assert_eq!(add.score(&instruction("mul w0, w0, w0")), 0);
+    }
+
+    #[test]
+    fn malformed_input() {
+        assert!(Assembly::<X86_64>::parse("testdata/does-not-exist.s", ()).is_err());
+        // Text that is not assembly, stray labels, and unbalanced operands must not panic
+        for text in [
+            "",
+            "\n\n\t.text\n",
+            "orphan:\nfoo:\n",
+            "0000000000000000 <sum>:\n   0:\n  garbage\n",
+            "f:\n\tmovl (%rax, %eax\n\tjne\n\tjmp .L9]]\n",
+            "Disassembly of section .text:\n<x>:\n",
+        ] {
+            let functions = super::parse_text::<X86_64>(text);
+            for function in functions.values() {
+                for block in function.blocks() {
+                    for index in 0..block.len() {
+                        block.get(index).render();
+                    }
+                }
+            }
+            super::parse_text::<AArch64>(text);
+        }
+        assert!(super::parse_instruction::<AArch64>("").is_none());
+    }
+
+    #[test]
+    fn aarch64_listing_targets() {
+        // GNU objdump listing of `pick`, where the conditional branches put the target after the tested register
+        let listing = "
+0000000000000000 <pick>:
+   0:\tb4000080 \tcbz\tx0, 10 <pick+0x10>
+   4:\t361800a1 \ttbz\tw1, #3, 18 <pick+0x18>
+   8:\t52800040 \tmov\tw0, #0x2                   \t// #2
+   c:\td65f03c0 \tret
+  10:\t2a1f03e0 \tmov\tw0, wzr
+  14:\td65f03c0 \tret
+  18:\t52800020 \tmov\tw0, #0x1                   \t// #1
+  1c:\td65f03c0 \tret
+";
+        let functions = super::parse_text::<AArch64>(listing);
+        let blocks: Vec<_> = functions["pick"]
+            .blocks()
+            .map(|block| block.name().into_owned())
+            .collect();
+        assert_eq!(blocks, ["0", "1", "2", "10", "18"]);
+    }
+
+    #[test]
+    fn intel_operands() {
+        use super::{Dialect, OperandKind};
+        for register in ["rax", "EAX", "r8d", "r15", "sil", "xmm0", "st(1)"] {
+            assert_eq!(X86_64::operand_kind(register), OperandKind::Register);
+        }
+        for immediate in ["0x28", "-8", "$0x28"] {
+            assert_eq!(X86_64::operand_kind(immediate), OperandKind::Immediate);
+        }
+        assert_eq!(
+            X86_64::operand_kind("QWORD PTR [rbp-8]"),
+            OperandKind::Memory
+        );
+        assert_eq!(X86_64::operand_kind("rax_table"), OperandKind::Symbol);
+        let instruction = |text| super::parse_instruction::<X86_64>(text).unwrap();
+        let mov = instruction("mov rax, rbx");
+        assert_eq!(mov.score(&instruction("mov rcx, r9")), 4);
+        assert!(mov.score(&instruction("mov rcx, 0x10")) < 4);
+    }
+}
@@ -0,0 +1,187 @@
+use super::OperandKind;
+use crate::InstructionClass;
+
+/// AT&T or Intel syntax x86-64 assembly
+pub struct X86_64;
+impl super::Dialect for X86_64 {
+    const COMMENTS: &'static [&'static str] = &["#"];
+    const PREFIXES: &'static [&'static str] = &[
+        "bnd", "data16", "lock", "notrack", "rep", "repe", "repne", "repnz", "repz",
+    ];
+    const GAP: i32 = 2;
+
+    fn is_flow_control(mnemonic: &str) -> bool {
+        let mnemonic = mnemonic.rsplit(' ').next().unwrap_or(mnemonic);
+        mnemonic.starts_with('j') || mnemonic.starts_with("ret") || mnemonic.starts_with("loop")
+    }
+
+    fn operand_kind(operand: &str) -> OperandKind {
+        if operand.starts_with('%') || is_register(operand) {
+            OperandKind::Register
+        } else if operand.starts_with('$') || is_integer(operand) {
+            OperandKind::Immediate
+        } else if operand.contains('(') || operand.contains('[') || operand.starts_with('*') {
+            OperandKind::Memory
+        } else {
+            OperandKind::Symbol
+        }
+    }
+
+    fn base_mnemonic(mnemonic: &str) -> &str {
+        // AT&T syntax puts the operand size on the mnemonic; that is removed only for common instructions where the suffix can't be confused with a real instruction name (e.g., `shl` is not `sh` + `l`)
+        for base in [
+            "add", "and", "cmp", "dec", "imul", "inc", "lea", "mov", "neg", "not", "or", "pop",
+            "push", "sar", "shl", "shr", "sub", "test", "xor",
+        ] {
+            if let Some(suffix) = mnemonic.strip_prefix(base) {
+                if matches!(suffix, "" | "b" | "w" | "l" | "q") {
+                    return base;
+                }
+            }
+        }
+        mnemonic
+    }
+
+    fn class(mnemonic: &str, _operands: &str) -> InstructionClass {
+        let mnemonic = mnemonic.rsplit(' ').next().unwrap_or(mnemonic);
+        let base = Self::base_mnemonic(mnemonic);
+        let starts_with_any = |prefixes: &[&str]| prefixes.iter().any(|p| base.starts_with(p));
+        if base.starts_with("call") {
+            InstructionClass::Call
+        } else if Self::is_flow_control(base) {
+            InstructionClass::Branch
+        } else if base.starts_with("cmov") {
+            InstructionClass::Logic
+        } else if base.starts_with("movz")
+            || base.starts_with("movsx")
+            || matches!(
+                base,
+                "movsbw" | "movsbl" | "movsbq" | "movswl" | "movswq" | "movslq"
+            )
+            || matches!(
+                base,
+                "cbw" | "cwde" | "cdqe" | "cwd" | "cdq" | "cqo" | "cltq" | "cqto"
+            )
+        {
+            // Sign and zero extension
+            InstructionClass::Arith
+        } else if starts_with_any(&["comis", "ucomis"]) {
+            InstructionClass::Compare
+        } else if base.starts_with('v')
+            || (base.starts_with('p')
+                && !matches!(base, "push" | "pop" | "pause" | "popcnt")
+                && !starts_with_any(&["prefetch", "pushf", "popf"]))
+            || base.ends_with("ps")
+            || base.ends_with("pd")
+        {
+            InstructionClass::Vector
+        } else if base.starts_with("cvt")
+            || base.starts_with('f')
+            || base.ends_with("ss")
+            || base.ends_with("sd")
+        {
+            InstructionClass::Float
+        } else if starts_with_any(&["cmpxchg", "xadd", "xchg", "mov", "lods", "stos"])
+            || matches!(base, "push" | "pop")
+        {
+            InstructionClass::Memory
+        } else if starts_with_any(&["cmp", "test", "set"]) {
+            InstructionClass::Compare
+        } else if matches!(base, "add" | "sub" | "inc" | "dec" | "neg" | "imul" | "lea")
+            || starts_with_any(&["adc", "sbb", "div", "idiv", "mul"])
+        {
+            InstructionClass::Arith
+        } else if matches!(base, "and" | "or" | "xor" | "not" | "shl" | "shr" | "sar")
+            || starts_with_any(&[
+                "andn", "bextr", "bs", "bt", "lzcnt", "popcnt", "rcl", "rcr", "rol", "ror", "sal",
+                "shld", "shrd", "tzcnt",
+            ])
+        {
+            InstructionClass::Logic
+        } else {
+            InstructionClass::Other
+        }
+    }
+}
+
+/// Check if an operand is a bare register name, as used in Intel syntax
+fn is_register(operand: &str) -> bool {
+    let name = operand.to_ascii_lowercase();
+    let numbered = |prefix: &str, limit: u32| {
+        name.strip_prefix(prefix)
+            .and_then(|number| number.parse::<u32>().ok())
+            .is_some_and(|number| number < limit)
+    };
+    matches!(
+        name.as_str(),
+        "al" | "ah"
+            | "ax"
+            | "eax"
+            | "rax"
+            | "bl"
+            | "bh"
+            | "bx"
+            | "ebx"
+            | "rbx"
+            | "cl"
+            | "ch"
+            | "cx"
+            | "ecx"
+            | "rcx"
+            | "dl"
+            | "dh"
+            | "dx"
+            | "edx"
+            | "rdx"
+            | "sil"
+            | "si"
+            | "esi"
+            | "rsi"
+            | "dil"
+            | "di"
+            | "edi"
+            | "rdi"
+            | "spl"
+            | "sp"
+            | "esp"
+            | "rsp"
+            | "bpl"
+            | "bp"
+            | "ebp"
+            | "rbp"
+            | "ip"
+            | "eip"
+            | "rip"
+            | "cs"
+            | "ds"
+            | "es"
+            | "fs"
+            | "gs"
+            | "ss"
+    ) || numbered("r", 16)
+        || ["b", "w", "d"].iter().any(|suffix| {
+            name.strip_suffix(suffix)
+                .and_then(|name| name.strip_prefix('r'))
+                .and_then(|number| number.parse::<u32>().ok())
+                .is_some_and(|number| (8..16).contains(&number))
+        })
+        || numbered("xmm", 32)
+        || numbered("ymm", 32)
+        || numbered("zmm", 32)
+        || numbered("k", 8)
+        || numbered("mm", 8)
+        || name == "st"
+        || name.starts_with("st(")
+}
+
+/// Check if an operand is a bare integer, in decimal or hexadecimal, as used for immediates in Intel syntax
+fn is_integer(operand: &str) -> bool {
+    let digits = operand.strip_prefix('-').unwrap_or(operand);
+    match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) => !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        None => !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()),
+    }
+}
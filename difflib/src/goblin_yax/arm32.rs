@@ -1,25 +1,44 @@
 use std::borrow::Cow;
 
-use yaxpeax_arm::armv7::Opcode;
+use super::YaxInstruction;
+use yaxpeax_arm::armv7::{Opcode, Operand};
 impl super::MachArch for yaxpeax_arm::armv7::ARMv7 {
     const CPU_TYPE: Option<u32> = Some(0x0000000c);
 }
 impl super::YaxInstruction for yaxpeax_arm::armv7::Instruction {
     const GAP: i32 = -1;
+    const NORMALIZATION: super::Normalization = super::Normalization {
+        pc_registers: &["pc"],
+        absolute_from: None,
+    };
 
     fn is_flow_control(&self) -> bool {
-        match self.opcode {
+        matches!(
+            self.opcode,
             Opcode::B
-            | Opcode::BLX
-            | Opcode::BX
-            | Opcode::BXJ
-            | Opcode::CBNZ
-            | Opcode::CBZ
-            | Opcode::BL
-            | Opcode::TBB
-            | Opcode::TBH => true,
-            _ => false,
-        }
+                | Opcode::BLX
+                | Opcode::BX
+                | Opcode::BXJ
+                | Opcode::CBNZ
+                | Opcode::CBZ
+                | Opcode::BL
+                | Opcode::TBB
+                | Opcode::TBH
+        )
+    }
+
+    fn relative_base(offset: usize, _length: usize) -> usize {
+        // The program counter reads two instructions ahead
+        offset + 8
+    }
+
+    fn relative_operand(&self) -> Option<i64> {
+        // Offsets are counted in instructions, which are half as long in Thumb
+        self.operands.iter().find_map(|operand| match operand {
+            Operand::BranchOffset(offset) => Some(*offset as i64 * 4),
+            Operand::BranchThumbOffset(offset) => Some(*offset as i64 * 2),
+            _ => None,
+        })
     }
 }
 impl crate::Instruction for yaxpeax_arm::armv7::Instruction {
@@ -36,4 +55,40 @@ impl crate::Instruction for yaxpeax_arm::armv7::Instruction {
     fn render<'a>(&self) -> Cow<'a, str> {
         Cow::Owned(self.to_string())
     }
+
+    fn class(&self) -> crate::InstructionClass {
+        let text = self.to_string();
+        let (mnemonic, _) = super::split_instruction(&text, &[]);
+        // Condition codes and the flag-setting suffix are ignored
+        let starts_with_any = |prefixes: &[&str]| prefixes.iter().any(|p| mnemonic.starts_with(p));
+        // A conditional call has a two letter condition after `bl`, while `ble`, `blt`, and `bls` are conditional branches
+        if mnemonic == "bl"
+            || mnemonic.starts_with("blx")
+            || (mnemonic.starts_with("bl") && mnemonic.len() == 4)
+        {
+            crate::InstructionClass::Call
+        } else if self.is_flow_control() {
+            crate::InstructionClass::Branch
+        } else if starts_with_any(&["ldr", "ldm", "str", "stm", "push", "pop", "mov", "swp"]) {
+            crate::InstructionClass::Memory
+        } else if starts_with_any(&["cmp", "cmn", "tst", "teq"]) {
+            crate::InstructionClass::Compare
+        } else if starts_with_any(&["vld", "vst", "vldr", "vstr"]) {
+            crate::InstructionClass::Memory
+        } else if mnemonic.starts_with('v') {
+            crate::InstructionClass::Float
+        } else if starts_with_any(&[
+            "and", "orr", "orn", "eor", "bic", "mvn", "lsl", "lsr", "asr", "ror", "rrx", "clz",
+            "rbit", "rev", "ubfx", "sbfx", "bfi", "bfc",
+        ]) {
+            crate::InstructionClass::Logic
+        } else if starts_with_any(&[
+            "add", "adc", "sub", "sbc", "rsb", "rsc", "mul", "mla", "mls", "umull", "umlal",
+            "smull", "smlal", "sdiv", "udiv", "uxt", "sxt", "qadd", "qsub",
+        ]) {
+            crate::InstructionClass::Arith
+        } else {
+            crate::InstructionClass::Other
+        }
+    }
 }
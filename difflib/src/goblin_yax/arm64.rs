@@ -1,26 +1,47 @@
 use std::borrow::Cow;
 
-use yaxpeax_arm::armv8::a64::Opcode;
+use crate::asm::aarch64::AArch64;
+use crate::asm::Dialect;
+use yaxpeax_arm::armv8::a64::{Opcode, Operand};
 impl super::MachArch for yaxpeax_arm::armv8::a64::ARMv8 {
     const CPU_TYPE: Option<u32> = Some(0x0100000c);
 }
 impl super::YaxInstruction for yaxpeax_arm::armv8::a64::Instruction {
     const GAP: i32 = -1;
+    const NORMALIZATION: super::Normalization = super::Normalization {
+        pc_registers: &[],
+        absolute_from: None,
+    };
 
     fn is_flow_control(&self) -> bool {
-        match self.opcode {
+        matches!(
+            self.opcode,
             Opcode::B
-            | Opcode::BCAX
-            | Opcode::BL
-            | Opcode::BLR
-            | Opcode::Bcc(_)
-            | Opcode::CBNZ
-            | Opcode::CBZ
-            | Opcode::RET
-            | Opcode::TBNZ
-            | Opcode::TBZ => true,
-            _ => false,
+                | Opcode::BCAX
+                | Opcode::BL
+                | Opcode::BLR
+                | Opcode::Bcc(_)
+                | Opcode::CBNZ
+                | Opcode::CBZ
+                | Opcode::RET
+                | Opcode::TBNZ
+                | Opcode::TBZ
+        )
+    }
+
+    fn relative_base(offset: usize, _length: usize) -> usize {
+        offset
+    }
+
+    fn relative_operand(&self) -> Option<i64> {
+        // The offset of `adrp` is to a page, not an address
+        if self.opcode == Opcode::ADRP {
+            return None;
         }
+        self.operands.iter().find_map(|operand| match operand {
+            Operand::PCOffset(offset) => Some(*offset),
+            _ => None,
+        })
     }
 }
 impl crate::Instruction for yaxpeax_arm::armv8::a64::Instruction {
@@ -37,4 +58,10 @@ impl crate::Instruction for yaxpeax_arm::armv8::a64::Instruction {
     fn render<'a>(&self) -> Cow<'a, str> {
         Cow::Owned(self.to_string())
     }
+
+    fn class(&self) -> crate::InstructionClass {
+        let text = self.to_string();
+        let (mnemonic, operands) = super::split_instruction(&text, &[]);
+        AArch64::class(mnemonic, operands)
+    }
 }
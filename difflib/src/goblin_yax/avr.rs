@@ -1,22 +1,34 @@
 use std::borrow::Cow;
 
-use yaxpeax_avr::Opcode;
+use super::YaxInstruction;
+use yaxpeax_avr::{Opcode, Operand};
 impl super::MachArch for yaxpeax_avr::AVR {
     const CPU_TYPE: Option<u32> = None;
 }
 impl super::YaxInstruction for yaxpeax_avr::Instruction {
     const GAP: i32 = -1;
+    const NORMALIZATION: super::Normalization = super::Normalization {
+        pc_registers: &[],
+        absolute_from: None,
+    };
 
     fn is_flow_control(&self) -> bool {
-        match self.opcode {
-            Opcode::EIJMP
-            | Opcode::IJMP
-            | Opcode::JMP
-            | Opcode::RET
-            | Opcode::RJMP
-            | Opcode::RETI => true,
-            _ => false,
-        }
+        matches!(
+            self.opcode,
+            Opcode::EIJMP | Opcode::IJMP | Opcode::JMP | Opcode::RET | Opcode::RJMP | Opcode::RETI
+        )
+    }
+
+    fn relative_base(offset: usize, length: usize) -> usize {
+        offset + length
+    }
+
+    fn relative_operand(&self) -> Option<i64> {
+        // Offsets are counted in words
+        self.operands.iter().find_map(|operand| match operand {
+            Operand::AddrPCRelative(offset) => Some(*offset as i64 * 2),
+            _ => None,
+        })
     }
 }
 impl crate::Instruction for yaxpeax_avr::Instruction {
@@ -33,4 +45,31 @@ impl crate::Instruction for yaxpeax_avr::Instruction {
     fn render<'a>(&self) -> Cow<'a, str> {
         Cow::Owned(self.to_string())
     }
+
+    fn class(&self) -> crate::InstructionClass {
+        let text = self.to_string();
+        let (mnemonic, _) = super::split_instruction(&text, &[]);
+        match mnemonic.to_ascii_lowercase().as_str() {
+            "call" | "rcall" | "icall" | "eicall" => crate::InstructionClass::Call,
+            "add" | "adc" | "adiw" | "sub" | "subi" | "sbc" | "sbci" | "sbiw" | "inc" | "dec"
+            | "neg" | "mul" | "muls" | "mulsu" | "fmul" | "fmuls" | "fmulsu" => {
+                crate::InstructionClass::Arith
+            }
+            "and" | "andi" | "or" | "ori" | "eor" | "com" | "lsl" | "lsr" | "asr" | "rol"
+            | "ror" | "swap" | "sbr" | "cbr" | "ser" | "clr" | "bst" | "bld" => {
+                crate::InstructionClass::Logic
+            }
+            "ld" | "ldd" | "lds" | "ldi" | "st" | "std" | "sts" | "lpm" | "elpm" | "spm"
+            | "push" | "pop" | "mov" | "movw" | "in" | "out" | "xch" | "las" | "lac" | "lat" => {
+                crate::InstructionClass::Memory
+            }
+            "cp" | "cpc" | "cpi" | "cpse" | "tst" | "sbrc" | "sbrs" | "sbic" | "sbis" => {
+                crate::InstructionClass::Compare
+            }
+            mnemonic if self.is_flow_control() || mnemonic.starts_with("br") => {
+                crate::InstructionClass::Branch
+            }
+            _ => crate::InstructionClass::Other,
+        }
+    }
 }
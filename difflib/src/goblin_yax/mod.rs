@@ -5,10 +5,14 @@ pub mod x86_32;
 pub mod x86_64;
 use goblin::mach::constants::S_ATTR_PURE_INSTRUCTIONS;
 use goblin::mach::constants::S_ATTR_SOME_INSTRUCTIONS;
+use goblin::mach::load_command::CommandVariant;
 use goblin::mach::symbols::NO_SECT;
+use goblin::mach::symbols::N_STAB;
 use goblin::Object;
 use num_traits::Zero;
+use std::borrow::Cow;
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::fmt::Display;
 use yaxpeax_arch::AddressBase;
 use yaxpeax_arch::Arch;
@@ -22,12 +26,59 @@ pub struct GoblinYax<A: yaxpeax_arch::Arch> {
 
 pub struct GoblinYaxFunction<A: yaxpeax_arch::Arch> {
     blocks: Vec<GoblinYaxBlock<A>>,
+    /// The archive member the function came from, if it was in an archive
+    member: Option<String>,
     name: String,
 }
 pub struct GoblinYaxBlock<A: yaxpeax_arch::Arch> {
     id: usize,
-    instructions: Vec<A::Instruction>,
-    terminator: Option<A::Instruction>,
+    instructions: Vec<GoblinYaxInstruction<A>>,
+    terminator: Option<GoblinYaxInstruction<A>>,
+}
+pub struct GoblinYaxInstruction<A: yaxpeax_arch::Arch> {
+    instruction: A::Instruction,
+    /// The source location from the debugging information, if any
+    location: Option<String>,
+    /// The symbol or block this instruction refers to, if any
+    target: Option<Target>,
+}
+#[derive(PartialEq, Eq)]
+enum Target {
+    /// A block in the same function, by its position
+    Block(usize),
+    /// A function or data symbol, from a relocation or the symbol table, possibly with an offset
+    Symbol(String),
+    /// An address that does not belong to any known block or symbol
+    Address,
+}
+/// Information from the container used to name the addresses in instructions
+#[derive(Default)]
+struct References {
+    /// The symbols referenced by relocations, keyed by the file offset of the bytes they patch
+    relocations: BTreeMap<usize, String>,
+    /// Data symbols, keyed by address, with their names and sizes
+    data: BTreeMap<u64, (String, u64)>,
+    /// The regions of executable code, as addresses, file offsets, and sizes, where functions that are called can be found
+    code: Vec<(u64, usize, usize)>,
+    /// The source locations from the debugging information, keyed by the address where they start; the end of a sequence of instructions has no location
+    lines: BTreeMap<u64, Option<String>>,
+}
+/// How operands that hold addresses are found in the instructions of an architecture
+struct Normalization {
+    /// Registers that hold the program counter, so memory operands based on them are relative (_e.g._, `rip`)
+    pc_registers: &'static [&'static str],
+    /// Immediates at least this large are taken to be absolute addresses; if `None`, immediates are never treated as addresses
+    absolute_from: Option<u64>,
+}
+/// An operand that holds an address
+#[derive(Clone, Copy)]
+enum AddressOperand {
+    /// A relative branch or address (_e.g._, `$+0x10`)
+    Relative(i64),
+    /// A memory operand relative to the program counter (_e.g._, `[rip + 0x10]`)
+    PcRelative(i64),
+    /// An immediate that is an absolute address
+    Absolute(u64),
 }
 pub enum GoblinYaxError<A: yaxpeax_arch::Arch> {
     Fat,
@@ -40,7 +91,7 @@ pub enum GoblinYaxError<A: yaxpeax_arch::Arch> {
 impl<A: yaxpeax_arch::Arch> Display for GoblinYaxError<A> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            GoblinYaxError::Fat => f.write_str("architecture is not present in FAT binary"),
+            GoblinYaxError::Fat => f.write_str("architecture is not present in binary"),
             GoblinYaxError::Goblin(g) => g.fmt(f),
             GoblinYaxError::Io(i) => i.fmt(f),
             GoblinYaxError::NoSym => f.write_str("symbol table is corrupt"),
@@ -52,13 +103,206 @@ impl<A: yaxpeax_arch::Arch> Display for GoblinYaxError<A> {
 
 trait YaxInstruction: crate::Instruction {
     const GAP: i32;
+    /// How operands that hold addresses are found, so they can be replaced by symbolic names
+    const NORMALIZATION: Normalization;
     fn is_flow_control(&self) -> bool;
+    /// The offset that relative operands (_e.g._, `$+0x10`) are measured from, given the offset and length of the instruction
+    fn relative_base(offset: usize, length: usize) -> usize;
+    /// The displacement, in bytes, of a relative branch or call, decoded from its operands
+    fn relative_operand(&self) -> Option<i64>;
+}
+
+/// Split a disassembled instruction into its mnemonic, including any prefixes (_e.g._, `lock`), and the text of its operands
+fn split_instruction<'a>(text: &'a str, prefixes: &[&str]) -> (&'a str, &'a str) {
+    let mut end = 0;
+    for word in text.split(' ') {
+        end += word.len();
+        if !prefixes.contains(&word) {
+            break;
+        }
+        end += 1;
+    }
+    let end = end.min(text.len());
+    (&text[..end], text[end..].trim())
+}
+
+/// Parse a hexadecimal number (_e.g._, `0x10`) at the start of some text, returning it and its length in the text
+fn parse_hex(text: &str) -> Option<(u64, usize)> {
+    let digits = text.strip_prefix("0x")?;
+    let length = digits
+        .find(|c: char| !c.is_ascii_hexdigit())
+        .unwrap_or(digits.len());
+    Some((u64::from_str_radix(&digits[..length], 16).ok()?, length + 2))
+}
+
+/// Find the first operand that holds an address in an instruction, along with where it is in the rendered text
+///
+/// Relative branches and calls are decoded from the operands, since the disassemblers don't render them consistently (_e.g._, `jz $+0x10` but `call -0x2a`); the displacement is always the last operand. Other addresses are found in the text.
+fn find_address<I: YaxInstruction>(
+    instruction: &I,
+    text: &str,
+) -> Option<(std::ops::Range<usize>, AddressOperand)> {
+    if let Some(displacement) = instruction.relative_operand() {
+        let start = text
+            .rfind(&[' ', ','][..])
+            .map(|start| start + 1)
+            .unwrap_or(0);
+        return Some((start..text.len(), AddressOperand::Relative(displacement)));
+    }
+    let normalization = &I::NORMALIZATION;
+    for register in normalization.pc_registers {
+        if let Some(start) = text.find(&format!("[{}", register)) {
+            // The displacement is everything from the register to the closing bracket (_e.g._, ` + 0x10` or `, #-0x10`)
+            let start = start + 1 + register.len();
+            let end = start + text[start..].find(']')?;
+            let displacement = text[start..end].trim_start_matches(&[' ', ',', '+', '#'][..]);
+            let (negative, displacement) = match displacement.strip_prefix('-') {
+                Some(displacement) => (true, displacement.trim_start()),
+                None => (false, displacement),
+            };
+            let value = if displacement.is_empty() {
+                0
+            } else {
+                parse_hex(displacement)?.0 as i64
+            };
+            return Some((
+                start..end,
+                AddressOperand::PcRelative(if negative { -value } else { value }),
+            ));
+        }
+    }
+    let minimum = normalization.absolute_from?;
+    text.match_indices("0x").find_map(|(start, _)| {
+        if text[..start].ends_with(|c: char| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+        let (value, length) = parse_hex(&text[start..])?;
+        if value >= minimum {
+            Some((start..(start + length), AddressOperand::Absolute(value)))
+        } else {
+            None
+        }
+    })
+}
+
+/// Find functions that are only known from being called
+///
+/// Every call to an address in the code that isn't the start of a known function is taken as a new function, which runs to the next known function or the end of the code, and the new functions are searched in turn. Calls patched by a relocation are to their symbol, not the address in the instruction.
+fn discover<A: yaxpeax_arch::Arch>(
+    buffer: &[u8],
+    references: &References,
+    functions: &mut Vec<(String, usize, usize, u64)>,
+) where
+    for<'a> U8Reader<'a>: Reader<<A as Arch>::Address, <A as Arch>::Word>,
+    A::Instruction: YaxInstruction,
+{
+    let code = &references.code;
+    if code.is_empty() {
+        return;
+    }
+    let mut known: BTreeSet<_> = functions
+        .iter()
+        .map(|(_, _, _, address)| *address)
+        .collect();
+    let mut index = 0;
+    while index < functions.len() {
+        let (_, start, end, address) = functions[index];
+        index += 1;
+        let decoder = A::Decoder::default();
+        let mut addr = A::Address::zero();
+        let mut calls = Vec::new();
+        while let Some(rest) = buffer
+            .get((start + addr.to_linear())..end)
+            .filter(|v| !v.is_empty())
+        {
+            let offset = addr.to_linear();
+            let mut reader = U8Reader::new(rest);
+            match decoder.decode(&mut reader) {
+                Ok(instruction) => {
+                    addr += instruction.len();
+                    let length = addr.to_linear() - offset;
+                    if crate::Instruction::class(&instruction) != crate::InstructionClass::Call
+                        || references
+                            .relocations
+                            .range((start + offset)..(start + offset + length))
+                            .next()
+                            .is_some()
+                    {
+                        continue;
+                    }
+                    if let Some(displacement) = instruction.relative_operand() {
+                        calls.push(
+                            (address as i64
+                                + A::Instruction::relative_base(offset, length) as i64
+                                + displacement) as u64,
+                        );
+                    }
+                }
+                // The whole function will fail to decode later, so there's no need to report it here
+                Err(_) => break,
+            }
+        }
+        for call in calls {
+            if !known.insert(call) {
+                continue;
+            }
+            if let Some(&(region, region_offset, size)) = code
+                .iter()
+                .find(|(region, _, size)| *region <= call && call - region < *size as u64)
+            {
+                let limit = known
+                    .range(call + 1..)
+                    .next()
+                    .copied()
+                    .unwrap_or(u64::MAX)
+                    .min(region + size as u64);
+                let start = region_offset + (call - region) as usize;
+                functions.push((unnamed(call), start, start + (limit - call) as usize, call));
+            }
+        }
+    }
 }
 
 trait MachArch {
     const CPU_TYPE: Option<u32>;
 }
 
+/// A slice of a universal (_fat_) MachO binary, selected by its CPU type and, optionally, subtype
+#[derive(Clone, Copy)]
+pub struct Slice {
+    cpu_type: u32,
+    cpu_subtype: Option<u32>,
+}
+impl Slice {
+    /// Find a slice by the name used by Apple's tools (_e.g._, `arm64e` or `x86_64h`)
+    pub fn named(name: &str) -> Option<Slice> {
+        let (cpu_type, cpu_subtype) = match name {
+            "i386" | "i686" | "x86" | "x86_32" | "x86-32" => (0x00000007, None),
+            "x86_64" | "x86-64" | "x64" => (0x01000007, Some(3)),
+            "x86_64h" => (0x01000007, Some(8)),
+            "arm" | "arm32" | "aarch32" => (0x0000000c, None),
+            "armv7" => (0x0000000c, Some(9)),
+            "armv7s" => (0x0000000c, Some(11)),
+            "armv7k" => (0x0000000c, Some(12)),
+            "arm64" | "aarch64" | "armv8" => (0x0100000c, Some(0)),
+            "arm64e" => (0x0100000c, Some(2)),
+            _ => return None,
+        };
+        Some(Slice {
+            cpu_type,
+            cpu_subtype,
+        })
+    }
+    fn matches(&self, cpu_type: u32, cpu_subtype: u32) -> bool {
+        // The top byte of the subtype holds capability bits (_e.g._, the pointer authentication ABI version for arm64e)
+        cpu_type == self.cpu_type
+            && self
+                .cpu_subtype
+                .map(|subtype| cpu_subtype & 0x00ffffff == subtype)
+                .unwrap_or(true)
+    }
+}
+
 impl<A: yaxpeax_arch::Arch + MachArch> crate::Program for GoblinYax<A>
 where
     for<'a> U8Reader<'a>: Reader<<A as Arch>::Address, <A as Arch>::Word>,
@@ -68,219 +312,307 @@ where
 
     type ParseError = GoblinYaxError<A>;
 
-    type ParseOptions = ();
+    /// The slice to use from a universal MachO binary; if not set, the first one for the instruction set is used
+    type ParseOptions = Option<Slice>;
 
     type Function = GoblinYaxFunction<A>;
 
     fn parse(
         file: impl AsRef<std::path::Path>,
-        _options: Self::ParseOptions,
+        options: Self::ParseOptions,
     ) -> Result<Self, Self::ParseError> {
         let buffer = std::fs::read(file).map_err(GoblinYaxError::Io)?;
+        Self::parse_bytes(&buffer, options)
+    }
+
+    fn parse_bytes(buffer: &[u8], options: Self::ParseOptions) -> Result<Self, Self::ParseError> {
         fn convert<'a, A: yaxpeax_arch::Arch + MachArch, S: AsRef<str>>(
             buffer: &[u8],
-            iter: impl Iterator<Item = (Option<S>, usize, usize)>,
+            references: &References,
+            iter: impl Iterator<Item = (Option<S>, usize, usize, u64)>,
         ) -> Result<BTreeMap<String, GoblinYaxFunction<A>>, GoblinYaxError<A>>
         where
             for<'r> U8Reader<'r>: Reader<<A as Arch>::Address, <A as Arch>::Word>,
             A::Instruction: YaxInstruction,
         {
-            iter.map(|(name, start, end)| {
-                let name = demangle(name.ok_or(GoblinYaxError::NoSym)?.as_ref());
-                let decoder = A::Decoder::default();
-                let mut addr = A::Address::zero();
-                let mut blocks = Vec::new();
-                let mut instructions = Vec::new();
-                while let Some(rest) = buffer
-                    .get((start + addr.to_linear())..end)
-                    .filter(|v| !v.is_empty())
-                {
-                    let mut reader = U8Reader::new(rest);
-                    match decoder.decode(&mut reader) {
-                        Ok(inst) => {
-                            addr += inst.len();
-                            let new_block = inst.is_flow_control();
-                            if new_block {
-                                let id = blocks.len();
-                                blocks.push(GoblinYaxBlock {
-                                    id,
-                                    instructions,
-                                    terminator: Some(inst),
-                                });
-                                instructions = Vec::new();
-                            } else {
-                                instructions.push(inst);
+            let mut functions = iter
+                .map(|(name, start, end, address)| {
+                    Ok((
+                        crate::demangle(name.ok_or(GoblinYaxError::NoSym)?.as_ref()),
+                        start,
+                        end,
+                        address,
+                    ))
+                })
+                .collect::<Result<Vec<_>, GoblinYaxError<A>>>()?;
+            // The same function can be in more than one symbol table, but distinct functions can also share a name (_e.g._, static functions from different source files), so later ones are numbered
+            let mut addresses_by_name: BTreeMap<String, Vec<u64>> = BTreeMap::new();
+            functions.retain_mut(|(name, _, _, address)| {
+                let addresses = addresses_by_name.entry(name.clone()).or_default();
+                if addresses.contains(address) {
+                    return false;
+                }
+                addresses.push(*address);
+                if addresses.len() > 1 {
+                    *name = format!("{}#{}", name, addresses.len());
+                }
+                true
+            });
+            discover::<A>(buffer, references, &mut functions);
+            let symbols: BTreeMap<_, _> = functions
+                .iter()
+                .map(|(name, start, end, address)| {
+                    (*address, (name.as_str(), (end - start) as u64))
+                })
+                .chain(
+                    references
+                        .data
+                        .iter()
+                        .map(|(address, (name, size))| (*address, (name.as_str(), *size))),
+                )
+                .collect();
+            functions
+                .iter()
+                .map(|(name, start, end, address)| {
+                    let (start, end, address) = (*start, *end, *address);
+                    let decoder = A::Decoder::default();
+                    let mut addr = A::Address::zero();
+                    let mut decoded = Vec::new();
+                    while let Some(rest) = buffer
+                        .get((start + addr.to_linear())..end)
+                        .filter(|v| !v.is_empty())
+                    {
+                        let offset = addr.to_linear();
+                        let mut reader = U8Reader::new(rest);
+                        match decoder.decode(&mut reader) {
+                            Ok(instruction) => {
+                                addr += instruction.len();
+                                let length = addr.to_linear() - offset;
+                                let relocation = references
+                                    .relocations
+                                    .range((start + offset)..(start + offset + length))
+                                    .next()
+                                    .map(|(_, target)| target.clone());
+                                // Address operands are resolved to an address, with whether they are relative branches
+                                let destination = find_address(
+                                    &instruction,
+                                    &crate::Instruction::render(&instruction),
+                                )
+                                .map(
+                                    |(_, operand)| match operand {
+                                        AddressOperand::Relative(displacement) => (
+                                            (address as i64
+                                                + A::Instruction::relative_base(offset, length)
+                                                    as i64
+                                                + displacement)
+                                                as u64,
+                                            true,
+                                        ),
+                                        AddressOperand::PcRelative(displacement) => (
+                                            (address as i64
+                                                + A::Instruction::relative_base(offset, length)
+                                                    as i64
+                                                + displacement)
+                                                as u64,
+                                            false,
+                                        ),
+                                        AddressOperand::Absolute(value) => (value, false),
+                                    },
+                                );
+                                decoded.push((offset, instruction, relocation, destination));
+                            }
+                            Err(e) => {
+                                return Err(GoblinYaxError::Yax(e));
                             }
                         }
-                        Err(e) => {
-                            return Err(GoblinYaxError::Yax(e));
+                    }
+                    // Blocks start after flow control and at anything that is branched to, so that branch targets can be named
+                    let leaders: BTreeSet<_> = decoded
+                        .iter()
+                        .filter(|(_, instruction, _, _)| {
+                            crate::Instruction::class(instruction) != crate::InstructionClass::Call
+                        })
+                        .filter_map(|(_, _, _, destination)| match destination {
+                            Some((destination, true)) => Some(*destination),
+                            _ => None,
+                        })
+                        .collect();
+                    let mut block_starts = BTreeMap::new();
+                    let mut new_block = true;
+                    for (offset, instruction, _, _) in &decoded {
+                        let offset = address + *offset as u64;
+                        if new_block || leaders.contains(&offset) {
+                            let id = block_starts.len();
+                            block_starts.insert(offset, id);
                         }
+                        new_block = instruction.is_flow_control();
                     }
-                }
-                if !instructions.is_empty() {
-                    // This means a chunk of assembly with no terminal flow control...
-                    let id = blocks.len();
-                    blocks.push(GoblinYaxBlock {
-                        id,
-                        instructions,
-                        terminator: None,
-                    });
-                }
-                Ok((name.clone(), GoblinYaxFunction::<A> { blocks, name }))
-            })
-            .collect()
+                    let mut blocks = Vec::new();
+                    let mut instructions = Vec::new();
+                    for (offset, instruction, relocation, destination) in decoded {
+                        if !instructions.is_empty()
+                            && block_starts.contains_key(&(address + offset as u64))
+                        {
+                            // This block falls through into a block that is branched to
+                            let id = blocks.len();
+                            blocks.push(GoblinYaxBlock {
+                                id,
+                                instructions,
+                                terminator: None,
+                            });
+                            instructions = Vec::new();
+                        }
+                        let target = relocation.map(Target::Symbol).or_else(|| {
+                            let (destination, _) = destination?;
+                            Some(match block_starts.get(&destination) {
+                                Some(&id) => Target::Block(id),
+                                None => match symbols.range(..=destination).next_back() {
+                                    Some((&symbol, &(name, _))) if symbol == destination => {
+                                        Target::Symbol(name.to_string())
+                                    }
+                                    Some((&symbol, &(name, size)))
+                                        if destination - symbol < size =>
+                                    {
+                                        Target::Symbol(format!(
+                                            "{}+{:#x}",
+                                            name,
+                                            destination - symbol
+                                        ))
+                                    }
+                                    _ => Target::Address,
+                                },
+                            })
+                        });
+                        let location = references
+                            .lines
+                            .range(..=(address + offset as u64))
+                            .next_back()
+                            .and_then(|(_, location)| location.clone());
+                        let inst = GoblinYaxInstruction::<A> {
+                            instruction,
+                            location,
+                            target,
+                        };
+                        let new_block = YaxInstruction::is_flow_control(&inst.instruction);
+                        if new_block {
+                            let id = blocks.len();
+                            blocks.push(GoblinYaxBlock {
+                                id,
+                                instructions,
+                                terminator: Some(inst),
+                            });
+                            instructions = Vec::new();
+                        } else {
+                            instructions.push(inst);
+                        }
+                    }
+                    if !instructions.is_empty() {
+                        // This means a chunk of assembly with no terminal flow control...
+                        let id = blocks.len();
+                        blocks.push(GoblinYaxBlock {
+                            id,
+                            instructions,
+                            terminator: None,
+                        });
+                    }
+                    Ok((
+                        name.clone(),
+                        GoblinYaxFunction::<A> {
+                            blocks,
+                            member: None,
+                            name: name.clone(),
+                        },
+                    ))
+                })
+                .collect()
         }
         fn extract<A: yaxpeax_arch::Arch + MachArch>(
             buffer: &[u8],
+            slice: Option<Slice>,
         ) -> Result<BTreeMap<String, GoblinYaxFunction<A>>, GoblinYaxError<A>>
         where
             for<'a> U8Reader<'a>: Reader<<A as Arch>::Address, <A as Arch>::Word>,
             A::Instruction: YaxInstruction,
         {
             Ok(
-                match Object::parse(&buffer).map_err(GoblinYaxError::Goblin)? {
+                match Object::parse(buffer).map_err(GoblinYaxError::Goblin)? {
                     Object::Elf(elf) => convert(
                         buffer,
-                        elf.dynsyms
-                            .iter()
-                            .map(|sym| (elf.dynstrtab.get_at(sym.st_name), sym))
-                            .chain(
-                                elf.syms
-                                    .iter()
-                                    .map(|sym| (elf.strtab.get_at(sym.st_name), sym)),
-                            )
-                            .filter(|(_, sym)| sym.is_function() && sym.st_size > 0)
-                            .map(|(name, sym)| {
-                                let section = &elf.section_headers[sym.st_shndx];
-                                let start =
-                                    (section.sh_offset + sym.st_value - section.sh_addr) as usize;
-                                (name, start, (start + sym.st_size as usize))
-                            }),
+                        &elf_references(&elf, buffer),
+                        elf_functions(&elf, buffer).into_iter(),
                     )?,
                     Object::PE(pe) => convert(
                         buffer,
-                        pe.exports.iter().filter_map(|export| {
-                            match (&export.name, export.offset) {
-                                (Some(name), Some(start)) => {
-                                    // PE doesn't include symbol lengths, so we try to find the next symbol and if we can't, we just assume it takes up the remainder of the section.
-                                    let previous_symbol = pe
-                                        .exports
-                                        .iter()
-                                        .flat_map(|other| other.offset.iter().copied())
-                                        .filter(|&other| other > start)
-                                        .min();
-                                    match previous_symbol {
-                                        Some(end) => Some((Some(name), start, end)),
-                                        None => {
-                                            eprintln!("Can't determine the size of {}.", name);
-                                            None
-                                        }
-                                    }
-                                }
-                                _ => None,
-                            }
-                        }),
+                        &References::default(),
+                        pe_functions(&pe, buffer).into_iter(),
                     )?,
                     Object::Mach(mach) => {
-                        let mach = match mach {
-                            goblin::mach::Mach::Fat(fat) => match A::CPU_TYPE {
-                                Some(cpu_type) => {
-                                    let arch = fat
-                                        .find_cputype(cpu_type)
-                                        .map_err(GoblinYaxError::Goblin)?
-                                        .ok_or(GoblinYaxError::Fat)?;
-                                    goblin::mach::MachO::parse(&buffer, arch.offset as usize)
-                                        .map_err(GoblinYaxError::Goblin)?
+                        let (mach, base) = match mach {
+                            goblin::mach::Mach::Fat(fat) => {
+                                let slice = slice
+                                    .or_else(|| {
+                                        A::CPU_TYPE.map(|cpu_type| Slice {
+                                            cpu_type,
+                                            cpu_subtype: None,
+                                        })
+                                    })
+                                    .ok_or(GoblinYaxError::Fat)?;
+                                let mut selected = None;
+                                for arch in fat.iter_arches() {
+                                    let arch = arch.map_err(GoblinYaxError::Goblin)?;
+                                    if slice.matches(arch.cputype, arch.cpusubtype) {
+                                        selected = Some(arch);
+                                        break;
+                                    }
                                 }
-                                None => {
-                                    return Err(GoblinYaxError::Fat);
+                                let arch = selected.ok_or(GoblinYaxError::Fat)?;
+                                // Offsets within a slice (_e.g._, of the symbol table) are from the start of the slice, so it is parsed on its own
+                                (
+                                    goblin::mach::MachO::parse(arch.slice(buffer), 0)
+                                        .map_err(GoblinYaxError::Goblin)?,
+                                    arch.offset as usize,
+                                )
+                            }
+                            goblin::mach::Mach::Binary(bin) => {
+                                if let Some(slice) = slice {
+                                    if !slice.matches(bin.header.cputype, bin.header.cpusubtype) {
+                                        return Err(GoblinYaxError::Fat);
+                                    }
                                 }
-                            },
-                            goblin::mach::Mach::Binary(bin) => bin,
+                                (bin, 0)
+                            }
+                        };
+                        let endian = if mach.little_endian {
+                            gimli::RunTimeEndian::Little
+                        } else {
+                            gimli::RunTimeEndian::Big
                         };
                         convert(
                             buffer,
-                            mach.symbols()
-                                .filter_map(|sym| match sym {
-                                    Err(_) => None,
-                                    Ok((name, list)) => {
-                                        if list.n_sect == NO_SECT.into() {
-                                            None
-                                        } else {
-                                            let mut section = list.n_sect - 1;
-                                            let mut fileoff = 0;
-                                            for segment in &mach.segments {
-                                                if section < segment.nsects as usize {
-                                                    let sections = segment.sections().ok()?;
-                                                    let (section, _) = &sections[section];
-                                                    if section.flags & S_ATTR_PURE_INSTRUCTIONS == 0
-                                                        && section.flags & S_ATTR_SOME_INSTRUCTIONS
-                                                            == 0
-                                                    {
-                                                        return None;
-                                                    }
-                                                    fileoff = segment.fileoff;
-                                                    break;
-                                                } else {
-                                                    section -= segment.nsects as usize;
-                                                }
-                                            }
-                                            let start = (fileoff + list.n_value) as usize;
-                                            // MachO doesn't include symbol lengths, so we try to find the next symbol and if we can't, we just assume it takes up the remainder of the section.
-                                            let previous_symbol = mach
-                                                .symbols()
-                                                .filter_map(|other| match other {
-                                                    Ok((_, other)) => {
-                                                        if other.n_sect == list.n_sect
-                                                            && other.n_value > list.n_value
-                                                        {
-                                                            Some(other.n_value)
-                                                        } else {
-                                                            None
-                                                        }
-                                                    }
-                                                    Err(_) => None,
-                                                })
-                                                .min()
-                                                .map(|off| fileoff + off);
-                                            match previous_symbol {
-                                                Some(end) => Some((
-                                                    Some(name.to_owned()),
-                                                    start,
-                                                    end as usize,
-                                                )),
-                                                None => {
-                                                    eprintln!(
-                                                        "Can't determine the size of {}.",
-                                                        name
-                                                    );
-                                                    None
-                                                }
-                                            }
-                                        }
-                                    }
-                                })
-                                .chain(
-                                    mach.exports()
-                                        .map_err(GoblinYaxError::Goblin)?
-                                        .into_iter()
-                                        .map(|export| {
-                                            (
-                                                Some(export.name),
-                                                export.offset as usize,
-                                                (export.offset as usize + export.size),
-                                            )
-                                        }),
-                                ),
+                            &References {
+                                lines: dwarf_lines(endian, |name| {
+                                    mach_section_data(&mach, buffer, base, name)
+                                }),
+                                ..Default::default()
+                            },
+                            mach_functions(&mach, buffer, base)
+                                .map_err(GoblinYaxError::Goblin)?
+                                .into_iter(),
                         )?
                     }
                     Object::Archive(ar) => {
+                        // Members can define functions with the same name (_e.g._, static functions), so functions are qualified by the member they come from
                         let mut result = BTreeMap::new();
-                        for (_, member, _) in ar.summarize().into_iter() {
-                            result.extend(extract(
+                        for (member_name, member, _) in ar.summarize().into_iter() {
+                            for (name, mut function) in extract(
                                 &buffer[member.offset as usize
                                     ..(member.offset as usize + member.size())],
-                            )?);
+                                slice,
+                            )? {
+                                function.name = format!("{}:{}", member_name, name);
+                                function.member = Some(member_name.to_string());
+                                result.insert(function.name.clone(), function);
+                            }
                         }
                         result
                     }
@@ -292,18 +624,69 @@ where
         }
 
         Ok(GoblinYax {
-            funcs: extract(&buffer)?,
+            funcs: extract(buffer, options)?,
         })
     }
 
+    fn select_architecture(
+        _options: Self::ParseOptions,
+        architecture: &str,
+    ) -> Option<Self::ParseOptions> {
+        // Only slices that can be decoded by this instruction set can be selected
+        Slice::named(architecture)
+            .filter(|slice| Some(slice.cpu_type) == A::CPU_TYPE)
+            .map(Some)
+    }
+
     fn get(&self, name: &str) -> Option<&Self::Function> {
-        self.funcs.get(name)
+        if let Some(function) = self.funcs.get(name) {
+            return Some(function);
+        }
+        // Functions in archives can also be found without the member name, as long as only one member has them
+        let mut candidates = self
+            .funcs
+            .values()
+            .filter(|function| function.unqualified_name() == name);
+        let function = candidates.next()?;
+        if candidates.next().is_none() {
+            Some(function)
+        } else {
+            None
+        }
+    }
+
+    fn candidates(&self, name: &str) -> Vec<String> {
+        let candidates: Vec<_> = self
+            .funcs
+            .values()
+            .filter(|function| function.unqualified_name() == name)
+            .map(|function| function.name.clone())
+            .collect();
+        if candidates.len() > 1 {
+            candidates
+        } else {
+            Vec::new()
+        }
     }
 
     fn functions<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Self::Function> + 'a> {
         Box::new(self.funcs.values())
     }
 }
+impl<A: yaxpeax_arch::Arch> GoblinYaxFunction<A> {
+    /// The archive member the function came from, if it was in an archive
+    pub fn member(&self) -> Option<&str> {
+        self.member.as_deref()
+    }
+    /// The name of the function without the archive member
+    pub fn unqualified_name(&self) -> &str {
+        self.member
+            .as_ref()
+            .and_then(|member| self.name.strip_prefix(member.as_str()))
+            .and_then(|name| name.strip_prefix(':'))
+            .unwrap_or(&self.name)
+    }
+}
 impl<A: yaxpeax_arch::Arch> crate::Function for GoblinYaxFunction<A>
 where
     A::Instruction: YaxInstruction,
@@ -322,9 +705,9 @@ impl<A: yaxpeax_arch::Arch> crate::BasicBlock for GoblinYaxBlock<A>
 where
     A::Instruction: YaxInstruction,
 {
-    type Instruction = A::Instruction;
+    type Instruction = GoblinYaxInstruction<A>;
 
-    type Terminator = Option<A::Instruction>;
+    type Terminator = Option<GoblinYaxInstruction<A>>;
 
     fn get(&self, index: usize) -> &Self::Instruction {
         &self.instructions[index]
@@ -342,13 +725,1001 @@ where
         &self.terminator
     }
 }
+impl<A: yaxpeax_arch::Arch> crate::Instruction for GoblinYaxInstruction<A>
+where
+    A::Instruction: YaxInstruction,
+{
+    const EQUIVALENT: i32 = A::Instruction::EQUIVALENT;
+
+    fn score(&self, other: &Self) -> i32 {
+        let score = self.instruction.score(&other.instruction);
+        match (&self.target, &other.target) {
+            // Block numbers shift whenever a block is added and unknown addresses shift whenever anything moves, so they are not compared
+            (Some(Target::Block(_)), Some(Target::Block(_)))
+            | (Some(Target::Address), Some(Target::Address)) => score,
+            (left, right) if score > 0 && left != right => score - 1,
+            _ => score,
+        }
+    }
+
+    fn render<'a>(&self) -> std::borrow::Cow<'a, str> {
+        let name = match &self.target {
+            Some(Target::Block(id)) => format!("block {}", id),
+            Some(Target::Symbol(name)) => name.clone(),
+            Some(Target::Address) => "<addr>".to_string(),
+            None => return self.instruction.render(),
+        };
+        let text = self.instruction.render();
+        std::borrow::Cow::Owned(match find_address(&self.instruction, &text) {
+            Some((range, AddressOperand::PcRelative(_))) => {
+                format!("{} + {}{}", &text[..range.start], name, &text[range.end..])
+            }
+            Some((range, _)) => {
+                format!("{}{}{}", &text[..range.start], name, &text[range.end..])
+            }
+            // A relocation can patch an operand that doesn't look like an address; in object files, it is zero until linked
+            None => match text.strip_suffix("0x0") {
+                Some(rest) if rest.ends_with(&[' ', ',', '#'][..]) => format!("{}{}", rest, name),
+                _ => format!("{} <{}>", text, name),
+            },
+        })
+    }
+
+    fn class(&self) -> crate::InstructionClass {
+        self.instruction.class()
+    }
+
+    fn location(&self) -> Option<std::borrow::Cow<'_, str>> {
+        self.location.as_deref().map(std::borrow::Cow::Borrowed)
+    }
+}
+
+/// The address of a symbol in an ELF file
+///
+/// In object files, symbol values are relative to their section, so the file offset is used instead to keep symbols in different sections apart.
+fn elf_address(elf: &goblin::elf::Elf, sym: &goblin::elf::Sym) -> u64 {
+    match elf.section_headers.get(sym.st_shndx) {
+        Some(section) if elf.header.e_type == goblin::elf::header::ET_REL => {
+            section.sh_offset + sym.st_value
+        }
+        _ => sym.st_value,
+    }
+}
 
-fn demangle(symbol: &str) -> String {
-    if let Ok(name) = rustc_demangle::try_demangle(symbol) {
-        name.to_string()
-    } else if let Ok(name) = cpp_demangle::Symbol::new(symbol) {
-        name.to_string()
+/// Collect the relocations that apply to sections in an ELF file and the data symbols
+///
+/// In object files, every call and reference to global data is zero until linked, so the relocation is the only way to know the target.
+fn elf_references(elf: &goblin::elf::Elf, buffer: &[u8]) -> References {
+    let data = elf
+        .dynsyms
+        .iter()
+        .map(|sym| (elf.dynstrtab.get_at(sym.st_name), sym))
+        .chain(
+            elf.syms
+                .iter()
+                .map(|sym| (elf.strtab.get_at(sym.st_name), sym)),
+        )
+        .filter(|(_, sym)| sym.st_type() == goblin::elf::sym::STT_OBJECT && sym.st_size > 0)
+        .filter_map(|(name, sym)| {
+            Some((
+                elf_address(elf, &sym),
+                (crate::demangle(name?), sym.st_size),
+            ))
+        })
+        .collect();
+    let code = elf
+        .section_headers
+        .iter()
+        .filter(|section| {
+            section.is_executable()
+                && section.sh_type != goblin::elf::section_header::SHT_NOBITS
+                // Calls into the PLT are calls to other libraries
+                && !elf
+                    .shdr_strtab
+                    .get_at(section.sh_name)
+                    .map(|name| name.starts_with(".plt"))
+                    .unwrap_or(false)
+        })
+        .map(|section| {
+            let address = if elf.header.e_type == goblin::elf::header::ET_REL {
+                section.sh_offset
+            } else {
+                section.sh_addr
+            };
+            (
+                address,
+                section.sh_offset as usize,
+                section.sh_size as usize,
+            )
+        })
+        .collect();
+    let mut relocations = BTreeMap::new();
+    for (index, relocs) in &elf.shdr_relocs {
+        let header = match elf.section_headers.get(*index) {
+            Some(header) => header,
+            None => continue,
+        };
+        // Dynamic relocations (`.rela.dyn`) are not tied to a section
+        let section = match elf.section_headers.get(header.sh_info as usize) {
+            Some(section) if header.sh_info != 0 => section,
+            _ => continue,
+        };
+        let dynamic = elf
+            .section_headers
+            .get(header.sh_link as usize)
+            .map(|symbols| symbols.sh_type == goblin::elf::section_header::SHT_DYNSYM)
+            .unwrap_or(false);
+        for reloc in relocs.iter() {
+            let (symbol, strtab) = if dynamic {
+                (elf.dynsyms.get(reloc.r_sym), &elf.dynstrtab)
+            } else {
+                (elf.syms.get(reloc.r_sym), &elf.strtab)
+            };
+            let name = match symbol {
+                Some(symbol) if reloc.r_sym != 0 => {
+                    if symbol.st_type() == goblin::elf::sym::STT_SECTION {
+                        // References to local data are made relative to the section, so the addend is what identifies the target
+                        let name = elf
+                            .section_headers
+                            .get(symbol.st_shndx)
+                            .and_then(|target| elf.shdr_strtab.get_at(target.sh_name))
+                            .unwrap_or("?");
+                        match reloc.r_addend {
+                            Some(addend) if addend < 0 => format!("{}-{:#x}", name, -addend),
+                            Some(addend) if addend > 0 => format!("{}+{:#x}", name, addend),
+                            _ => name.to_string(),
+                        }
+                    } else {
+                        match strtab.get_at(symbol.st_name) {
+                            Some(name) => crate::demangle(name),
+                            None => continue,
+                        }
+                    }
+                }
+                _ => continue,
+            };
+            // In object files, offsets are relative to the section; otherwise, they are virtual addresses
+            let offset = if elf.header.e_type == goblin::elf::header::ET_REL {
+                reloc.r_offset
+            } else {
+                reloc.r_offset.wrapping_sub(section.sh_addr)
+            };
+            relocations.insert((section.sh_offset + offset) as usize, name);
+        }
+    }
+    let endian = if elf.little_endian {
+        gimli::RunTimeEndian::Little
     } else {
-        symbol.to_string()
+        gimli::RunTimeEndian::Big
+    };
+    References {
+        relocations,
+        data,
+        code,
+        lines: dwarf_lines(endian, |name| elf_section_data(elf, buffer, name)),
+    }
+}
+
+/// Get the contents of a section in an ELF file by name
+///
+/// In object files, the relocations for the section are applied, since debugging information refers to code and strings through relocations. Compressed sections are not supported.
+fn elf_section_data<'a>(
+    elf: &goblin::elf::Elf,
+    buffer: &'a [u8],
+    name: &str,
+) -> Option<Cow<'a, [u8]>> {
+    let (index, section) = elf
+        .section_headers
+        .iter()
+        .enumerate()
+        .find(|(_, section)| elf.shdr_strtab.get_at(section.sh_name) == Some(name))?;
+    if section.sh_flags & goblin::elf::section_header::SHF_COMPRESSED as u64 != 0 {
+        return None;
+    }
+    let mut data = Cow::Borrowed(
+        buffer.get(section.sh_offset as usize..(section.sh_offset + section.sh_size) as usize)?,
+    );
+    if elf.header.e_type != goblin::elf::header::ET_REL {
+        return Some(data);
+    }
+    for (relocs_index, relocs) in &elf.shdr_relocs {
+        if elf
+            .section_headers
+            .get(*relocs_index)
+            .map(|header| header.sh_info as usize != index)
+            .unwrap_or(true)
+        {
+            continue;
+        }
+        let data = data.to_mut();
+        for reloc in relocs.iter() {
+            let symbol = match elf.syms.get(reloc.r_sym) {
+                Some(symbol) => symbol,
+                None => continue,
+            };
+            // References to code are addresses, which use the same addresses as the functions; references to other debugging sections are 32-bit offsets into them
+            let (value, width) = if elf
+                .section_headers
+                .get(symbol.st_shndx)
+                .map(|section| section.is_executable())
+                .unwrap_or(false)
+            {
+                (elf_address(elf, &symbol), if elf.is_64 { 8 } else { 4 })
+            } else if symbol.st_type() == goblin::elf::sym::STT_SECTION {
+                (0, 4)
+            } else {
+                (symbol.st_value, 4)
+            };
+            let field = match data.get_mut(reloc.r_offset as usize..reloc.r_offset as usize + width)
+            {
+                Some(field) => field,
+                None => continue,
+            };
+            // Without an explicit addend, the addend is the value already in place
+            let addend = reloc
+                .r_addend
+                .map(|addend| addend as u64)
+                .unwrap_or_else(|| {
+                    field
+                        .iter()
+                        .enumerate()
+                        .fold(0, |value, (position, &byte)| {
+                            let shift = if elf.little_endian {
+                                position
+                            } else {
+                                width - 1 - position
+                            } * 8;
+                            value | (byte as u64) << shift
+                        })
+                });
+            let value = value.wrapping_add(addend);
+            for (position, byte) in field.iter_mut().enumerate() {
+                let shift = if elf.little_endian {
+                    position
+                } else {
+                    width - 1 - position
+                } * 8;
+                *byte = (value >> shift) as u8;
+            }
+        }
+    }
+    Some(data)
+}
+
+/// Get the contents of a section in a MachO file by its ELF-style name (_e.g._, `.debug_line`)
+///
+/// MachO section names start with `__` instead of `.` and are limited to 16 characters.
+fn mach_section_data<'a>(
+    mach: &goblin::mach::MachO,
+    buffer: &'a [u8],
+    base: usize,
+    name: &str,
+) -> Option<Cow<'a, [u8]>> {
+    let name = format!("__{}", name.strip_prefix('.')?);
+    let name = &name[..name.len().min(16)];
+    for segment in &mach.segments {
+        for (section, _) in segment.sections().ok()? {
+            if section.name().ok() == Some(name) {
+                let start = base + section.offset as usize;
+                return buffer
+                    .get(start..start + section.size as usize)
+                    .map(Cow::Borrowed);
+            }
+        }
+    }
+    None
+}
+
+/// Read the line tables from DWARF debugging information
+///
+/// The sections are loaded by their ELF names (_e.g._, `.debug_line`) and the result maps the address where each row of the table starts to its source location. The end of each sequence is recorded without a location, so that code after it is not attributed to the last row.
+fn dwarf_lines<'a>(
+    endian: gimli::RunTimeEndian,
+    load: impl Fn(&str) -> Option<Cow<'a, [u8]>>,
+) -> BTreeMap<u64, Option<String>> {
+    use gimli::SectionId;
+    let mut lines = BTreeMap::new();
+    let sections: Vec<_> = [
+        SectionId::DebugAbbrev,
+        SectionId::DebugAddr,
+        SectionId::DebugInfo,
+        SectionId::DebugLine,
+        SectionId::DebugLineStr,
+        SectionId::DebugStr,
+        SectionId::DebugStrOffsets,
+    ]
+    .into_iter()
+    .filter_map(|id| Some((id, load(id.name())?)))
+    .collect();
+    if !sections.iter().any(|(id, _)| *id == SectionId::DebugLine) {
+        return lines;
+    }
+    let dwarf = match gimli::Dwarf::load(|id| -> Result<_, gimli::Error> {
+        let data = sections
+            .iter()
+            .find(|(other, _)| *other == id)
+            .map(|(_, data)| &data[..])
+            .unwrap_or(&[]);
+        Ok(gimli::EndianSlice::new(data, endian))
+    }) {
+        Ok(dwarf) => dwarf,
+        Err(_) => return lines,
+    };
+    let mut units = dwarf.units();
+    while let Ok(Some(header)) = units.next() {
+        let unit = match dwarf.unit(header) {
+            Ok(unit) => unit,
+            Err(_) => continue,
+        };
+        let program = match unit.line_program.clone() {
+            Some(program) => program,
+            None => continue,
+        };
+        let mut rows = program.rows();
+        while let Ok(Some((header, row))) = rows.next_row() {
+            if row.end_sequence() {
+                lines.entry(row.address()).or_insert(None);
+                continue;
+            }
+            let file = row
+                .file(header)
+                .and_then(|file| dwarf.attr_string(&unit, file.path_name()).ok());
+            if let Some(file) = file {
+                lines.insert(
+                    row.address(),
+                    Some(format!(
+                        "{}:{}",
+                        file.to_string_lossy(),
+                        row.line().map(|line| line.get()).unwrap_or(0)
+                    )),
+                );
+            }
+        }
+    }
+    lines
+}
+
+/// Functions found in a container, as their names, if they have one, the file offsets of their start and end, and their addresses
+type FoundFunctions = Vec<(Option<String>, usize, usize, u64)>;
+
+/// Find the functions in an ELF file, as names, file offsets, and addresses
+///
+/// Functions come from the symbol tables and, for code without symbols (_e.g._, in a stripped binary), from the frame descriptions in `.eh_frame` and the entry point. Functions without a symbol are named by their address.
+fn elf_functions(elf: &goblin::elf::Elf, buffer: &[u8]) -> FoundFunctions {
+    let mut functions: Vec<_> = elf
+        .dynsyms
+        .iter()
+        .map(|sym| (elf.dynstrtab.get_at(sym.st_name), sym))
+        .chain(
+            elf.syms
+                .iter()
+                .map(|sym| (elf.strtab.get_at(sym.st_name), sym)),
+        )
+        .filter(|(_, sym)| sym.is_function() && sym.st_size > 0)
+        .map(|(name, sym)| {
+            let section = &elf.section_headers[sym.st_shndx];
+            let start = (section.sh_offset + sym.st_value - section.sh_addr) as usize;
+            (
+                name.map(str::to_string),
+                start,
+                (start + sym.st_size as usize),
+                elf_address(elf, &sym),
+            )
+        })
+        .collect();
+    // In object files, the frame descriptions and entry point are not relocated yet
+    if elf.header.e_type == goblin::elf::header::ET_REL {
+        return functions;
+    }
+    let known: BTreeSet<_> = functions
+        .iter()
+        .map(|(_, _, _, address)| *address)
+        .collect();
+    let mut unnamed_functions: BTreeMap<u64, Option<u64>> = eh_frame_ranges(elf, buffer)
+        .into_iter()
+        .map(|(start, length)| (start, Some(start + length)))
+        .collect();
+    if elf.entry != 0 {
+        unnamed_functions.entry(elf.entry).or_default();
+    }
+    for (&address, &end) in &unnamed_functions {
+        if known.contains(&address) {
+            continue;
+        }
+        let section = match elf.section_headers.iter().find(|section| {
+            section.is_executable()
+                && section.sh_type != goblin::elf::section_header::SHT_NOBITS
+                && section.sh_addr <= address
+                && address - section.sh_addr < section.sh_size
+        }) {
+            Some(section) => section,
+            None => continue,
+        };
+        let section_end = section.sh_addr + section.sh_size;
+        let end = end
+            .or_else(|| {
+                known
+                    .range(address + 1..)
+                    .chain(unnamed_functions.range(address + 1..).map(|(next, _)| next))
+                    .min()
+                    .copied()
+            })
+            .unwrap_or(section_end)
+            .min(section_end);
+        let start = (section.sh_offset + address - section.sh_addr) as usize;
+        let name = if address == elf.entry {
+            "entry".to_string()
+        } else {
+            unnamed(address)
+        };
+        functions.push((Some(name), start, start + (end - address) as usize, address));
+    }
+    functions
+}
+
+/// Read the ranges of the functions described in `.eh_frame`, as starting addresses and lengths
+///
+/// Almost every function has a frame description, since they are needed to unwind the stack, so they are kept even when a binary is stripped.
+fn eh_frame_ranges(elf: &goblin::elf::Elf, buffer: &[u8]) -> Vec<(u64, u64)> {
+    use gimli::UnwindSection;
+    let section = match elf
+        .section_headers
+        .iter()
+        .find(|section| elf.shdr_strtab.get_at(section.sh_name) == Some(".eh_frame"))
+    {
+        Some(section) => section,
+        None => return Vec::new(),
+    };
+    let data = match buffer
+        .get(section.sh_offset as usize..(section.sh_offset + section.sh_size) as usize)
+    {
+        Some(data) => data,
+        None => return Vec::new(),
+    };
+    let endian = if elf.little_endian {
+        gimli::RunTimeEndian::Little
+    } else {
+        gimli::RunTimeEndian::Big
+    };
+    let mut eh_frame = gimli::EhFrame::new(data, endian);
+    eh_frame.set_address_size(if elf.is_64 { 8 } else { 4 });
+    let bases = gimli::BaseAddresses::default().set_eh_frame(section.sh_addr);
+    let mut ranges = Vec::new();
+    let mut entries = eh_frame.entries(&bases);
+    while let Ok(Some(entry)) = entries.next() {
+        if let gimli::CieOrFde::Fde(partial) = entry {
+            if let Ok(fde) = partial.parse(gimli::EhFrame::cie_from_offset) {
+                if fde.len() > 0 {
+                    ranges.push((fde.initial_address(), fde.len()));
+                }
+            }
+        }
+    }
+    ranges
+}
+
+/// The name given to a function that has no symbol
+fn unnamed(address: u64) -> String {
+    format!("sub_{:x}", address)
+}
+
+/// Find the functions in a PE file, as names, file offsets, and addresses
+///
+/// PE files don't record the size of functions, so the bounds come from the exception table (`.pdata`) where there is one and otherwise run to the next known function or the end of the section. Functions are found from the exports, the COFF symbol table, the exception table, and the entry point.
+fn pe_functions(pe: &goblin::pe::PE, buffer: &[u8]) -> FoundFunctions {
+    use goblin::pe::section_table::{IMAGE_SCN_CNT_CODE, IMAGE_SCN_MEM_EXECUTE};
+    // Addresses and sizes come straight from the file, so sums of them can overflow; anything that would is skipped
+    let section_of = |rva: u32| {
+        pe.sections.iter().find(|section| {
+            section.virtual_address <= rva
+                && rva
+                    < section
+                        .virtual_address
+                        .saturating_add(section.virtual_size.max(section.size_of_raw_data))
+        })
+    };
+    let file_offset = |rva: u32| {
+        section_of(rva).and_then(|section| {
+            section
+                .pointer_to_raw_data
+                .checked_add(rva - section.virtual_address)
+                .map(|offset| offset as usize)
+        })
+    };
+    let read_u32 = |offset: usize| {
+        buffer
+            .get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+    };
+    // Functions by starting RVA, with their names and ending RVAs, if known
+    let mut functions: BTreeMap<u32, (Option<String>, Option<u32>)> = BTreeMap::new();
+    let mut name_function = |rva: u32, name: String| {
+        let (existing, _) = functions.entry(rva).or_default();
+        if existing.is_none() {
+            *existing = Some(name);
+        }
+    };
+    for export in &pe.exports {
+        if let (Some(name), None) = (export.name, &export.reexport) {
+            name_function(export.rva as u32, name.to_string());
+        }
+    }
+    let coff = &pe.header.coff_header;
+    if coff.pointer_to_symbol_table != 0 {
+        if let (Ok(symbols), Ok(strings)) = (coff.symbols(buffer), coff.strings(buffer)) {
+            for (_, _, symbol) in symbols.iter() {
+                // Functions have a derived type of function in the upper bits of the type
+                if symbol.section_number <= 0 || symbol.typ & 0x30 != 0x20 {
+                    continue;
+                }
+                if let (Some(section), Ok(name)) = (
+                    pe.sections.get(symbol.section_number as usize - 1),
+                    symbol.name(&strings),
+                ) {
+                    if let Some(rva) = section.virtual_address.checked_add(symbol.value) {
+                        name_function(rva, name.to_string());
+                    }
+                }
+            }
+        }
+    }
+    if pe.entry != 0 {
+        name_function(pe.entry as u32, "entry".to_string());
+    }
+    if let Some(pdata) = pe
+        .sections
+        .iter()
+        .find(|section| section.name().ok() == Some(".pdata"))
+    {
+        let start = pdata.pointer_to_raw_data as usize;
+        let size = match pdata.virtual_size {
+            0 => pdata.size_of_raw_data,
+            size => size.min(pdata.size_of_raw_data),
+        } as usize;
+        let pdata = buffer.get(start..start + size).unwrap_or(&[]);
+        match coff.machine {
+            goblin::pe::header::COFF_MACHINE_X86_64 => {
+                // Each RUNTIME_FUNCTION is the start, end, and unwind information
+                for entry in pdata.chunks_exact(12) {
+                    let begin = u32::from_le_bytes(entry[0..4].try_into().unwrap());
+                    let end = u32::from_le_bytes(entry[4..8].try_into().unwrap());
+                    let unwind = u32::from_le_bytes(entry[8..12].try_into().unwrap());
+                    // Chained unwind information describes a part of another function that has been moved out of line
+                    let chained = file_offset(unwind)
+                        .and_then(|offset| buffer.get(offset))
+                        .map(|flags| (flags >> 3) & UNW_FLAG_CHAININFO != 0)
+                        .unwrap_or(false);
+                    if begin != 0 && !chained {
+                        functions.entry(begin).or_default().1 = Some(end);
+                    }
+                }
+            }
+            goblin::pe::header::COFF_MACHINE_ARM64 => {
+                // Each entry is the start and either packed unwind information or the address of the unwind information, which both include the length in instructions
+                for entry in pdata.chunks_exact(8) {
+                    let begin = u32::from_le_bytes(entry[0..4].try_into().unwrap());
+                    let unwind = u32::from_le_bytes(entry[4..8].try_into().unwrap());
+                    let length = if unwind & 3 != 0 {
+                        Some((unwind >> 2) & 0x7ff)
+                    } else {
+                        file_offset(unwind)
+                            .and_then(read_u32)
+                            .map(|header| header & 0x3ffff)
+                    };
+                    if let (Some(end), true) = (
+                        length.and_then(|length| begin.checked_add(length * 4)),
+                        begin != 0,
+                    ) {
+                        functions.entry(begin).or_default().1 = Some(end);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    functions
+        .iter()
+        .filter_map(|(&start, (name, end))| {
+            let section = section_of(start).filter(|section| {
+                section.characteristics & (IMAGE_SCN_CNT_CODE | IMAGE_SCN_MEM_EXECUTE) != 0
+            })?;
+            let section_end = section
+                .virtual_address
+                .saturating_add(match section.virtual_size {
+                    0 => section.size_of_raw_data,
+                    size => size.min(section.size_of_raw_data),
+                });
+            let end = end
+                .or_else(|| {
+                    functions
+                        .range((std::ops::Bound::Excluded(start), std::ops::Bound::Unbounded))
+                        .next()
+                        .map(|(&next, _)| next)
+                })
+                .unwrap_or(section_end)
+                .min(section_end);
+            let offset = section
+                .pointer_to_raw_data
+                .checked_add(start - section.virtual_address)? as usize;
+            let address = pe.image_base as u64 + start as u64;
+            Some((
+                Some(name.clone().unwrap_or_else(|| unnamed(address))),
+                offset,
+                offset + end.saturating_sub(start) as usize,
+                address,
+            ))
+        })
+        .collect()
+}
+
+/// Find the functions in a MachO file, as names, file offsets, and addresses
+///
+/// MachO doesn't record the size of functions, so each function runs to the next function or the end of its section. Functions are found from the symbol table, the exports, and `LC_FUNCTION_STARTS`, which is kept even in stripped binaries. The MachO file starts at `base` in the buffer, since it may be one slice of a fat binary.
+fn mach_functions(
+    mach: &goblin::mach::MachO,
+    buffer: &[u8],
+    base: usize,
+) -> Result<FoundFunctions, goblin::error::Error> {
+    let mut sections = Vec::new();
+    for segment in &mach.segments {
+        for (section, _) in segment.sections()? {
+            if section.flags & (S_ATTR_PURE_INSTRUCTIONS | S_ATTR_SOME_INSTRUCTIONS) != 0 {
+                sections.push(section);
+            }
+        }
+    }
+    let text = mach
+        .segments
+        .iter()
+        .find(|segment| segment.name().ok() == Some("__TEXT"))
+        .map(|segment| segment.vmaddr)
+        .unwrap_or(0);
+    // Functions by address, with their names, if known
+    let mut functions: BTreeMap<u64, Option<String>> = BTreeMap::new();
+    let mut external = BTreeSet::new();
+    for (name, symbol) in mach.symbols().flatten() {
+        // Names starting with `l` or `L` are the assembler's temporary labels (_e.g._, `ltmp0`), which can share an address with a function
+        if symbol.n_sect == NO_SECT.into()
+            || symbol.n_type & N_STAB != 0
+            || name.starts_with(&['l', 'L'][..])
+        {
+            continue;
+        }
+        // External symbols are preferred to local ones at the same address
+        let function = functions.entry(symbol.n_value).or_default();
+        if function.is_none() || (symbol.is_global() && !external.contains(&symbol.n_value)) {
+            *function = Some(name.to_string());
+        }
+        if symbol.is_global() {
+            external.insert(symbol.n_value);
+        }
+    }
+    for export in mach.exports()? {
+        // Exports are relative to the start of the image
+        functions
+            .entry(text + export.offset)
+            .or_default()
+            .get_or_insert_with(|| export.name);
+    }
+    for command in &mach.load_commands {
+        if let CommandVariant::FunctionStarts(starts) = &command.command {
+            // The function starts are a sequence of ULEB128 deltas from the start of the image, ending with zero
+            let start = base + starts.dataoff as usize;
+            let data = buffer
+                .get(start..start + starts.datasize as usize)
+                .unwrap_or(&[]);
+            let mut address = text;
+            let mut delta = 0;
+            let mut shift = 0;
+            for &byte in data {
+                delta |= ((byte & 0x7f) as u64) << shift;
+                if byte & 0x80 != 0 {
+                    shift += 7;
+                    if shift >= 64 {
+                        break;
+                    }
+                    continue;
+                }
+                if delta == 0 {
+                    break;
+                }
+                address += delta;
+                functions.entry(address).or_default();
+                delta = 0;
+                shift = 0;
+            }
+        }
+    }
+    Ok(functions
+        .iter()
+        .filter_map(|(&address, name)| {
+            let section = sections
+                .iter()
+                .find(|section| section.addr <= address && address < section.addr + section.size)?;
+            let end = functions
+                .range(address + 1..)
+                .next()
+                .map(|(&next, _)| next)
+                .unwrap_or(u64::MAX)
+                .min(section.addr + section.size);
+            let offset = base + section.offset as usize + (address - section.addr) as usize;
+            Some((
+                Some(name.clone().unwrap_or_else(|| unnamed(address))),
+                offset,
+                offset + (end - address) as usize,
+                address,
+            ))
+        })
+        .collect())
+}
+
+/// The flag in x64 unwind information for an entry that continues another function's unwind information
+const UNW_FLAG_CHAININFO: u8 = 0x4;
+
+#[cfg(test)]
+mod tests {
+    use crate::{BasicBlock, Function, Instruction, Program};
+
+    /// Parse a binary from the test data, panicking if it can't be parsed
+    pub(crate) fn parse<A: yaxpeax_arch::Arch + super::MachArch>(file: &str) -> super::GoblinYax<A>
+    where
+        for<'a> super::U8Reader<'a>:
+            super::Reader<<A as super::Arch>::Address, <A as super::Arch>::Word>,
+        A::Instruction: super::YaxInstruction,
+    {
+        super::GoblinYax::parse(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("testdata")
+                .join(file),
+            None,
+        )
+        .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Parse a binary from a buffer
+    pub(crate) fn try_parse<A: yaxpeax_arch::Arch + super::MachArch>(
+        buffer: &[u8],
+    ) -> Result<super::GoblinYax<A>, super::GoblinYaxError<A>>
+    where
+        for<'a> super::U8Reader<'a>:
+            super::Reader<<A as super::Arch>::Address, <A as super::Arch>::Word>,
+        A::Instruction: super::YaxInstruction,
+    {
+        super::GoblinYax::parse_bytes(buffer, None)
+    }
+
+    /// Render every instruction in a function, in order
+    pub(crate) fn rendered(function: &impl Function) -> Vec<String> {
+        function
+            .blocks()
+            .flat_map(|block| {
+                (0..block.len())
+                    .map(|index| block.get(index).render().into_owned())
+                    .chain(std::iter::once(block.terminator().render().into_owned()))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn branch_and_call_targets() {
+        // Blocks are numbered in order, and a block that falls through has no terminator
+        let program = parse::<yaxpeax_x86::x86_64>("x86_64-loop.o");
+        let sum = rendered(program.get("sum").unwrap());
+        for instruction in ["jle block 4", "jnz block 2", "jmp block 3"] {
+            assert!(
+                sum.iter().any(|rendered| rendered == instruction),
+                "{:?}",
+                sum
+            );
+        }
+        // A call to a function in the same section needs no relocation, so it is found from the symbol table
+        let program = parse::<yaxpeax_arm::armv8::a64::ARMv8>("aarch64-targets.o");
+        assert_eq!(
+            rendered(program.get("sum_squares").unwrap()),
+            [
+                "stp x30, x21, [sp, #-0x20]!",
+                "stp x20, x19, [sp, #0x10]",
+                "cmp w0, #0x1",
+                "b.lt block 5",
+                "mov w19, w0",
+                "mov w21, wzr",
+                "mov w20, wzr",
+                "<no instruction>",
+                "mov w0, w21",
+                "bl sq",
+                "add w21, w21, #0x1",
+                "add w20, w20, w0",
+                "cmp w21, w19",
+                "b.lt block 2",
+                "b block 6",
+                "mov w20, wzr",
+                "<no instruction>",
+                "mov w0, w20",
+                "ldp x20, x19, [sp, #0x10]",
+                "ldp x30, x21, [sp], #0x20",
+                "ret"
+            ]
+        );
+    }
+
+    #[test]
+    fn address_operands() {
+        // With no relocations, a load from a data symbol is named by its offset, and one from outside any symbol is not
+        let program = parse::<yaxpeax_x86::x86_64>("x86_64-addresses");
+        assert_eq!(
+            rendered(program.get("third").unwrap()),
+            ["mov eax, dword [rip + table+0x8]", "ret"]
+        );
+        assert_eq!(
+            rendered(program.get("greeting").unwrap()),
+            ["lea rax, qword [rip + <addr>]", "ret"]
+        );
+    }
+
+    #[test]
+    fn pe_functions_without_exports() {
+        let program = parse::<yaxpeax_x86::x86_64>("x86_64-pe.exe");
+        let mut names: Vec<_> = program
+            .functions()
+            .map(|function| function.name().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["entry", "square", "sub_140001010"]);
+        // The exception table gives the ends of the functions, so the padding after them is left out
+        assert_eq!(
+            rendered(program.get("entry").unwrap()),
+            [
+                "sub rsp, 0x28",
+                "call sub_140001010",
+                "add rsp, 0x28",
+                "ret"
+            ]
+        );
+        assert_eq!(
+            rendered(program.get("sub_140001010").unwrap()),
+            ["lea eax, dword [rcx + 0x1]", "ret"]
+        );
+        assert_eq!(
+            rendered(program.get("square").unwrap()).last().unwrap(),
+            "ret"
+        );
+    }
+
+    #[test]
+    fn stripped_calls_are_followed() {
+        let program = parse::<yaxpeax_x86::x86_64>("x86_64-stripped");
+        let mut names: Vec<_> = program
+            .functions()
+            .map(|function| function.name().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["entry", "sub_4000b0", "sub_4000b6"]);
+        assert!(rendered(program.get("entry").unwrap()).contains(&"call sub_4000b6".to_string()));
+        assert_eq!(
+            rendered(program.get("sub_4000b6").unwrap())
+                .iter()
+                .filter(|instruction| *instruction == "call sub_4000b0")
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn dwarf_line_locations() {
+        // The line table of an object file refers to the code through relocations, which have to be applied
+        let program = parse::<yaxpeax_x86::x86_64>("x86_64-loop-debug.o");
+        let locations = |name: &str| -> Vec<_> {
+            program
+                .get(name)
+                .unwrap()
+                .blocks()
+                .flat_map(|block| {
+                    (0..block.len())
+                        .map(|index| block.get(index).location().map(|l| l.into_owned()))
+                        .chain(std::iter::once(
+                            block.terminator().location().map(|l| l.into_owned()),
+                        ))
+                        .collect::<Vec<_>>()
+                })
+                .collect()
+        };
+        assert_eq!(locations("clamp"), vec![Some("loop.c:12".to_string()); 4]);
+        assert_eq!(
+            locations("sum").first(),
+            Some(&Some("loop.c:8".to_string()))
+        );
+        // Without debugging information, there are no locations
+        let program = parse::<yaxpeax_x86::x86_64>("x86_64-loop.o");
+        assert!(program
+            .functions()
+            .flat_map(|function| function.blocks())
+            .all(|block| block.terminator().location().is_none()));
+    }
+
+    #[test]
+    fn truncated_pe() {
+        let buffer = std::fs::read(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/x86_64-pe.exe"),
+        )
+        .unwrap();
+        // Without the headers there is nothing to parse; further in, the missing parts of the image are just left out
+        for length in [0, 0x40, 0x100] {
+            assert!(try_parse::<yaxpeax_x86::x86_64>(&buffer[..length]).is_err());
+        }
+        for length in (0..buffer.len()).step_by(0x10) {
+            let _ = try_parse::<yaxpeax_x86::x86_64>(&buffer[..length]);
+        }
+    }
+
+    #[test]
+    fn pe_address_overflow() {
+        let buffer = std::fs::read(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/x86_64-pe.exe"),
+        )
+        .unwrap();
+        let read_u32 = |buffer: &[u8], offset: usize| {
+            u32::from_le_bytes(buffer[offset..offset + 4].try_into().unwrap()) as usize
+        };
+        let pe = read_u32(&buffer, 0x3c);
+        let sections = pe + 24 + u16::from_le_bytes([buffer[pe + 20], buffer[pe + 21]]) as usize;
+        let symbols = read_u32(&buffer, pe + 12);
+        let pdata = read_u32(&buffer, sections + 40 + 20);
+        let patch = |patches: &[(usize, &[u8])]| {
+            let mut buffer = buffer.clone();
+            for (offset, bytes) in patches {
+                buffer[*offset..*offset + bytes.len()].copy_from_slice(bytes);
+            }
+            buffer
+        };
+        // A code section whose size runs past the end of the address space
+        let huge_section = patch(&[(sections + 8, &u32::MAX.to_le_bytes())]);
+        // A symbol whose value runs past the end of the address space
+        let huge_symbol = patch(&[(symbols + 8, &u32::MAX.to_le_bytes())]);
+        // An ARM64 exception table entry for a function that runs past the end of the address space
+        let huge_function = patch(&[
+            (pe + 4, &0xaa64u16.to_le_bytes()),
+            (pdata, &0xffff_fff0u32.to_le_bytes()),
+        ]);
+        for buffer in [huge_section, huge_symbol, huge_function] {
+            if let Ok(program) = try_parse::<yaxpeax_x86::x86_64>(&buffer) {
+                assert!(program.get("entry").is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn relocations_name_operands() {
+        let program = parse::<yaxpeax_x86::x86_64>("x86_64-relocations.o");
+        let mut names: Vec<_> = program
+            .functions()
+            .map(|function| function.name().into_owned())
+            .collect();
+        names.sort();
+        // Calls patched by relocations are not followed, and only mangled names are demangled
+        assert_eq!(names, ["address", "bar", "baz(int)"]);
+        let bar = rendered(program.get("bar").unwrap());
+        for instruction in ["call foo", "add eax, dword [rip + g]", "call baz(int)"] {
+            assert!(
+                bar.iter().any(|rendered| rendered == instruction),
+                "{:?}",
+                bar
+            );
+        }
+        assert_eq!(
+            rendered(program.get("address").unwrap()),
+            ["mov eax, g", "ret"]
+        );
+    }
+
+    #[test]
+    fn mach_temporary_labels_are_not_names() {
+        let program = parse::<yaxpeax_arm::armv8::a64::ARMv8>("arm64-mach.o");
+        let mut names: Vec<_> = program
+            .functions()
+            .map(|function| function.name().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["_sq", "_twice"]);
     }
 }
@@ -1,35 +1,82 @@
 use std::borrow::Cow;
 
-use yaxpeax_x86::long_mode::Opcode;
+use crate::asm::x86_64::X86_64;
+use crate::asm::Dialect;
+use yaxpeax_x86::long_mode::{Opcode, Operand};
 use yaxpeax_x86::x86_64;
 impl super::MachArch for x86_64 {
     const CPU_TYPE: Option<u32> = Some(0x01000007);
 }
 impl super::YaxInstruction for yaxpeax_x86::long_mode::Instruction {
     const GAP: i32 = -1;
+    const NORMALIZATION: super::Normalization = super::Normalization {
+        pc_registers: &["rip"],
+        absolute_from: None,
+    };
 
     fn is_flow_control(&self) -> bool {
-        match self.opcode() {
+        matches!(
+            self.opcode(),
             Opcode::JA
-            | Opcode::JB
-            | Opcode::JG
-            | Opcode::JGE
-            | Opcode::JL
-            | Opcode::JLE
-            | Opcode::JMPE
-            | Opcode::JMPF
-            | Opcode::JNA
-            | Opcode::JNB
-            | Opcode::JNO
-            | Opcode::JNP
-            | Opcode::JNS
-            | Opcode::JNZ
-            | Opcode::JRCXZ
-            | Opcode::JS
-            | Opcode::JZ
-            | Opcode::RETURN
-            | Opcode::RETF => true,
-            _ => false,
+                | Opcode::JB
+                | Opcode::JG
+                | Opcode::JGE
+                | Opcode::JL
+                | Opcode::JLE
+                | Opcode::JMPE
+                | Opcode::JMPF
+                | Opcode::JNA
+                | Opcode::JNB
+                | Opcode::JNO
+                | Opcode::JNP
+                | Opcode::JNS
+                | Opcode::JNZ
+                | Opcode::JRCXZ
+                | Opcode::JS
+                | Opcode::JZ
+                | Opcode::RETURN
+                | Opcode::RETF
+        )
+    }
+
+    fn relative_base(offset: usize, length: usize) -> usize {
+        offset + length
+    }
+
+    fn relative_operand(&self) -> Option<i64> {
+        // Pushes also take signed immediates, so only branches and calls are relative
+        if !matches!(
+            self.opcode(),
+            Opcode::CALL
+                | Opcode::JMP
+                | Opcode::JRCXZ
+                | Opcode::LOOP
+                | Opcode::LOOPZ
+                | Opcode::LOOPNZ
+                | Opcode::JO
+                | Opcode::JNO
+                | Opcode::JB
+                | Opcode::JNB
+                | Opcode::JZ
+                | Opcode::JNZ
+                | Opcode::JNA
+                | Opcode::JA
+                | Opcode::JS
+                | Opcode::JNS
+                | Opcode::JP
+                | Opcode::JNP
+                | Opcode::JL
+                | Opcode::JGE
+                | Opcode::JLE
+                | Opcode::JG
+        ) || self.operand_count() == 0
+        {
+            return None;
+        }
+        match self.operand(0) {
+            Operand::ImmediateI8(displacement) => Some(displacement as i64),
+            Operand::ImmediateI32(displacement) => Some(displacement as i64),
+            _ => None,
         }
     }
 }
@@ -47,4 +94,10 @@ impl crate::Instruction for yaxpeax_x86::long_mode::Instruction {
     fn render<'a>(&self) -> Cow<'a, str> {
         Cow::Owned(self.to_string())
     }
+
+    fn class(&self) -> crate::InstructionClass {
+        let text = self.to_string();
+        let (mnemonic, operands) = super::split_instruction(&text, X86_64::PREFIXES);
+        X86_64::class(mnemonic, operands)
+    }
 }
@@ -0,0 +1,327 @@
+use crate::{Function, Lookup};
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+/// A file that is part of an input
+pub struct InputFile {
+    /// The name used to identify the functions from this file (_e.g._, `src/foo.o`); a single file has no label and its functions are not qualified
+    pub label: Option<String>,
+    /// The path to the file
+    pub path: PathBuf,
+    /// Whether the file was named explicitly, rather than found in a directory; files found in a directory that cannot be parsed are skipped
+    pub explicit: bool,
+}
+
+/// Expand an input into the files that make it up
+///
+/// An input can be:
+/// - a directory, where all the files in it, recursively, are used and labelled by their path relative to the directory
+/// - a glob (_e.g._, `build/*.o`), where the files are labelled by their path relative to the part of the pattern without wildcards
+/// - a response file (_e.g._, `@objects.txt`), which lists one file per line, labelled as written
+/// - a single file
+pub fn expand(input: impl AsRef<Path>) -> Result<Vec<InputFile>, String> {
+    let input = input.as_ref();
+    let text = input.to_string_lossy();
+    if let Some(list) = text.strip_prefix('@') {
+        let contents = std::fs::read_to_string(list).map_err(|e| format!("{}: {}", list, e))?;
+        Ok(contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| InputFile {
+                label: Some(line.to_string()),
+                path: PathBuf::from(line),
+                explicit: true,
+            })
+            .collect())
+    } else if text.contains(['*', '?', '[']) {
+        let base: PathBuf = input
+            .components()
+            .take_while(|component| {
+                !component
+                    .as_os_str()
+                    .to_string_lossy()
+                    .contains(['*', '?', '['])
+            })
+            .collect();
+        let mut files = Vec::new();
+        for path in glob::glob(&text).map_err(|e| format!("{}: {}", text, e))? {
+            let path = path.map_err(|e| e.to_string())?;
+            if path.is_file() {
+                files.push(InputFile {
+                    label: Some(label(&path, &base)),
+                    path,
+                    explicit: true,
+                });
+            }
+        }
+        if files.is_empty() {
+            Err(format!("{}: no files match", text))
+        } else {
+            Ok(files)
+        }
+    } else if input.is_dir() {
+        fn walk(directory: &Path, base: &Path, files: &mut Vec<InputFile>) -> Result<(), String> {
+            let mut entries = std::fs::read_dir(directory)
+                .and_then(|entries| {
+                    entries
+                        .map(|entry| entry.map(|entry| entry.path()))
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .map_err(|e| format!("{}: {}", directory.display(), e))?;
+            // Directory order is arbitrary, but the functions must be in a consistent order
+            entries.sort();
+            for path in entries {
+                if path.is_dir() {
+                    walk(&path, base, files)?;
+                } else {
+                    files.push(InputFile {
+                        label: Some(label(&path, base)),
+                        path,
+                        explicit: false,
+                    });
+                }
+            }
+            Ok(())
+        }
+        let mut files = Vec::new();
+        walk(input, input, &mut files)?;
+        Ok(files)
+    } else {
+        Ok(vec![InputFile {
+            label: None,
+            path: input.to_path_buf(),
+            explicit: true,
+        }])
+    }
+}
+
+fn label(path: &Path, base: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Check if an input is a single file, rather than a collection of files
+pub(crate) fn is_single(files: &[InputFile]) -> bool {
+    matches!(files, [InputFile { label: None, .. }])
+}
+
+/// Parse every file in an input
+///
+/// Files found in a directory that cannot be parsed (_e.g._, dependency files in a build tree) are skipped, but at least one file must be parsed.
+pub(crate) fn parse_all<T, E: Display>(
+    files: &[InputFile],
+    mut parse: impl FnMut(&Path) -> Result<T, E>,
+) -> Result<Vec<(Option<&str>, T)>, String> {
+    let mut programs = Vec::new();
+    let mut first_error = None;
+    for file in files {
+        match parse(&file.path) {
+            Ok(program) => programs.push((file.label.as_deref(), program)),
+            Err(e) => {
+                let message = format!("{}: {}", file.path.display(), e);
+                if file.explicit {
+                    return Err(message);
+                }
+                first_error.get_or_insert(message);
+            }
+        }
+    }
+    if programs.is_empty() {
+        Err(first_error.unwrap_or_else(|| "no files to parse".to_string()))
+    } else {
+        Ok(programs)
+    }
+}
+
+/// A function from a program made of many files, named by the file it came from (_e.g._, `src/foo.o:bar`)
+pub(crate) struct SourcedFunction<'a, F> {
+    function: &'a F,
+    name: String,
+}
+impl<'a, F: Function> Function for SourcedFunction<'a, F> {
+    type BasicBlock = F::BasicBlock;
+
+    fn blocks<'b>(&'b self) -> Box<dyn Iterator<Item = &'b Self::BasicBlock> + 'b> {
+        self.function.blocks()
+    }
+
+    fn name<'b>(&'b self) -> Cow<'b, str> {
+        Cow::Borrowed(&self.name)
+    }
+}
+
+/// The functions from all the files in an input, merged into one program
+pub(crate) struct Merged<'a, F> {
+    functions: Vec<SourcedFunction<'a, F>>,
+    by_name: BTreeMap<String, usize>,
+    by_unqualified_name: BTreeMap<String, Vec<usize>>,
+}
+impl<'a, F: Function> Merged<'a, F> {
+    pub(crate) fn new(sources: impl Iterator<Item = (Option<&'a str>, &'a F)>) -> Self {
+        let mut functions = Vec::new();
+        let mut by_name = BTreeMap::new();
+        let mut by_unqualified_name: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for (label, function) in sources {
+            let unqualified = function.name().into_owned();
+            let name = match label {
+                Some(label) => format!("{}:{}", label, unqualified),
+                None => unqualified.clone(),
+            };
+            by_name.insert(name.clone(), functions.len());
+            by_unqualified_name
+                .entry(unqualified)
+                .or_default()
+                .push(functions.len());
+            functions.push(SourcedFunction { function, name });
+        }
+        Merged {
+            functions,
+            by_name,
+            by_unqualified_name,
+        }
+    }
+    /// Retrive a function by name
+    ///
+    /// The file can be omitted from the name as long as only one file has a function by that name; otherwise, the name is ambiguous.
+    pub(crate) fn get(&self, name: &str) -> Lookup<'_, SourcedFunction<'a, F>> {
+        if let Some(&index) = self.by_name.get(name) {
+            return Lookup::Found(&self.functions[index]);
+        }
+        match self
+            .by_unqualified_name
+            .get(name)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+        {
+            [] => Lookup::Missing,
+            [index] => Lookup::Found(&self.functions[*index]),
+            indices => Lookup::Ambiguous(
+                indices
+                    .iter()
+                    .map(|&index| self.functions[index].name.clone())
+                    .collect(),
+            ),
+        }
+    }
+    /// Iterate over all functions available
+    pub(crate) fn functions(&self) -> impl Iterator<Item = &SourcedFunction<'a, F>> {
+        self.functions.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create a directory of files, some nested, named after the test
+    fn tree(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("ctflgrdiff-{}-{}", std::process::id(), name));
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        for file in ["b.o", "a.o", "a.d", "sub/c.o"] {
+            std::fs::write(root.join(file), file).unwrap();
+        }
+        root
+    }
+
+    fn labels(files: &[InputFile]) -> Vec<(&str, bool)> {
+        files
+            .iter()
+            .map(|file| (file.label.as_deref().unwrap_or(""), file.explicit))
+            .collect()
+    }
+
+    #[test]
+    fn expand_directory() {
+        let root = tree("directory");
+        let files = expand(&root).unwrap();
+        assert_eq!(
+            labels(&files),
+            [
+                ("a.d", false),
+                ("a.o", false),
+                ("b.o", false),
+                (Path::new("sub").join("c.o").to_str().unwrap(), false)
+            ]
+        );
+        assert!(files.iter().all(|file| file.path.starts_with(&root)));
+        assert!(!is_single(&files));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn expand_glob() {
+        let root = tree("glob");
+        let files = expand(root.join("*.o")).unwrap();
+        assert_eq!(labels(&files), [("a.o", true), ("b.o", true)]);
+        let files = expand(root.join("*").join("*.o")).unwrap();
+        assert_eq!(
+            labels(&files),
+            [(Path::new("sub").join("c.o").to_str().unwrap(), true)]
+        );
+        assert!(expand(root.join("*.a")).is_err());
+        assert!(expand(root.join("[")).is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn expand_response_file() {
+        let root = tree("response");
+        let list = root.join("objects.txt");
+        std::fs::write(
+            &list,
+            format!(
+                "  {}\n\n{}\n",
+                root.join("b.o").display(),
+                root.join("a.o").display()
+            ),
+        )
+        .unwrap();
+        let files = expand(format!("@{}", list.display())).unwrap();
+        let paths: Vec<_> = files.iter().map(|file| file.path.clone()).collect();
+        assert_eq!(paths, [root.join("b.o"), root.join("a.o")]);
+        assert!(files.iter().all(|file| file.explicit));
+        assert!(expand(format!("@{}", root.join("missing.txt").display())).is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn expand_single_file() {
+        let files = expand("program.o").unwrap();
+        assert!(is_single(&files));
+        assert_eq!(files[0].path, Path::new("program.o"));
+    }
+
+    #[test]
+    fn parse_all_skips_unparsable_files_found_in_directories() {
+        let root = tree("parse");
+        let parse = |path: &Path| {
+            if path.extension().is_some_and(|extension| extension == "o") {
+                Ok(path.file_name().unwrap().to_string_lossy().into_owned())
+            } else {
+                Err("not an object")
+            }
+        };
+        let files = expand(&root).unwrap();
+        let programs = parse_all(&files, parse).unwrap();
+        assert_eq!(programs.len(), 3);
+        assert_eq!(programs[0], (Some("a.o"), "a.o".to_string()));
+        // Explicitly named files must be parsed
+        let files = expand(root.join("a.*")).unwrap();
+        assert!(parse_all(&files, parse).is_err());
+        // Directories with nothing that can be parsed are an error
+        std::fs::remove_dir_all(&root).unwrap();
+        let root = tree("parse-nothing");
+        let files = expand(&root).unwrap();
+        let error = parse_all(&files, |_| Err::<(), _>("not an object")).err();
+        assert!(error.is_some_and(|error| error.ends_with("not an object")));
+        assert!(parse_all(&[], parse).is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
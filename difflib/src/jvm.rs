@@ -0,0 +1,982 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::io::Read;
+
+/// JVM bytecode from a class file or a JAR of class files
+///
+/// Each method is a function, named by its class, name, and descriptor (_e.g._, `com.example.Foo.bar(I)V`).
+pub struct Jvm {
+    funcs: BTreeMap<String, JvmMethod>,
+}
+pub struct JvmMethod {
+    blocks: Vec<JvmBlock>,
+    name: String,
+}
+pub struct JvmBlock {
+    instructions: Vec<JvmInstruction>,
+    pc: usize,
+    terminator: Option<JvmInstruction>,
+}
+pub struct JvmInstruction {
+    opcode: u8,
+    /// The resolved symbolic operand for instructions that refer to the constant pool (_e.g._, the method being invoked); this is compared when scoring
+    reference: Option<String>,
+    /// The other operands (local variables, constants, branch targets); these are only displayed
+    operands: String,
+}
+pub enum JvmError {
+    Io(std::io::Error),
+    Malformed(&'static str),
+    Unrecognized,
+    Zip(zip::result::ZipError),
+}
+impl Display for JvmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JvmError::Io(i) => i.fmt(f),
+            JvmError::Malformed(m) => write!(f, "class file is malformed: {}", m),
+            JvmError::Unrecognized => f.write_str("file is not a class file or JAR"),
+            JvmError::Zip(z) => z.fmt(f),
+        }
+    }
+}
+
+/// Groups of related opcodes; two different opcodes in the same group get partial credit
+#[derive(PartialEq, Eq)]
+enum Family {
+    ArrayLoad,
+    ArrayStore,
+    Arith,
+    Bitwise,
+    Compare,
+    Constant,
+    Convert,
+    Field,
+    Goto,
+    IfCompare,
+    IfZero,
+    Invoke,
+    Load,
+    Monitor,
+    New,
+    Return,
+    Stack,
+    Store,
+    Switch,
+    Other,
+}
+
+/// The mnemonic and the number of operand bytes for each opcode; variable length instructions have a length of 0
+const OPCODES: [(&str, usize); 202] = [
+    ("nop", 0),
+    ("aconst_null", 0),
+    ("iconst_m1", 0),
+    ("iconst_0", 0),
+    ("iconst_1", 0),
+    ("iconst_2", 0),
+    ("iconst_3", 0),
+    ("iconst_4", 0),
+    ("iconst_5", 0),
+    ("lconst_0", 0),
+    ("lconst_1", 0),
+    ("fconst_0", 0),
+    ("fconst_1", 0),
+    ("fconst_2", 0),
+    ("dconst_0", 0),
+    ("dconst_1", 0),
+    ("bipush", 1),
+    ("sipush", 2),
+    ("ldc", 1),
+    ("ldc_w", 2),
+    ("ldc2_w", 2),
+    ("iload", 1),
+    ("lload", 1),
+    ("fload", 1),
+    ("dload", 1),
+    ("aload", 1),
+    ("iload_0", 0),
+    ("iload_1", 0),
+    ("iload_2", 0),
+    ("iload_3", 0),
+    ("lload_0", 0),
+    ("lload_1", 0),
+    ("lload_2", 0),
+    ("lload_3", 0),
+    ("fload_0", 0),
+    ("fload_1", 0),
+    ("fload_2", 0),
+    ("fload_3", 0),
+    ("dload_0", 0),
+    ("dload_1", 0),
+    ("dload_2", 0),
+    ("dload_3", 0),
+    ("aload_0", 0),
+    ("aload_1", 0),
+    ("aload_2", 0),
+    ("aload_3", 0),
+    ("iaload", 0),
+    ("laload", 0),
+    ("faload", 0),
+    ("daload", 0),
+    ("aaload", 0),
+    ("baload", 0),
+    ("caload", 0),
+    ("saload", 0),
+    ("istore", 1),
+    ("lstore", 1),
+    ("fstore", 1),
+    ("dstore", 1),
+    ("astore", 1),
+    ("istore_0", 0),
+    ("istore_1", 0),
+    ("istore_2", 0),
+    ("istore_3", 0),
+    ("lstore_0", 0),
+    ("lstore_1", 0),
+    ("lstore_2", 0),
+    ("lstore_3", 0),
+    ("fstore_0", 0),
+    ("fstore_1", 0),
+    ("fstore_2", 0),
+    ("fstore_3", 0),
+    ("dstore_0", 0),
+    ("dstore_1", 0),
+    ("dstore_2", 0),
+    ("dstore_3", 0),
+    ("astore_0", 0),
+    ("astore_1", 0),
+    ("astore_2", 0),
+    ("astore_3", 0),
+    ("iastore", 0),
+    ("lastore", 0),
+    ("fastore", 0),
+    ("dastore", 0),
+    ("aastore", 0),
+    ("bastore", 0),
+    ("castore", 0),
+    ("sastore", 0),
+    ("pop", 0),
+    ("pop2", 0),
+    ("dup", 0),
+    ("dup_x1", 0),
+    ("dup_x2", 0),
+    ("dup2", 0),
+    ("dup2_x1", 0),
+    ("dup2_x2", 0),
+    ("swap", 0),
+    ("iadd", 0),
+    ("ladd", 0),
+    ("fadd", 0),
+    ("dadd", 0),
+    ("isub", 0),
+    ("lsub", 0),
+    ("fsub", 0),
+    ("dsub", 0),
+    ("imul", 0),
+    ("lmul", 0),
+    ("fmul", 0),
+    ("dmul", 0),
+    ("idiv", 0),
+    ("ldiv", 0),
+    ("fdiv", 0),
+    ("ddiv", 0),
+    ("irem", 0),
+    ("lrem", 0),
+    ("frem", 0),
+    ("drem", 0),
+    ("ineg", 0),
+    ("lneg", 0),
+    ("fneg", 0),
+    ("dneg", 0),
+    ("ishl", 0),
+    ("lshl", 0),
+    ("ishr", 0),
+    ("lshr", 0),
+    ("iushr", 0),
+    ("lushr", 0),
+    ("iand", 0),
+    ("land", 0),
+    ("ior", 0),
+    ("lor", 0),
+    ("ixor", 0),
+    ("lxor", 0),
+    ("iinc", 2),
+    ("i2l", 0),
+    ("i2f", 0),
+    ("i2d", 0),
+    ("l2i", 0),
+    ("l2f", 0),
+    ("l2d", 0),
+    ("f2i", 0),
+    ("f2l", 0),
+    ("f2d", 0),
+    ("d2i", 0),
+    ("d2l", 0),
+    ("d2f", 0),
+    ("i2b", 0),
+    ("i2c", 0),
+    ("i2s", 0),
+    ("lcmp", 0),
+    ("fcmpl", 0),
+    ("fcmpg", 0),
+    ("dcmpl", 0),
+    ("dcmpg", 0),
+    ("ifeq", 2),
+    ("ifne", 2),
+    ("iflt", 2),
+    ("ifge", 2),
+    ("ifgt", 2),
+    ("ifle", 2),
+    ("if_icmpeq", 2),
+    ("if_icmpne", 2),
+    ("if_icmplt", 2),
+    ("if_icmpge", 2),
+    ("if_icmpgt", 2),
+    ("if_icmple", 2),
+    ("if_acmpeq", 2),
+    ("if_acmpne", 2),
+    ("goto", 2),
+    ("jsr", 2),
+    ("ret", 1),
+    ("tableswitch", 0),
+    ("lookupswitch", 0),
+    ("ireturn", 0),
+    ("lreturn", 0),
+    ("freturn", 0),
+    ("dreturn", 0),
+    ("areturn", 0),
+    ("return", 0),
+    ("getstatic", 2),
+    ("putstatic", 2),
+    ("getfield", 2),
+    ("putfield", 2),
+    ("invokevirtual", 2),
+    ("invokespecial", 2),
+    ("invokestatic", 2),
+    ("invokeinterface", 4),
+    ("invokedynamic", 4),
+    ("new", 2),
+    ("newarray", 1),
+    ("anewarray", 2),
+    ("arraylength", 0),
+    ("athrow", 0),
+    ("checkcast", 2),
+    ("instanceof", 2),
+    ("monitorenter", 0),
+    ("monitorexit", 0),
+    ("wide", 0),
+    ("multianewarray", 3),
+    ("ifnull", 2),
+    ("ifnonnull", 2),
+    ("goto_w", 4),
+    ("jsr_w", 4),
+];
+const WIDE: u8 = 0xc4;
+const TABLESWITCH: u8 = 0xaa;
+const LOOKUPSWITCH: u8 = 0xab;
+
+impl JvmInstruction {
+    fn mnemonic(&self) -> &'static str {
+        OPCODES[self.opcode as usize].0
+    }
+    /// The mnemonic without an implicit local variable or constant (_e.g._, `iload_1` is `iload`), so those are treated like the explicit forms
+    fn base(&self) -> &'static str {
+        let mnemonic = self.mnemonic();
+        match mnemonic.rsplit_once('_') {
+            Some((base, "0" | "1" | "2" | "3" | "4" | "5" | "m1"))
+                if base.ends_with("load") || base.ends_with("store") || base.ends_with("const") =>
+            {
+                base
+            }
+            _ => match mnemonic {
+                "ldc_w" | "ldc2_w" => "ldc",
+                "goto_w" => "goto",
+                "jsr_w" => "jsr",
+                _ => mnemonic,
+            },
+        }
+    }
+    fn family(&self) -> Family {
+        match self.opcode {
+            0x01..=0x14 => Family::Constant,
+            0x15..=0x2d => Family::Load,
+            0x2e..=0x35 => Family::ArrayLoad,
+            0x36..=0x4e => Family::Store,
+            0x4f..=0x56 => Family::ArrayStore,
+            0x57..=0x5f => Family::Stack,
+            0x60..=0x77 | 0x84 => Family::Arith,
+            0x78..=0x83 => Family::Bitwise,
+            0x85..=0x93 => Family::Convert,
+            0x94..=0x98 => Family::Compare,
+            0x99..=0x9e | 0xc6 | 0xc7 => Family::IfZero,
+            0x9f..=0xa6 => Family::IfCompare,
+            0xa7 | 0xa8 | 0xa9 | 0xc8 | 0xc9 => Family::Goto,
+            0xaa | 0xab => Family::Switch,
+            0xac..=0xb1 | 0xbf => Family::Return,
+            0xb2..=0xb5 => Family::Field,
+            0xb6..=0xba => Family::Invoke,
+            0xbb..=0xbd | 0xc5 => Family::New,
+            0xc2 | 0xc3 => Family::Monitor,
+            _ => Family::Other,
+        }
+    }
+    fn is_flow_control(&self) -> bool {
+        matches!(
+            self.family(),
+            Family::IfZero | Family::IfCompare | Family::Goto | Family::Switch | Family::Return
+        )
+    }
+}
+
+impl crate::Program for Jvm {
+    const GAP: i32 = 2;
+
+    type ParseError = JvmError;
+
+    type ParseOptions = ();
+
+    type Function = JvmMethod;
+
+    fn parse(
+        file: impl AsRef<std::path::Path>,
+        _options: Self::ParseOptions,
+    ) -> Result<Self, Self::ParseError> {
+        let buffer = std::fs::read(file).map_err(JvmError::Io)?;
+        Self::parse_bytes(&buffer, ())
+    }
+
+    fn parse_bytes(buffer: &[u8], _options: Self::ParseOptions) -> Result<Self, Self::ParseError> {
+        let mut funcs = BTreeMap::new();
+        if buffer.starts_with(&[0xca, 0xfe, 0xba, 0xbe]) {
+            parse_class(buffer, &mut funcs)?;
+        } else if buffer.starts_with(b"PK") {
+            let mut archive =
+                zip::ZipArchive::new(std::io::Cursor::new(buffer)).map_err(JvmError::Zip)?;
+            for index in 0..archive.len() {
+                let mut entry = archive.by_index(index).map_err(JvmError::Zip)?;
+                // Multi-release JARs contain alternate versions of classes that would have the same names
+                if !entry.name().ends_with(".class") || entry.name().starts_with("META-INF/") {
+                    continue;
+                }
+                let mut class = Vec::new();
+                entry.read_to_end(&mut class).map_err(JvmError::Io)?;
+                parse_class(&class, &mut funcs)?;
+            }
+        } else {
+            return Err(JvmError::Unrecognized);
+        }
+        Ok(Jvm { funcs })
+    }
+
+    fn get(&self, name: &str) -> Option<&Self::Function> {
+        self.funcs.get(name)
+    }
+
+    fn functions<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Self::Function> + 'a> {
+        Box::new(self.funcs.values())
+    }
+}
+impl crate::Function for JvmMethod {
+    type BasicBlock = JvmBlock;
+
+    fn blocks<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Self::BasicBlock> + 'a> {
+        Box::new(self.blocks.iter())
+    }
+
+    fn name<'a>(&'a self) -> Cow<'a, str> {
+        Cow::Borrowed(&self.name)
+    }
+}
+impl crate::BasicBlock for JvmBlock {
+    type Instruction = JvmInstruction;
+
+    type Terminator = Option<JvmInstruction>;
+
+    fn get(&self, index: usize) -> &Self::Instruction {
+        &self.instructions[index]
+    }
+
+    fn len(&self) -> usize {
+        self.instructions.len()
+    }
+
+    fn name<'a>(&'a self) -> Cow<'a, str> {
+        Cow::Owned(format!("{}", self.pc))
+    }
+
+    fn terminator(&self) -> &Self::Terminator {
+        &self.terminator
+    }
+}
+impl crate::Instruction for JvmInstruction {
+    const EQUIVALENT: i32 = 4;
+    fn score(&self, other: &Self) -> i32 {
+        if self.base() == other.base() {
+            if self.reference == other.reference {
+                4
+            } else {
+                3
+            }
+        } else if self.family() == other.family() && self.family() != Family::Other {
+            3
+        } else {
+            0
+        }
+    }
+
+    fn render<'a>(&self) -> Cow<'a, str> {
+        let mut result = self.mnemonic().to_string();
+        for operand in self
+            .reference
+            .iter()
+            .chain(std::iter::once(&self.operands))
+            .filter(|o| !o.is_empty())
+        {
+            result.push(' ');
+            result.push_str(operand);
+        }
+        Cow::Owned(result)
+    }
+
+    fn class(&self) -> crate::InstructionClass {
+        // Floating point operations have mnemonics starting with the type (e.g., `fadd` or `d2i`)
+        let float = self.mnemonic().starts_with(['f', 'd']);
+        match self.family() {
+            Family::ArrayLoad
+            | Family::ArrayStore
+            | Family::Constant
+            | Family::Field
+            | Family::Load
+            | Family::New
+            | Family::Stack
+            | Family::Store => crate::InstructionClass::Memory,
+            Family::Arith if float => crate::InstructionClass::Float,
+            Family::Arith => crate::InstructionClass::Arith,
+            Family::Bitwise => crate::InstructionClass::Logic,
+            Family::Compare => crate::InstructionClass::Compare,
+            Family::Convert if self.mnemonic().contains(['f', 'd']) => {
+                crate::InstructionClass::Float
+            }
+            Family::Convert => crate::InstructionClass::Arith,
+            Family::Goto | Family::IfCompare | Family::IfZero | Family::Return | Family::Switch => {
+                crate::InstructionClass::Branch
+            }
+            Family::Invoke => crate::InstructionClass::Call,
+            Family::Monitor | Family::Other => crate::InstructionClass::Other,
+        }
+    }
+}
+
+struct Cursor<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+impl<'a> Cursor<'a> {
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8], JvmError> {
+        let result = self
+            .data
+            .get(self.position..self.position + length)
+            .ok_or(JvmError::Malformed("unexpected end of data"))?;
+        self.position += length;
+        Ok(result)
+    }
+    fn u8(&mut self) -> Result<u8, JvmError> {
+        Ok(self.bytes(1)?[0])
+    }
+    fn u16(&mut self) -> Result<u16, JvmError> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+    fn u32(&mut self) -> Result<u32, JvmError> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+}
+
+enum Constant {
+    Utf8(String),
+    Integer(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    Class(u16),
+    String(u16),
+    Reference(u16, u16),
+    NameAndType(u16, u16),
+    MethodHandle(u16),
+    MethodType(u16),
+    Dynamic(u16),
+    Module(u16),
+    /// The slot after a long or double, or the unused slot 0
+    Unusable,
+}
+
+struct ConstantPool(Vec<Constant>);
+impl ConstantPool {
+    fn utf8(&self, index: u16) -> Result<&str, JvmError> {
+        match self.0.get(index as usize) {
+            Some(Constant::Utf8(s)) => Ok(s),
+            _ => Err(JvmError::Malformed("expected a UTF-8 constant")),
+        }
+    }
+    fn class(&self, index: u16) -> Result<String, JvmError> {
+        match self.0.get(index as usize) {
+            Some(Constant::Class(name)) => Ok(self.utf8(*name)?.replace('/', ".")),
+            _ => Err(JvmError::Malformed("expected a class constant")),
+        }
+    }
+    /// Produce a human-readable version of a constant for display
+    fn describe(&self, index: u16) -> Result<String, JvmError> {
+        self.describe_nested(index, 0)
+    }
+    /// Produce a human-readable version of a constant that is referenced by other constants
+    ///
+    /// The deepest valid nesting is a method handle's reference's name and type, so anything deeper is a cycle
+    fn describe_nested(&self, index: u16, depth: u32) -> Result<String, JvmError> {
+        if depth > 2 {
+            return Err(JvmError::Malformed("constant pool references are circular"));
+        }
+        Ok(
+            match self
+                .0
+                .get(index as usize)
+                .ok_or(JvmError::Malformed("constant pool index out of range"))?
+            {
+                Constant::Utf8(s) => s.clone(),
+                Constant::Integer(v) => format!("{}", v),
+                Constant::Float(v) => format!("{}f", v),
+                Constant::Long(v) => format!("{}L", v),
+                Constant::Double(v) => format!("{}d", v),
+                Constant::Class(_) => self.class(index)?,
+                Constant::String(s) => format!("{:?}", self.utf8(*s)?),
+                Constant::Reference(class, name_and_type) => {
+                    format!(
+                        "{}.{}",
+                        self.class(*class)?,
+                        self.describe_nested(*name_and_type, depth + 1)?
+                    )
+                }
+                Constant::NameAndType(name, descriptor) => {
+                    format!("{}{}", self.utf8(*name)?, self.utf8(*descriptor)?)
+                }
+                Constant::MethodHandle(reference) => self.describe_nested(*reference, depth + 1)?,
+                Constant::MethodType(descriptor) => self.utf8(*descriptor)?.to_string(),
+                Constant::Dynamic(name_and_type) => {
+                    self.describe_nested(*name_and_type, depth + 1)?
+                }
+                Constant::Module(name) => self.utf8(*name)?.to_string(),
+                Constant::Unusable => return Err(JvmError::Malformed("unusable constant")),
+            },
+        )
+    }
+}
+
+fn parse_class(data: &[u8], funcs: &mut BTreeMap<String, JvmMethod>) -> Result<(), JvmError> {
+    let mut cursor = Cursor { data, position: 8 };
+    let count = cursor.u16()?;
+    let mut constants = vec![Constant::Unusable];
+    while constants.len() < count as usize {
+        let tag = cursor.u8()?;
+        let constant = match tag {
+            1 => {
+                let length = cursor.u16()? as usize;
+                Constant::Utf8(String::from_utf8_lossy(cursor.bytes(length)?).into_owned())
+            }
+            3 => Constant::Integer(cursor.u32()? as i32),
+            4 => Constant::Float(f32::from_bits(cursor.u32()?)),
+            5 | 6 => {
+                let value = ((cursor.u32()? as u64) << 32) | cursor.u32()? as u64;
+                constants.push(if tag == 5 {
+                    Constant::Long(value as i64)
+                } else {
+                    Constant::Double(f64::from_bits(value))
+                });
+                Constant::Unusable
+            }
+            7 => Constant::Class(cursor.u16()?),
+            8 => Constant::String(cursor.u16()?),
+            9..=11 => Constant::Reference(cursor.u16()?, cursor.u16()?),
+            12 => Constant::NameAndType(cursor.u16()?, cursor.u16()?),
+            15 => {
+                cursor.u8()?;
+                Constant::MethodHandle(cursor.u16()?)
+            }
+            16 => Constant::MethodType(cursor.u16()?),
+            17 | 18 => {
+                cursor.u16()?;
+                Constant::Dynamic(cursor.u16()?)
+            }
+            19 | 20 => Constant::Module(cursor.u16()?),
+            _ => return Err(JvmError::Malformed("unknown constant pool tag")),
+        };
+        constants.push(constant);
+    }
+    let constants = ConstantPool(constants);
+    cursor.u16()?;
+    let class_name = constants.class(cursor.u16()?)?;
+    cursor.u16()?;
+    let interfaces = cursor.u16()? as usize;
+    cursor.bytes(interfaces * 2)?;
+    let fields = cursor.u16()?;
+    for _ in 0..fields {
+        cursor.bytes(6)?;
+        for _ in 0..cursor.u16()? {
+            cursor.u16()?;
+            let length = cursor.u32()? as usize;
+            cursor.bytes(length)?;
+        }
+    }
+    let methods = cursor.u16()?;
+    for _ in 0..methods {
+        cursor.u16()?;
+        let name = format!(
+            "{}.{}{}",
+            class_name,
+            constants.utf8(cursor.u16()?)?,
+            constants.utf8(cursor.u16()?)?
+        );
+        for _ in 0..cursor.u16()? {
+            let attribute = constants.utf8(cursor.u16()?)?;
+            let length = cursor.u32()? as usize;
+            let body = cursor.bytes(length)?;
+            // Abstract and native methods have no code and are skipped
+            if attribute == "Code" {
+                let blocks = parse_code(body, &constants)?;
+                funcs.insert(
+                    name.clone(),
+                    JvmMethod {
+                        blocks,
+                        name: name.clone(),
+                    },
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_code(body: &[u8], constants: &ConstantPool) -> Result<Vec<JvmBlock>, JvmError> {
+    let mut cursor = Cursor {
+        data: body,
+        position: 4,
+    };
+    let length = cursor.u32()? as usize;
+    let code = cursor.bytes(length)?;
+    // Blocks start at branch targets and at the boundaries of exception handler ranges
+    let mut leaders = BTreeSet::new();
+    for _ in 0..cursor.u16()? {
+        leaders.insert(cursor.u16()? as usize);
+        leaders.insert(cursor.u16()? as usize);
+        leaders.insert(cursor.u16()? as usize);
+        cursor.u16()?;
+    }
+
+    let mut instructions = Vec::new();
+    let mut cursor = Cursor {
+        data: code,
+        position: 0,
+    };
+    while cursor.position < code.len() {
+        let pc = cursor.position;
+        let relative = |offset: i64| (pc as i64 + offset) as usize;
+        let opcode = cursor.u8()?;
+        let (mnemonic, size) = *OPCODES
+            .get(opcode as usize)
+            .ok_or(JvmError::Malformed("unknown opcode"))?;
+        let mut reference = None;
+        let mut targets = Vec::new();
+        let operands = match opcode {
+            WIDE => {
+                let modified = cursor.u8()?;
+                let index = cursor.u16()?;
+                if modified == 0x84 {
+                    format!(
+                        "{} {}, {}",
+                        OPCODES[modified as usize].0,
+                        index,
+                        cursor.u16()? as i16
+                    )
+                } else {
+                    format!(
+                        "{} {}",
+                        OPCODES
+                            .get(modified as usize)
+                            .ok_or(JvmError::Malformed("unknown opcode"))?
+                            .0,
+                        index
+                    )
+                }
+            }
+            TABLESWITCH | LOOKUPSWITCH => {
+                cursor.position = cursor.position.next_multiple_of(4);
+                targets.push(relative(cursor.u32()? as i32 as i64));
+                let mut cases = Vec::new();
+                if opcode == TABLESWITCH {
+                    let low = cursor.u32()? as i32;
+                    let high = cursor.u32()? as i32;
+                    for value in low..=high {
+                        let target = relative(cursor.u32()? as i32 as i64);
+                        cases.push(format!("{}: {}", value, target));
+                        targets.push(target);
+                    }
+                } else {
+                    for _ in 0..cursor.u32()? {
+                        let value = cursor.u32()? as i32;
+                        let target = relative(cursor.u32()? as i32 as i64);
+                        cases.push(format!("{}: {}", value, target));
+                        targets.push(target);
+                    }
+                }
+                cases.push(format!("default: {}", targets[0]));
+                format!("[{}]", cases.join(", "))
+            }
+            // Constant pool references
+            0x12 => {
+                reference = Some(constants.describe(cursor.u8()? as u16)?);
+                String::new()
+            }
+            0x13 | 0x14 | 0xb2..=0xb8 | 0xbb | 0xbd | 0xc0 | 0xc1 => {
+                reference = Some(constants.describe(cursor.u16()?)?);
+                String::new()
+            }
+            0xb9 | 0xba => {
+                reference = Some(constants.describe(cursor.u16()?)?);
+                cursor.u16()?;
+                String::new()
+            }
+            0xc5 => {
+                reference = Some(constants.describe(cursor.u16()?)?);
+                format!("{}", cursor.u8()?)
+            }
+            0xbc => {
+                reference = Some(
+                    match cursor.u8()? {
+                        4 => "boolean",
+                        5 => "char",
+                        6 => "float",
+                        7 => "double",
+                        8 => "byte",
+                        9 => "short",
+                        10 => "int",
+                        11 => "long",
+                        _ => return Err(JvmError::Malformed("unknown array type")),
+                    }
+                    .to_string(),
+                );
+                String::new()
+            }
+            // Branches
+            0x99..=0xa8 | 0xc6 | 0xc7 => {
+                let target = relative(cursor.u16()? as i16 as i64);
+                targets.push(target);
+                format!("{}", target)
+            }
+            0xc8 | 0xc9 => {
+                let target = relative(cursor.u32()? as i32 as i64);
+                targets.push(target);
+                format!("{}", target)
+            }
+            0x10 => format!("{}", cursor.u8()? as i8),
+            0x11 => format!("{}", cursor.u16()? as i16),
+            0x84 => format!("{}, {}", cursor.u8()?, cursor.u8()? as i8),
+            _ => {
+                // Local variable indices
+                match size {
+                    0 => String::new(),
+                    1 => format!("{}", cursor.u8()?),
+                    _ => return Err(JvmError::Malformed(mnemonic)),
+                }
+            }
+        };
+        let instruction = JvmInstruction {
+            opcode,
+            reference,
+            operands,
+        };
+        if instruction.is_flow_control() {
+            leaders.insert(cursor.position);
+        }
+        leaders.extend(targets);
+        instructions.push((pc, instruction));
+    }
+
+    let mut blocks = Vec::new();
+    let mut current = JvmBlock {
+        instructions: Vec::new(),
+        pc: 0,
+        terminator: None,
+    };
+    for (pc, instruction) in instructions {
+        if pc != current.pc && leaders.contains(&pc) {
+            blocks.push(std::mem::replace(
+                &mut current,
+                JvmBlock {
+                    instructions: Vec::new(),
+                    pc,
+                    terminator: None,
+                },
+            ));
+        }
+        if instruction.is_flow_control() {
+            current.terminator = Some(instruction);
+        } else {
+            current.instructions.push(instruction);
+        }
+    }
+    blocks.push(current);
+    Ok(blocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BasicBlock, Function, Instruction, Program};
+
+    const CLASS: &str = "Counter.class";
+    const JAR: &str = "counter.jar";
+
+    fn path(file: &str) -> std::path::PathBuf {
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata")
+            .join(file)
+    }
+
+    fn parse(file: &str) -> super::Jvm {
+        super::Jvm::parse(path(file), ()).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// The blocks of a method, with the rendered instructions in each, the terminator last
+    fn rendered(method: &super::JvmMethod) -> Vec<(String, Vec<String>)> {
+        method
+            .blocks()
+            .map(|block| {
+                (
+                    block.name().into_owned(),
+                    (0..block.len())
+                        .map(|index| block.get(index).render().into_owned())
+                        .chain(block.terminator().iter().map(|t| t.render().into_owned()))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn methods_and_blocks() {
+        let program = parse(CLASS);
+        let names: Vec<_> = program
+            .functions()
+            .map(|method| method.name().into_owned())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "example.Counter.<init>()V",
+                "example.Counter.add(I)I",
+                "example.Counter.describe(I)Ljava/lang/String;",
+                "example.Counter.sum([J)J"
+            ]
+        );
+        // Blocks start at every case of the switch and the join after the conditional
+        let describe = rendered(
+            program
+                .get("example.Counter.describe(I)Ljava/lang/String;")
+                .unwrap(),
+        );
+        let blocks: Vec<_> = describe.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(blocks, ["0", "28", "31", "34", "38", "43", "45"]);
+        assert_eq!(
+            describe[0].1,
+            ["iload_0", "lookupswitch [0: 28, 1: 31, default: 34]"]
+        );
+        assert_eq!(describe[1].1, ["ldc \"none\"", "areturn"]);
+        assert_eq!(
+            rendered(program.get("example.Counter.<init>()V").unwrap()),
+            [(
+                "0".to_string(),
+                vec![
+                    "aload_0".to_string(),
+                    "invokespecial java.lang.Object.<init>()V".to_string(),
+                    "return".to_string()
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn jar_contains_class() {
+        let class = parse(CLASS);
+        let jar = parse(JAR);
+        for (class, jar) in class.functions().zip(jar.functions()) {
+            assert_eq!(class.name(), jar.name());
+            assert_eq!(rendered(class), rendered(jar));
+        }
+        assert_eq!(jar.functions().count(), 4);
+        assert!(matches!(
+            super::Jvm::parse(path("Counter.java"), ()),
+            Err(super::JvmError::Unrecognized)
+        ));
+    }
+
+    #[test]
+    fn families_give_partial_credit() {
+        let program = parse(CLASS);
+        let sum = program.get("example.Counter.sum([J)J").unwrap();
+        let body = sum.blocks().nth(2).unwrap();
+        let instruction = |index| body.get(index);
+        // `lload_1` and `lload 6` are the same operation on different local variables
+        assert_eq!(instruction(4).score(instruction(5)), 4);
+        // `lstore 6` and `lload 6` are not related
+        assert_eq!(instruction(3).score(instruction(5)), 0);
+        // `iload 5` and `lload 6` are loads of different types
+        assert_eq!(instruction(1).score(instruction(5)), 3);
+        let add = program.get("example.Counter.add(I)I").unwrap();
+        let block = add.blocks().next().unwrap();
+        // Reading and writing the same field are related, but not the same
+        assert_eq!(block.get(2).score(block.get(7)), 4);
+        assert_eq!(block.get(2).score(block.get(5)), 3);
+        assert_eq!(block.get(2).score(block.get(6)), 0);
+    }
+
+    #[test]
+    fn truncated_class() {
+        let class = std::fs::read(path(CLASS)).unwrap();
+        let mut complete = std::collections::BTreeMap::new();
+        super::parse_class(&class, &mut complete).unwrap_or_else(|e| panic!("{}", e));
+        // Only the class's own attributes, after the methods, are not read, so any other truncation is an error
+        for length in 0..class.len() {
+            let mut funcs = std::collections::BTreeMap::new();
+            if super::parse_class(&class[..length], &mut funcs).is_ok() {
+                assert!(funcs.keys().eq(complete.keys()), "{} bytes", length);
+            }
+        }
+    }
+
+    #[test]
+    fn circular_constants() {
+        use super::Constant;
+        let constants = super::ConstantPool(vec![
+            Constant::Unusable,
+            Constant::Utf8("example/Counter".to_string()),
+            Constant::Class(1),
+            Constant::Reference(2, 3),
+            Constant::MethodHandle(4),
+            Constant::Utf8("count".to_string()),
+            Constant::Utf8("I".to_string()),
+            Constant::NameAndType(5, 6),
+            Constant::Reference(2, 7),
+            Constant::MethodHandle(8),
+        ]);
+        assert_eq!(
+            constants.describe(9).unwrap_or_else(|e| panic!("{}", e)),
+            "example.Counter.countI"
+        );
+        for index in [3, 4] {
+            assert!(matches!(
+                constants.describe(index),
+                Err(super::JvmError::Malformed(_))
+            ));
+        }
+    }
+}
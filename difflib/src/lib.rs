@@ -1,5 +1,14 @@
+pub mod asm;
 pub mod goblin_yax;
+pub mod inputs;
+pub mod jvm;
+#[cfg(feature = "llvm")]
 pub mod llvm;
+#[cfg(feature = "llvm-text")]
+pub mod llvm_text;
+pub mod mixed;
+pub mod ptx;
+pub mod pyc;
 use std::{
     borrow::Cow,
     collections::{BTreeMap, BTreeSet},
@@ -8,6 +17,7 @@ use std::{
 };
 
 /// Indicates what function(s) should be compared from the programs provided
+#[derive(Clone)]
 pub enum FunctionName {
     /// Compare two functions of different names
     ///
@@ -19,6 +29,28 @@ pub enum FunctionName {
     Unspecified,
 }
 
+/// Types that can be shared between threads when functions are compared in parallel (with the `rayon` feature); otherwise, any type
+#[cfg(feature = "rayon")]
+pub trait MaybeSync: Sync {}
+#[cfg(feature = "rayon")]
+impl<T: Sync + ?Sized> MaybeSync for T {}
+/// Types that can be shared between threads when functions are compared in parallel (with the `rayon` feature); otherwise, any type
+#[cfg(not(feature = "rayon"))]
+pub trait MaybeSync {}
+#[cfg(not(feature = "rayon"))]
+impl<T: ?Sized> MaybeSync for T {}
+
+/// Types that can be sent between threads when functions are compared in parallel (with the `rayon` feature); otherwise, any type
+#[cfg(feature = "rayon")]
+pub trait MaybeSend: Send {}
+#[cfg(feature = "rayon")]
+impl<T: Send + ?Sized> MaybeSend for T {}
+/// Types that can be sent between threads when functions are compared in parallel (with the `rayon` feature); otherwise, any type
+#[cfg(not(feature = "rayon"))]
+pub trait MaybeSend {}
+#[cfg(not(feature = "rayon"))]
+impl<T: ?Sized> MaybeSend for T {}
+
 /// A source program that can be used as input for diffing
 ///
 /// Although this is called "program", it represent any collection of files and could be a complete program, a library, a partially compiled object file, methods of a single class, etc.
@@ -38,8 +70,25 @@ pub trait Program: Sized {
     /// Parsing an input file and produce a program for diffing
     fn parse(file: impl AsRef<Path>, options: Self::ParseOptions)
         -> Result<Self, Self::ParseError>;
+    /// Parse a program that is already in memory (_e.g._, read from standard input) and produce a program for diffing
+    ///
+    /// Text formats must be UTF-8.
+    fn parse_bytes(bytes: &[u8], options: Self::ParseOptions) -> Result<Self, Self::ParseError>;
+    /// Select one architecture from a file that can contain several (_e.g._, a slice of a universal MachO binary)
+    ///
+    /// If the format has no notion of architecture or the name is not recognized, `None` is returned.
+    fn select_architecture(
+        _options: Self::ParseOptions,
+        _architecture: &str,
+    ) -> Option<Self::ParseOptions> {
+        None
+    }
     /// Retrive a function by name, if it exists
     fn get(&self, name: &str) -> Option<&Self::Function>;
+    /// The names of the functions a name could refer to when it is ambiguous (_e.g._, a static function defined in several archive members), so [Program::get] found nothing
+    fn candidates(&self, _name: &str) -> Vec<String> {
+        Vec::new()
+    }
     /// Iterate over all functions available
     fn functions<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Self::Function> + 'a>;
 }
@@ -47,7 +96,7 @@ pub trait Program: Sized {
 /// A function for diffing
 ///
 /// The diff works on the control flow graph level, so a function is a collection of basic blocks
-pub trait Function {
+pub trait Function: MaybeSync {
     /// The type of a basic block in a function
     type BasicBlock: BasicBlock;
     /// The basic blocks associated with this function
@@ -61,7 +110,7 @@ pub trait Function {
 /// A basic block in a function
 ///
 /// Each basic block is a list of instructions that terminates in a flow control instruction (_e.g._, conditional branch). Functionally, terminal instructions and the body instructions can be different types, though they can be the same.
-pub trait BasicBlock {
+pub trait BasicBlock: MaybeSync {
     /// The type of a body instruction
     type Instruction: Instruction;
     /// The type of the terminal (flow control) instruction
@@ -72,6 +121,10 @@ pub trait BasicBlock {
     fn get(&self, index: usize) -> &Self::Instruction;
     /// The number of body instructions in the block (_i.e._, excluding the terminal instruction)
     fn len(&self) -> usize;
+    /// Whether the block has no body instructions (_i.e._, it is only the terminal instruction)
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
     /// The name of the block
     ///
     /// This is arbitrary and if meaningful names are not available to display, using an incrementing number is acceptable.
@@ -82,7 +135,7 @@ pub trait BasicBlock {
 /// A single instruction
 ///
 /// This trait is used for both body and terminal instructions
-pub trait Instruction {
+pub trait Instruction: MaybeSync {
     /// A score threshold where two instructions are considered an exact match. That is, they should not be highlighted as a diff
     const EQUIVALENT: i32;
     /// Compute the similarity score for two instructions
@@ -91,6 +144,36 @@ pub trait Instruction {
     fn score(&self, other: &Self) -> i32;
     /// Display the instruction for the user to consume
     fn render<'a>(&self) -> Cow<'a, str>;
+    /// The broad kind of operation this instruction performs
+    ///
+    /// This is used to align instructions from programs in different formats (_e.g._, LLVM IR and the machine code generated from it), where the instructions cannot be compared directly.
+    fn class(&self) -> InstructionClass;
+    /// The source location (_e.g._, `foo.c:12`) this instruction was compiled from, if debugging information is available
+    fn location(&self) -> Option<Cow<'_, str>> {
+        None
+    }
+}
+/// A format-independent classification of instructions
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum InstructionClass {
+    /// Integer arithmetic, including address computation and integer conversions
+    Arith,
+    /// Bitwise operations, shifts, and selects
+    Logic,
+    /// Loads, stores, moves, and atomic operations
+    Memory,
+    /// Comparisons and setting flags
+    Compare,
+    /// Function calls
+    Call,
+    /// Jumps, branches, and returns
+    Branch,
+    /// Scalar floating point operations and conversions
+    Float,
+    /// SIMD and vector operations
+    Vector,
+    /// Anything that does not fit another class
+    Other,
 }
 impl Instruction for () {
     const EQUIVALENT: i32 = 0;
@@ -101,6 +184,10 @@ impl Instruction for () {
     fn render<'a>(&self) -> Cow<'a, str> {
         Cow::Borrowed("")
     }
+
+    fn class(&self) -> InstructionClass {
+        InstructionClass::Other
+    }
 }
 impl<T: Instruction> Instruction for Option<T> {
     const EQUIVALENT: i32 = T::EQUIVALENT;
@@ -119,16 +206,39 @@ impl<T: Instruction> Instruction for Option<T> {
             None => Cow::Borrowed("<no instruction>"),
         }
     }
+
+    fn class(&self) -> InstructionClass {
+        match self {
+            Some(inst) => inst.class(),
+            None => InstructionClass::Other,
+        }
+    }
+
+    fn location(&self) -> Option<Cow<'_, str>> {
+        self.as_ref().and_then(|inst| inst.location())
+    }
 }
 
 /// The output of a diff process
-pub trait IntoDiffResult {
+pub trait IntoDiffResult: MaybeSend {
     /// The type of a single row comparing two instructions
     type Row;
     // Create a row that describes two basic blocks being compared given their names
     fn block_row(left: Cow<str>, right: Cow<str>) -> Self::Row;
     /// Create a row comparing two instructions and how they relate
     fn row(left: Cow<str>, right: Cow<str>, kind: MatchDirection) -> Self::Row;
+    /// Create a row comparing two instructions and how they relate, along with the source locations (_e.g._, `foo.c:12`) they were compiled from, if known
+    ///
+    /// By default, the locations are discarded.
+    fn row_with_location(
+        left: Cow<str>,
+        right: Cow<str>,
+        kind: MatchDirection,
+        _left_location: Option<Cow<str>>,
+        _right_location: Option<Cow<str>>,
+    ) -> Self::Row {
+        Self::row(left, right, kind)
+    }
     /// Create a complete function comparison given their names and the individual rows of their instructions
     fn function(left_name: Cow<str>, right_name: Cow<str>, rows: Vec<Self::Row>) -> Self;
 }
@@ -170,6 +280,8 @@ pub enum MatchDirection {
 pub enum Error<E> {
     /// Functions could not be located from the input programs
     NoMatch(FunctionLocation),
+    /// A function name matches several functions; the name and the names of the functions it could refer to are included
+    Ambiguous(FunctionLocation, String, Vec<String>),
     /// The input file could not be parsed
     ParseError(FunctionLocation, E),
 }
@@ -177,6 +289,8 @@ pub enum Error<E> {
 pub enum FormatError {
     /// Functions could not be located from the input programs
     NoMatch(FunctionLocation),
+    /// A function name matches several functions; the name and the names of the functions it could refer to are included
+    Ambiguous(FunctionLocation, String, Vec<String>),
     /// The input file could not be parsed
     ParseError(FunctionLocation, String),
     /// The assembly format is not recognized
@@ -187,6 +301,7 @@ impl<E: Display> From<Error<E>> for FormatError {
     fn from(input: Error<E>) -> Self {
         match input {
             Error::NoMatch(l) => FormatError::NoMatch(l),
+            Error::Ambiguous(l, name, candidates) => FormatError::Ambiguous(l, name, candidates),
             Error::ParseError(l, e) => FormatError::ParseError(l, format!("{}", e)),
         }
     }
@@ -199,201 +314,573 @@ pub fn compute_diff<P: Program, D: IntoDiffResult>(
     name: FunctionName,
     options: P::ParseOptions,
 ) -> Result<(bool, Vec<D>), Error<P::ParseError>> {
-    fn find_functions<'a, T, P: Program>(
-        left: Option<&'a T>,
-        right: Option<&'a T>,
-    ) -> Result<Box<dyn Iterator<Item = (&'a T, &'a T)> + 'a>, Error<P::ParseError>> {
-        match (left, right) {
-            (Some(left), Some(right)) => Ok(Box::new(std::iter::once((left, right)))),
-            (None, Some(_)) => Err(Error::NoMatch(FunctionLocation::Left)),
-            (Some(_), None) => Err(Error::NoMatch(FunctionLocation::Right)),
+    // A collection of files (_e.g._, a directory) can't be parsed as one program, so it is only the same program on both sides if it is a single file
+    if left.as_ref() == right.as_ref()
+        && inputs::expand(left.as_ref())
+            .map(|files| inputs::is_single(&files))
+            .unwrap_or(true)
+    {
+        return compute_diff_in_file::<P, D>(left, name, options);
+    }
+    compute_diff_with_options::<P, D>(left, options, right, options, name)
+}
+
+/// Parse one program and compare functions within it (_e.g._, a specialised version of a function against the generic one)
+///
+/// The program is only parsed once. Parsing errors are reported in [FunctionLocation::Both].
+pub fn compute_diff_in_file<P: Program, D: IntoDiffResult>(
+    file: impl AsRef<Path>,
+    name: FunctionName,
+    options: P::ParseOptions,
+) -> Result<(bool, Vec<D>), Error<P::ParseError>> {
+    let program =
+        P::parse(file, options).map_err(|e| Error::ParseError(FunctionLocation::Both, e))?;
+    let pairs = pair_functions(
+        name,
+        |name| Lookup::in_program(&program, name),
+        |name| Lookup::in_program(&program, name),
+        program.functions(),
+        program.functions(),
+    )?;
+    Ok(diff_functions(P::GAP, pairs))
+}
+
+/// Parse and compare two programs that are parsed with different options (_e.g._, two slices of the same universal binary)
+pub fn compute_diff_with_options<P: Program, D: IntoDiffResult>(
+    left: impl AsRef<Path>,
+    left_options: P::ParseOptions,
+    right: impl AsRef<Path>,
+    right_options: P::ParseOptions,
+    name: FunctionName,
+) -> Result<(bool, Vec<D>), Error<P::ParseError>> {
+    diff_sources::<P, D>(
+        Source::File(left.as_ref()),
+        left_options,
+        Source::File(right.as_ref()),
+        right_options,
+        name,
+    )
+}
 
-            (None, None) => Err(Error::NoMatch(FunctionLocation::Both)),
+/// Parse and compare two programs that are already in memory (_e.g._, read from standard input)
+pub fn compute_diff_from_bytes<P: Program, D: IntoDiffResult>(
+    left: &[u8],
+    right: &[u8],
+    name: FunctionName,
+    options: P::ParseOptions,
+) -> Result<(bool, Vec<D>), Error<P::ParseError>> {
+    diff_sources::<P, D>(
+        Source::Bytes(left),
+        options,
+        Source::Bytes(right),
+        options,
+        name,
+    )
+}
+
+/// Where the program for one side of a diff comes from
+#[derive(Clone, Copy)]
+pub(crate) enum Source<'a> {
+    /// A file, which may be a collection of files (_e.g._, a directory)
+    File(&'a Path),
+    /// A program already in memory
+    Bytes(&'a [u8]),
+}
+impl<'a> Source<'a> {
+    pub(crate) fn parse<P: Program>(self, options: P::ParseOptions) -> Result<P, P::ParseError> {
+        match self {
+            Source::File(path) => P::parse(path, options),
+            Source::Bytes(bytes) => P::parse_bytes(bytes, options),
         }
     }
-    let left = P::parse(left, options).map_err(|e| Error::ParseError(FunctionLocation::Left, e))?;
-    let right =
-        P::parse(right, options).map_err(|e| Error::ParseError(FunctionLocation::Right, e))?;
-    let pairs = match name {
-        FunctionName::Different(left_name, right_name) => {
-            find_functions::<_, P>(left.get(left_name.as_str()), right.get(right_name.as_str()))?
+}
+
+/// One side of a diff once any collection of files has been expanded
+enum Side<'a> {
+    Single(Source<'a>),
+    Many(Vec<inputs::InputFile>),
+}
+impl<'a> Side<'a> {
+    fn expand(source: Source<'a>) -> Result<Self, String> {
+        Ok(match source {
+            Source::File(path) => {
+                let files = inputs::expand(path)?;
+                if inputs::is_single(&files) {
+                    Side::Single(source)
+                } else {
+                    Side::Many(files)
+                }
+            }
+            Source::Bytes(_) => Side::Single(source),
+        })
+    }
+    /// Parse every file on this side, labelled by the file it came from
+    fn parse<T, E: Display>(
+        &self,
+        mut parse: impl FnMut(Source) -> Result<T, E>,
+    ) -> Result<Vec<(Option<&str>, T)>, String> {
+        match self {
+            Side::Single(source) => Ok(vec![(None, parse(*source).map_err(|e| e.to_string())?)]),
+            Side::Many(files) => inputs::parse_all(files, |path| parse(Source::File(path))),
         }
-        FunctionName::Same(name) => {
-            find_functions::<_, P>(left.get(name.as_str()), right.get(name.as_str()))?
+    }
+}
+
+fn diff_sources<P: Program, D: IntoDiffResult>(
+    left: Source,
+    left_options: P::ParseOptions,
+    right: Source,
+    right_options: P::ParseOptions,
+    name: FunctionName,
+) -> Result<(bool, Vec<D>), Error<P::ParseError>> {
+    let left: P = left
+        .parse(left_options)
+        .map_err(|e| Error::ParseError(FunctionLocation::Left, e))?;
+    let right: P = right
+        .parse(right_options)
+        .map_err(|e| Error::ParseError(FunctionLocation::Right, e))?;
+    let pairs = pair_functions(
+        name,
+        |name| Lookup::in_program(&left, name),
+        |name| Lookup::in_program(&right, name),
+        left.functions(),
+        right.functions(),
+    )?;
+    Ok(diff_functions(P::GAP, pairs))
+}
+
+/// A function looked up by name
+pub(crate) enum Lookup<'a, F> {
+    Found(&'a F),
+    Missing,
+    /// The name matches several functions, which have these names
+    Ambiguous(Vec<String>),
+}
+impl<'a, F: Function> Lookup<'a, F> {
+    /// Look up a function in a program
+    fn in_program<P: Program<Function = F>>(program: &'a P, name: &str) -> Self {
+        match program.get(name) {
+            Some(function) => Lookup::Found(function),
+            None => match program.candidates(name) {
+                candidates if candidates.is_empty() => Lookup::Missing,
+                candidates => Lookup::Ambiguous(candidates),
+            },
         }
+    }
+}
+
+/// Select the pairs of functions to compare from two programs
+fn pair_functions<'a, F: Function + 'a, E>(
+    name: FunctionName,
+    get_left: impl Fn(&str) -> Lookup<'a, F>,
+    get_right: impl Fn(&str) -> Lookup<'a, F>,
+    left_functions: impl Iterator<Item = &'a F>,
+    right_functions: impl Iterator<Item = &'a F>,
+) -> Result<Vec<(&'a F, &'a F)>, Error<E>> {
+    fn find_functions<'a, T, E>(
+        left_name: &str,
+        left: Lookup<'a, T>,
+        right_name: &str,
+        right: Lookup<'a, T>,
+    ) -> Result<Vec<(&'a T, &'a T)>, Error<E>> {
+        match (left, right) {
+            (Lookup::Found(left), Lookup::Found(right)) => Ok(vec![(left, right)]),
+            (Lookup::Ambiguous(candidates), _) => Err(Error::Ambiguous(
+                FunctionLocation::Left,
+                left_name.to_string(),
+                candidates,
+            )),
+            (_, Lookup::Ambiguous(candidates)) => Err(Error::Ambiguous(
+                FunctionLocation::Right,
+                right_name.to_string(),
+                candidates,
+            )),
+            (Lookup::Missing, Lookup::Found(_)) => Err(Error::NoMatch(FunctionLocation::Left)),
+            (Lookup::Found(_), Lookup::Missing) => Err(Error::NoMatch(FunctionLocation::Right)),
+            (Lookup::Missing, Lookup::Missing) => Err(Error::NoMatch(FunctionLocation::Both)),
+        }
+    }
+    match name {
+        FunctionName::Different(left_name, right_name) => find_functions(
+            &left_name,
+            get_left(left_name.as_str()),
+            &right_name,
+            get_right(right_name.as_str()),
+        ),
+        FunctionName::Same(name) => find_functions(
+            &name,
+            get_left(name.as_str()),
+            &name,
+            get_right(name.as_str()),
+        ),
         FunctionName::Unspecified => {
-            let right: BTreeMap<_, _> = right.functions().map(|func| (func.name(), func)).collect();
-            let result: Vec<_> = left
-                .functions()
-                .filter_map(|left_fn| {
-                    right
-                        .get(left_fn.name().as_ref())
-                        .map(|&right_fn| (left_fn, right_fn))
-                })
+            let right: BTreeMap<_, _> = right_functions.map(|func| (func.name(), func)).collect();
+            let mut used_right = BTreeSet::new();
+            let mut unmatched_left = Vec::new();
+            let mut result = Vec::new();
+            for left_fn in left_functions {
+                match right.get_key_value(left_fn.name().as_ref()) {
+                    Some((right_name, &right_fn)) => {
+                        used_right.insert(right_name);
+                        result.push((left_fn, right_fn));
+                    }
+                    None => unmatched_left.push(left_fn),
+                }
+            }
+            unmatched_left.retain(|func| is_synthetic_name(&func.name()));
+            let unmatched_right = right
+                .iter()
+                .filter(|(name, _)| !used_right.contains(name) && is_synthetic_name(name))
+                .map(|(_, &func)| func)
                 .collect();
+            result.extend(pair_by_content(unmatched_left, unmatched_right));
             if result.is_empty() {
                 return Err(Error::NoMatch(FunctionLocation::Both));
             }
-            Box::new(result.into_iter())
+            Ok(result)
         }
-    };
-    let mut has_diff = true;
+    }
+}
+
+/// Check if a function name was made up by a parser because the function has no symbol (_e.g._, `sub_401000`), rather than coming from the program
+fn is_synthetic_name(name: &str) -> bool {
+    name.is_empty()
+        || name
+            .strip_prefix("sub_")
+            .map(|address| !address.is_empty() && address.chars().all(|c| c.is_ascii_hexdigit()))
+            .unwrap_or(false)
+}
+
+/// Pair functions whose names don't match by how similar their contents are
+///
+/// Functions without symbols (_e.g._, in stripped binaries) are named by their addresses, which will not agree between two builds, so only those functions are given. Each function is summarized by the classes of its instructions and the sizes of its blocks and the most similar functions are paired first; functions that are less than half similar are not paired.
+fn pair_by_content<'a, F: Function>(left: Vec<&'a F>, right: Vec<&'a F>) -> Vec<(&'a F, &'a F)> {
+    fn summarize<F: Function>(func: &F) -> BTreeMap<(Option<InstructionClass>, usize), usize> {
+        let mut summary = BTreeMap::new();
+        for block in func.blocks() {
+            *summary.entry((None, block.len())).or_default() += 1;
+            for index in 0..block.len() {
+                *summary
+                    .entry((Some(block.get(index).class()), 0))
+                    .or_default() += 1;
+            }
+            *summary
+                .entry((Some(block.terminator().class()), 0))
+                .or_default() += 1;
+        }
+        summary
+    }
+    let left_summaries: Vec<_> = left.iter().map(|func| summarize(*func)).collect();
+    let right_summaries: Vec<_> = right.iter().map(|func| summarize(*func)).collect();
+    let mut candidates = Vec::new();
+    for (left_id, left_summary) in left_summaries.iter().enumerate() {
+        for (right_id, right_summary) in right_summaries.iter().enumerate() {
+            let mut shared = 0;
+            let mut total = 0;
+            for (key, &count) in left_summary {
+                let other = right_summary.get(key).copied().unwrap_or(0);
+                shared += count.min(other);
+                total += count.max(other);
+            }
+            total += right_summary
+                .iter()
+                .filter(|(key, _)| !left_summary.contains_key(key))
+                .map(|(_, &count)| count)
+                .sum::<usize>();
+            if total > 0 && shared * 2 >= total {
+                candidates.push((shared as f64 / total as f64, left_id, right_id));
+            }
+        }
+    }
+    // The sort is stable, so ties are broken by the order of the functions
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let mut used_left = BTreeSet::new();
+    let mut used_right = BTreeSet::new();
+    let mut pairs = Vec::new();
+    for (_, left_id, right_id) in candidates {
+        if used_left.contains(&left_id) || used_right.contains(&right_id) {
+            continue;
+        }
+        used_left.insert(left_id);
+        used_right.insert(right_id);
+        pairs.push((left[left_id], right[right_id]));
+    }
+    pairs
+}
+
+/// Apply a function to every item, in parallel with the `rayon` feature; the results are in the same order as the items either way
+#[cfg(feature = "rayon")]
+fn map_items<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync + Send) -> Vec<R> {
+    use rayon::prelude::*;
+    items.par_iter().map(f).collect()
+}
+/// Apply a function to every item, in parallel with the `rayon` feature; the results are in the same order as the items either way
+#[cfg(not(feature = "rayon"))]
+fn map_items<T, R>(items: &[T], f: impl Fn(&T) -> R) -> Vec<R> {
+    items.iter().map(f).collect()
+}
+
+/// Align the blocks and instructions of pairs of functions
+fn diff_functions<F: Function, D: IntoDiffResult>(
+    gap: i32,
+    pairs: Vec<(&F, &F)>,
+) -> (bool, Vec<D>) {
+    let mut has_diff = false;
     let mut diffs = Vec::new();
-    for (left_func, right_func) in pairs {
-        let mut table = Vec::new();
-
-        let mut used_right_blocks = BTreeSet::new();
-        for left_block in left_func.blocks() {
-            let mut best_block = None;
-            for (right_id, right_block) in right_func.blocks().enumerate() {
-                let mut grid =
-                    vec![vec![(0i32, None); right_block.len() + 1]; left_block.len() + 1];
-                for i in 1..=left_block.len() {
-                    grid[i][0] = (i as i32 * -P::GAP, Some(MatchDirection::GapRight));
-                }
-                for i in 1..=right_block.len() {
-                    grid[0][i] = (i as i32 * -P::GAP, Some(MatchDirection::GapLeft));
-                }
-                for i in 0..left_block.len() {
-                    for j in 0..right_block.len() {
-                        let scores = [
-                            {
-                                let score = left_block.get(i).score(right_block.get(j));
-                                (
-                                    grid[i][j].0 + score,
-                                    Some(MatchDirection::Align(
-                                        score >= <<<P as Program>::Function as Function>::BasicBlock as BasicBlock>::Instruction::EQUIVALENT,
-                                    )),
-                                )
-                            },
-                            (grid[i + 1][j].0 - P::GAP, Some(MatchDirection::GapLeft)),
-                            (grid[i][j + 1].0 - P::GAP, Some(MatchDirection::GapRight)),
-                        ];
-                        grid[i + 1][j + 1] =
-                            scores.into_iter().max_by_key(|(score, _)| *score).unwrap();
-                    }
-                }
-                let (mut score, mut direction) = grid[left_block.len()][right_block.len()];
-                let terminator_score = left_block.terminator().score(right_block.terminator());
-                score += terminator_score;
-
-                if score > 0
-                    && best_block
-                        .as_ref()
-                        .map(|(best_score, _, _, _, _)| score > *best_score)
-                        .unwrap_or(true)
+    for (function_has_diff, diff) in map_items(&pairs, |(left_func, right_func)| {
+        diff_function(gap, *left_func, *right_func)
+    }) {
+        has_diff |= function_has_diff;
+        diffs.push(diff);
+    }
+    (has_diff, diffs)
+}
+
+/// Align the instructions of two blocks, returning the score, whether the terminators are equivalent, and the path through the instructions if the blocks are similar at all
+fn align_blocks<B: BasicBlock>(
+    gap: i32,
+    left_block: &B,
+    right_block: &B,
+) -> Option<(i32, bool, Vec<MatchDirection>)> {
+    let mut grid = vec![vec![(0i32, None); right_block.len() + 1]; left_block.len() + 1];
+    for (i, row) in grid.iter_mut().enumerate().skip(1) {
+        row[0] = (i as i32 * -gap, Some(MatchDirection::GapRight));
+    }
+    for (i, cell) in grid[0].iter_mut().enumerate().skip(1) {
+        *cell = (i as i32 * -gap, Some(MatchDirection::GapLeft));
+    }
+    for i in 0..left_block.len() {
+        for j in 0..right_block.len() {
+            let scores = [
                 {
-                    let mut path = Vec::new();
-                    let mut i = left_block.len();
-                    let mut j = right_block.len();
-                    loop {
-                        if let Some(direction) = direction {
-                            path.push(direction);
-                        }
-                        match direction {
-                            None => {
-                                break;
-                            }
-                            Some(MatchDirection::GapLeft) => {
-                                j -= 1;
-                            }
-                            Some(MatchDirection::GapRight) => {
-                                i -= 1;
-                            }
-                            Some(MatchDirection::Align(_)) => {
-                                i -= 1;
-                                j -= 1;
-                            }
-                        }
-                        direction = grid[i][j].1;
-                    }
+                    let score = left_block.get(i).score(right_block.get(j));
+                    (
+                        grid[i][j].0 + score,
+                        Some(MatchDirection::Align(score >= B::Instruction::EQUIVALENT)),
+                    )
+                },
+                (grid[i + 1][j].0 - gap, Some(MatchDirection::GapLeft)),
+                (grid[i][j + 1].0 - gap, Some(MatchDirection::GapRight)),
+            ];
+            grid[i + 1][j + 1] = scores.into_iter().max_by_key(|(score, _)| *score).unwrap();
+        }
+    }
+    let (mut score, mut direction) = grid[left_block.len()][right_block.len()];
+    let terminator_score = left_block.terminator().score(right_block.terminator());
+    score += terminator_score;
+    if score <= 0 {
+        return None;
+    }
+
+    let mut path = Vec::new();
+    let mut i = left_block.len();
+    let mut j = right_block.len();
+    loop {
+        if let Some(direction) = direction {
+            path.push(direction);
+        }
+        match direction {
+            None => {
+                break;
+            }
+            Some(MatchDirection::GapLeft) => {
+                j -= 1;
+            }
+            Some(MatchDirection::GapRight) => {
+                i -= 1;
+            }
+            Some(MatchDirection::Align(_)) => {
+                i -= 1;
+                j -= 1;
+            }
+        }
+        direction = grid[i][j].1;
+    }
+
+    path.reverse();
+    Some((score, terminator_score >= B::Terminator::EQUIVALENT, path))
+}
+
+/// Align the blocks and instructions of one pair of functions
+fn diff_function<F: Function, D: IntoDiffResult>(
+    gap: i32,
+    left_func: &F,
+    right_func: &F,
+) -> (bool, D) {
+    let mut has_diff = false;
+    let mut table = Vec::new();
+    let right_blocks: Vec<_> = right_func.blocks().collect();
 
-                    path.reverse();
-                    best_block = Some((
-                        score,
-                        terminator_score >= <<<P as Program>::Function as Function>::BasicBlock as BasicBlock>::Terminator::EQUIVALENT,
-                        right_id,
-                        right_block,
-                        path,
-                    ));
+    let mut used_right_blocks = BTreeSet::new();
+    for left_block in left_func.blocks() {
+        // Every candidate is aligned, but the best is chosen in order, so the first of equally good blocks is used
+        let mut best_block = None;
+        for (right_id, alignment) in map_items(&right_blocks, |right_block| {
+            align_blocks(gap, left_block, *right_block)
+        })
+        .into_iter()
+        .enumerate()
+        {
+            if let Some((score, terminator_equivalent, path)) = alignment {
+                if best_block
+                    .as_ref()
+                    .map(|(best_score, _, _, _)| score > *best_score)
+                    .unwrap_or(true)
+                {
+                    best_block = Some((score, terminator_equivalent, right_id, path));
                 }
             }
-            if let Some((_, terminator_equivalent, right_id, right_block, path)) = best_block {
-                used_right_blocks.insert(right_id);
-                table.push(D::block_row(left_block.name(), right_block.name()));
-                let mut i = 0;
-                let mut j = 0;
-                for direction in path {
-                    let (left, right) = match direction {
-                        MatchDirection::Align(equivalent) => {
-                            let result = (left_block.get(i).render(), right_block.get(j).render());
-                            if !equivalent {
-                                has_diff = true;
-                            }
-                            i += 1;
-                            j += 1;
-                            result
-                        }
-                        MatchDirection::GapLeft => {
-                            let result = (Cow::Borrowed(""), right_block.get(j).render());
-                            j += 1;
-                            has_diff = true;
-                            result
-                        }
-                        MatchDirection::GapRight => {
-                            let result = (left_block.get(i).render(), Cow::Borrowed(""));
+        }
+        if let Some((_, terminator_equivalent, right_id, path)) = best_block {
+            let right_block = right_blocks[right_id];
+            used_right_blocks.insert(right_id);
+            table.push(D::block_row(left_block.name(), right_block.name()));
+            let mut i = 0;
+            let mut j = 0;
+            for direction in path {
+                let (left, right, left_location, right_location) = match direction {
+                    MatchDirection::Align(equivalent) => {
+                        let result = (
+                            left_block.get(i).render(),
+                            right_block.get(j).render(),
+                            left_block.get(i).location(),
+                            right_block.get(j).location(),
+                        );
+                        if !equivalent {
                             has_diff = true;
-                            i += 1;
-                            result
                         }
-                    };
-                    table.push(D::row(left, right, direction));
-                }
-                table.push(D::row(
-                    left_block.terminator().render(),
-                    right_block.terminator().render(),
-                    MatchDirection::Align(terminator_equivalent),
-                ));
-            } else {
-                has_diff = true;
-                table.push(D::block_row(left_block.name(), Cow::Borrowed("")));
-                for instruction in 0..left_block.len() {
-                    table.push(D::row(
-                        left_block.get(instruction).render(),
-                        Cow::Borrowed(""),
-                        MatchDirection::GapRight,
-                    ));
-                }
-                table.push(D::row(
-                    left_block.terminator().render(),
-                    Cow::Borrowed(""),
-                    MatchDirection::Align(false),
+                        i += 1;
+                        j += 1;
+                        result
+                    }
+                    MatchDirection::GapLeft => {
+                        let result = (
+                            Cow::Borrowed(""),
+                            right_block.get(j).render(),
+                            None,
+                            right_block.get(j).location(),
+                        );
+                        j += 1;
+                        has_diff = true;
+                        result
+                    }
+                    MatchDirection::GapRight => {
+                        let result = (
+                            left_block.get(i).render(),
+                            Cow::Borrowed(""),
+                            left_block.get(i).location(),
+                            None,
+                        );
+                        has_diff = true;
+                        i += 1;
+                        result
+                    }
+                };
+                table.push(D::row_with_location(
+                    left,
+                    right,
+                    direction,
+                    left_location,
+                    right_location,
                 ));
             }
-        }
-        for (_, unused_block) in right_func
-            .blocks()
-            .enumerate()
-            .filter(|(id, _)| !used_right_blocks.contains(id))
-        {
+            table.push(D::row_with_location(
+                left_block.terminator().render(),
+                right_block.terminator().render(),
+                MatchDirection::Align(terminator_equivalent),
+                left_block.terminator().location(),
+                right_block.terminator().location(),
+            ));
+        } else {
             has_diff = true;
-            table.push(D::block_row(Cow::Borrowed(""), unused_block.name()));
-            for instruction in 0..unused_block.len() {
-                table.push(D::row(
+            table.push(D::block_row(left_block.name(), Cow::Borrowed("")));
+            for instruction in 0..left_block.len() {
+                table.push(D::row_with_location(
+                    left_block.get(instruction).render(),
                     Cow::Borrowed(""),
-                    unused_block.get(instruction).render(),
-                    MatchDirection::GapLeft,
+                    MatchDirection::GapRight,
+                    left_block.get(instruction).location(),
+                    None,
                 ));
             }
-            table.push(D::row(
+            table.push(D::row_with_location(
+                left_block.terminator().render(),
                 Cow::Borrowed(""),
-                unused_block.terminator().render(),
+                MatchDirection::Align(false),
+                left_block.terminator().location(),
+                None,
+            ));
+        }
+    }
+    for (_, unused_block) in right_func
+        .blocks()
+        .enumerate()
+        .filter(|(id, _)| !used_right_blocks.contains(id))
+    {
+        has_diff = true;
+        table.push(D::block_row(Cow::Borrowed(""), unused_block.name()));
+        for instruction in 0..unused_block.len() {
+            table.push(D::row_with_location(
+                Cow::Borrowed(""),
+                unused_block.get(instruction).render(),
                 MatchDirection::GapLeft,
+                None,
+                unused_block.get(instruction).location(),
             ));
         }
-        diffs.push(D::function(left_func.name(), right_func.name(), table));
+        table.push(D::row_with_location(
+            Cow::Borrowed(""),
+            unused_block.terminator().render(),
+            MatchDirection::GapLeft,
+            None,
+            unused_block.terminator().location(),
+        ));
     }
-    Ok((has_diff, diffs))
+    (
+        has_diff,
+        D::function(left_func.name(), right_func.name(), table),
+    )
+}
+
+/// An operation on a program whose type is selected at runtime from the name of a format
+pub(crate) trait FormatVisitor {
+    type Output;
+    fn visit<P: Program>(self, options: P::ParseOptions) -> Self::Output;
+}
+
+/// Apply an operation to the program type for a format, if the format name is recognized
+pub(crate) fn visit_format<V: FormatVisitor>(format: &str, visitor: V) -> Option<V::Output> {
+    Some(match format {
+        #[cfg(feature = "llvm")]
+        "ll" | "ll-ir" | "llir" => visitor.visit::<llvm_ir::Module>(true),
+        #[cfg(all(feature = "llvm-text", not(feature = "llvm")))]
+        "ll" | "ll-ir" | "llir" | "ll-text" => visitor.visit::<llvm_text::TextModule>(()),
+        #[cfg(all(feature = "llvm-text", feature = "llvm"))]
+        "ll-text" => visitor.visit::<llvm_text::TextModule>(()),
+        #[cfg(feature = "llvm")]
+        "ll-bc" | "llbc" => visitor.visit::<llvm_ir::Module>(false),
+        "arm64" | "aarch64" | "armv8" => {
+            visitor.visit::<goblin_yax::GoblinYax<yaxpeax_arm::armv8::a64::ARMv8>>(None)
+        }
+        "arm32" | "aarch32" | "armv7" => {
+            visitor.visit::<goblin_yax::GoblinYax<yaxpeax_arm::armv7::ARMv7>>(None)
+        }
+        "avr" => visitor.visit::<goblin_yax::GoblinYax<yaxpeax_avr::AVR>>(None),
+        "x86" | "x86-32" | "x86_32" | "i386" | "i686" => {
+            visitor.visit::<goblin_yax::GoblinYax<yaxpeax_x86::x86_32>>(None)
+        }
+        "x64" | "x86-64" | "x86_64" => {
+            visitor.visit::<goblin_yax::GoblinYax<yaxpeax_x86::x86_64>>(None)
+        }
+        "asm-x64" | "asm-x86-64" | "asm-x86_64" => {
+            visitor.visit::<asm::Assembly<asm::x86_64::X86_64>>(())
+        }
+        "asm-arm64" | "asm-aarch64" => visitor.visit::<asm::Assembly<asm::aarch64::AArch64>>(()),
+        "ptx" | "nvptx" => visitor.visit::<ptx::Ptx>(()),
+        "jvm" | "class" | "jar" => visitor.visit::<jvm::Jvm>(()),
+        "pyc" | "python" => visitor.visit::<pyc::PythonBytecode>(()),
+        _ => return None,
+    })
 }
 
 pub fn compute_diff_with_format<D: IntoDiffResult>(
@@ -402,48 +889,745 @@ pub fn compute_diff_with_format<D: IntoDiffResult>(
     right_file: impl AsRef<Path>,
     function_name: FunctionName,
 ) -> Result<(bool, Vec<D>), FormatError> {
-    match format {
-        "ll" | "ll-ir" | "llir" => {
-            compute_diff::<llvm_ir::Module, _>(left_file, right_file, function_name, true)
-                .map_err(|e| e.into())
-        }
-        "ll-bc" | "llbc" => {
-            compute_diff::<llvm_ir::Module, _>(left_file, right_file, function_name, false)
-                .map_err(|e| e.into())
-        }
-        "arm64" | "aarch64" | "armv8" => compute_diff::<
-            goblin_yax::GoblinYax<yaxpeax_arm::armv8::a64::ARMv8>,
-            _,
-        >(left_file, right_file, function_name, ())
-        .map_err(|e| e.into()),
-        "arm32" | "aarch32" | "armv7" => compute_diff::<
-            goblin_yax::GoblinYax<yaxpeax_arm::armv8::a64::ARMv8>,
-            _,
-        >(left_file, right_file, function_name, ())
-        .map_err(|e| e.into()),
-        "avr" => compute_diff::<goblin_yax::GoblinYax<yaxpeax_avr::AVR>, _>(
-            left_file,
-            right_file,
+    diff_sources_with_format(
+        format,
+        None,
+        Source::File(left_file.as_ref()),
+        None,
+        Source::File(right_file.as_ref()),
+        function_name,
+    )
+}
+
+/// Apply the architecture requested for a file, if any, to the options for parsing it
+fn select_architecture<P: Program>(
+    options: P::ParseOptions,
+    architecture: Option<&str>,
+) -> Result<P::ParseOptions, String> {
+    match architecture {
+        None => Ok(options),
+        Some(architecture) => P::select_architecture(options, architecture)
+            .ok_or_else(|| format!("architecture “{}” is not available", architecture)),
+    }
+}
+
+fn diff_sources_with_format<D: IntoDiffResult>(
+    format: &str,
+    left_architecture: Option<&str>,
+    left: Source,
+    right_architecture: Option<&str>,
+    right: Source,
+    function_name: FunctionName,
+) -> Result<(bool, Vec<D>), FormatError> {
+    struct Diff<'a, D> {
+        left_architecture: Option<&'a str>,
+        left: Source<'a>,
+        right_architecture: Option<&'a str>,
+        right: Source<'a>,
+        function_name: FunctionName,
+        output: std::marker::PhantomData<D>,
+    }
+    impl<'a, D: IntoDiffResult> FormatVisitor for Diff<'a, D> {
+        type Output = Result<(bool, Vec<D>), FormatError>;
+        fn visit<P: Program>(self, options: P::ParseOptions) -> Self::Output {
+            let left_options = select_architecture::<P>(options, self.left_architecture)
+                .map_err(|e| FormatError::ParseError(FunctionLocation::Left, e))?;
+            let right_options = select_architecture::<P>(options, self.right_architecture)
+                .map_err(|e| FormatError::ParseError(FunctionLocation::Right, e))?;
+            let left = Side::expand(self.left)
+                .map_err(|e| FormatError::ParseError(FunctionLocation::Left, e))?;
+            let right = Side::expand(self.right)
+                .map_err(|e| FormatError::ParseError(FunctionLocation::Right, e))?;
+            // The same file on both sides is only parsed once; collections of files are compared like any others
+            if let (Side::Single(Source::File(left)), Side::Single(Source::File(right))) =
+                (&left, &right)
+            {
+                if self.left_architecture == self.right_architecture && left == right {
+                    return compute_diff_in_file::<P, D>(left, self.function_name, left_options)
+                        .map_err(|e| e.into());
+                }
+            }
+            if let (Side::Single(left), Side::Single(right)) = (&left, &right) {
+                return diff_sources::<P, D>(
+                    *left,
+                    left_options,
+                    *right,
+                    right_options,
+                    self.function_name,
+                )
+                .map_err(|e| e.into());
+            }
+            let left = left
+                .parse(|source| source.parse::<P>(left_options))
+                .map_err(|e| FormatError::ParseError(FunctionLocation::Left, e))?;
+            let right = right
+                .parse(|source| source.parse::<P>(right_options))
+                .map_err(|e| FormatError::ParseError(FunctionLocation::Right, e))?;
+            diff_merged(P::GAP, self.function_name, &left, &right, |program| {
+                program.functions()
+            })
+        }
+    }
+    visit_format(
+        format,
+        Diff {
+            left_architecture,
+            left,
+            right_architecture,
+            right,
+            function_name,
+            output: std::marker::PhantomData,
+        },
+    )
+    .unwrap_or(Err(FormatError::BadFormat))
+}
+
+/// Parse and compare two programs that can be in different formats
+///
+/// If the formats are the same, this is the same as [compute_diff_with_format]. Otherwise, instructions are compared only by their [InstructionClass].
+///
+/// If a file contains several architectures (_e.g._, a universal MachO binary), the one to use can be selected by name (_e.g._, `arm64e` or `x86_64h`); otherwise, the one matching the format is used. The same file can be given for both sides to compare two of its architectures.
+pub fn compute_diff_with_formats<D: IntoDiffResult>(
+    left_format: &str,
+    left_architecture: Option<&str>,
+    left_file: impl AsRef<Path>,
+    right_format: &str,
+    right_architecture: Option<&str>,
+    right_file: impl AsRef<Path>,
+    function_name: FunctionName,
+) -> Result<(bool, Vec<D>), FormatError> {
+    diff_sources_with_formats(
+        left_format,
+        left_architecture,
+        Source::File(left_file.as_ref()),
+        right_format,
+        right_architecture,
+        Source::File(right_file.as_ref()),
+        function_name,
+    )
+}
+
+/// Parse and compare two programs that are already in memory and can be in different formats
+///
+/// This is the same as [compute_diff_with_formats], but the programs are provided as bytes (_e.g._, read from standard input or produced by another tool). Text formats must be UTF-8.
+pub fn compute_diff_from_bytes_with_formats<D: IntoDiffResult>(
+    left_format: &str,
+    left_architecture: Option<&str>,
+    left: &[u8],
+    right_format: &str,
+    right_architecture: Option<&str>,
+    right: &[u8],
+    function_name: FunctionName,
+) -> Result<(bool, Vec<D>), FormatError> {
+    diff_sources_with_formats(
+        left_format,
+        left_architecture,
+        Source::Bytes(left),
+        right_format,
+        right_architecture,
+        Source::Bytes(right),
+        function_name,
+    )
+}
+
+fn diff_sources_with_formats<D: IntoDiffResult>(
+    left_format: &str,
+    left_architecture: Option<&str>,
+    left: Source,
+    right_format: &str,
+    right_architecture: Option<&str>,
+    right: Source,
+    function_name: FunctionName,
+) -> Result<(bool, Vec<D>), FormatError> {
+    if left_format == right_format {
+        return diff_sources_with_format(
+            left_format,
+            left_architecture,
+            left,
+            right_architecture,
+            right,
             function_name,
+        );
+    }
+    if !is_format(left_format) || !is_format(right_format) {
+        return Err(FormatError::BadFormat);
+    }
+    let left =
+        Side::expand(left).map_err(|e| FormatError::ParseError(FunctionLocation::Left, e))?;
+    let right =
+        Side::expand(right).map_err(|e| FormatError::ParseError(FunctionLocation::Right, e))?;
+    let left = left
+        .parse(|source| {
+            mixed::ClassifiedProgram::parse_source(left_format, left_architecture, source)
+        })
+        .map_err(|e| FormatError::ParseError(FunctionLocation::Left, e))?;
+    let right = right
+        .parse(|source| {
+            mixed::ClassifiedProgram::parse_source(right_format, right_architecture, source)
+        })
+        .map_err(|e| FormatError::ParseError(FunctionLocation::Right, e))?;
+    diff_merged(mixed::GAP, function_name, &left, &right, |program| {
+        program.functions()
+    })
+}
+
+/// Check if a format name is recognized
+fn is_format(format: &str) -> bool {
+    struct Recognize;
+    impl FormatVisitor for Recognize {
+        type Output = ();
+        fn visit<P: Program>(self, _options: P::ParseOptions) -> Self::Output {}
+    }
+    visit_format(format, Recognize).is_some()
+}
+
+/// Compare programs made of many files, where the functions from all the files on each side are merged
+fn diff_merged<'a, T, F: Function + 'a, I: Iterator<Item = &'a F>, D: IntoDiffResult>(
+    gap: i32,
+    function_name: FunctionName,
+    left: &'a [(Option<&'a str>, T)],
+    right: &'a [(Option<&'a str>, T)],
+    functions: impl Fn(&'a T) -> I,
+) -> Result<(bool, Vec<D>), FormatError> {
+    let merge = |programs: &'a [(Option<&'a str>, T)]| {
+        inputs::Merged::new(programs.iter().flat_map(|(label, program)| {
+            functions(program).map(move |function| (*label, function))
+        }))
+    };
+    let left = merge(left);
+    let right = merge(right);
+    let pairs = pair_functions(
+        function_name,
+        |name| left.get(name),
+        |name| right.get(name),
+        left.functions(),
+        right.functions(),
+    )
+    .map_err(|e: Error<String>| FormatError::from(e))?;
+    Ok(diff_functions(gap, pairs))
+}
+
+/// Convert a symbol name to a human-readable form using either C++ or Rust demangling rules
+///
+/// Only names with a mangling prefix (`_Z` or `_R`, with an extra underscore on MachO) are demangled, since the demanglers accept some plain names (_e.g._, `g` would become `__float128`).
+pub fn demangle(symbol: &str) -> String {
+    if !["_Z", "__Z", "_R", "__R"]
+        .iter()
+        .any(|prefix| symbol.starts_with(prefix))
+    {
+        symbol.to_string()
+    } else if let Ok(name) = rustc_demangle::try_demangle(symbol) {
+        name.to_string()
+    } else if let Ok(name) = cpp_demangle::Symbol::new(symbol) {
+        name.to_string()
+    } else {
+        symbol.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEFT: &str = "
+sub_1000:
+	addl	$1, %edi
+	movl	%edi, %eax
+	retq
+before:
+	imull	$2, %edi, %eax
+	retq
+";
+    const RIGHT: &str = "
+sub_2000:
+	addl	$2, %edi
+	movl	%edi, %eax
+	retq
+after:
+	imull	$2, %edi, %eax
+	retq
+";
+
+    #[test]
+    fn only_synthetic_names_are_paired_by_content() {
+        type X86 = asm::Assembly<asm::x86_64::X86_64>;
+        let (left, right) = match (
+            X86::parse_bytes(LEFT.as_bytes(), ()),
+            X86::parse_bytes(RIGHT.as_bytes(), ()),
+        ) {
+            (Ok(left), Ok(right)) => (left, right),
+            _ => panic!("failed to parse assembly"),
+        };
+        let pairs = match pair_functions(
+            FunctionName::Unspecified,
+            |name| Lookup::in_program(&left, name),
+            |name| Lookup::in_program(&right, name),
+            left.functions(),
+            right.functions(),
+        ) {
+            Ok(pairs) => pairs,
+            Err(Error::<()>::NoMatch(location)) => panic!("no functions in {}", location.name()),
+            Err(_) => panic!("functions could not be paired"),
+        };
+        let names: Vec<_> = pairs
+            .iter()
+            .map(|(left, right)| (left.name().into_owned(), right.name().into_owned()))
+            .collect();
+        assert_eq!(names, [("sub_1000".to_string(), "sub_2000".to_string())]);
+    }
+
+    /// The names and instructions of each side of a comparison, leaving out block names and gaps
+    struct Sides(String, Vec<String>, Vec<String>);
+    impl IntoDiffResult for Sides {
+        type Row = (Option<String>, Option<String>);
+        fn block_row(_left: Cow<str>, _right: Cow<str>) -> Self::Row {
+            (None, None)
+        }
+        fn row(left: Cow<str>, right: Cow<str>, _kind: MatchDirection) -> Self::Row {
+            let side = |text: Cow<str>| Some(text.into_owned()).filter(|text| !text.is_empty());
+            (side(left), side(right))
+        }
+        fn function(left_name: Cow<str>, _right_name: Cow<str>, rows: Vec<Self::Row>) -> Self {
+            let (left, right): (Vec<_>, Vec<_>) = rows.into_iter().unzip();
+            Sides(
+                left_name.into_owned(),
+                left.into_iter().flatten().collect(),
+                right.into_iter().flatten().collect(),
+            )
+        }
+    }
+
+    fn testdata(file: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata")
+            .join(file)
+    }
+
+    #[test]
+    fn arm32_uses_the_32_bit_decoder() {
+        let sides = match compute_diff_with_format::<Sides>(
+            "arm32",
+            testdata("armv7-sq.o"),
+            testdata("armv7-sq.o"),
+            FunctionName::Same("sq".to_string()),
+        ) {
+            Ok((_, mut diffs)) => diffs.remove(0),
+            Err(_) => panic!("cannot compare 32-bit ARM code"),
+        };
+        assert_eq!(sides.1, ["mul r0, r0, r0", "bx lr"]);
+    }
+
+    /// Write a universal MachO binary from the test data, with the capability bits that real binaries can have in their subtypes
+    ///
+    /// The arm64e slice comes before the arm64 one, so the default for the instruction set is not the one selected by name.
+    fn universal(name: &str) -> std::path::PathBuf {
+        let slices = [
+            (0x0100000c, 0x80000002, "arm64e-mach.o"),
+            (0x0100000c, 0, "arm64-mach.o"),
+            (0x01000007, 0x80000003, "x86_64-mach.o"),
+            (0x01000007, 8, "x86_64h-mach.o"),
+        ];
+        let start = 8 + 20 * slices.len();
+        let mut header = Vec::new();
+        let mut body = Vec::new();
+        header.extend(0xcafebabeu32.to_be_bytes());
+        header.extend((slices.len() as u32).to_be_bytes());
+        for (cpu_type, cpu_subtype, file) in slices {
+            let data = std::fs::read(testdata(file)).unwrap();
+            let offset = (start + body.len() + 7) & !7;
+            body.resize(offset - start, 0);
+            for field in [cpu_type, cpu_subtype, offset as u32, data.len() as u32, 3] {
+                header.extend(field.to_be_bytes());
+            }
+            body.extend(data);
+        }
+        header.extend(body);
+        let path = std::env::temp_dir().join(format!("ctflgrdiff-{}-{}", std::process::id(), name));
+        std::fs::write(&path, header).unwrap();
+        path
+    }
+
+    #[test]
+    fn universal_slices_by_name() {
+        let path = universal("slices");
+        let names = |format: &str, architecture: Option<&str>| {
+            compute_diff_with_formats::<Sides>(
+                format,
+                architecture,
+                &path,
+                format,
+                architecture,
+                &path,
+                FunctionName::Unspecified,
+            )
+            .map(|(_, diffs)| diffs.into_iter().map(|sides| sides.0).collect::<Vec<_>>())
+        };
+        let sq = ["_sq", "_twice"].map(String::from).to_vec();
+        let targets = ["_sq", "_sum_squares"].map(String::from).to_vec();
+        // Without a name, the first slice for the instruction set is used
+        assert_eq!(names("arm64", None).ok(), Some(targets.clone()));
+        assert_eq!(names("arm64", Some("arm64")).ok(), Some(sq.clone()));
+        assert_eq!(names("arm64", Some("arm64e")).ok(), Some(targets.clone()));
+        assert_eq!(names("x86_64", Some("x86_64")).ok(), Some(sq));
+        assert_eq!(names("x86_64", Some("x86_64h")).ok(), Some(targets));
+        // Slices for another instruction set can't be selected, so the left-hand side is blamed first; a slice that isn't in the file is a parsing error of the file on both sides
+        for (format, architecture, location) in [
+            ("arm64", "x86_64", FunctionLocation::Left),
+            ("arm64", "arm64_32", FunctionLocation::Left),
+            ("arm32", "armv7", FunctionLocation::Both),
+        ] {
+            match names(format, Some(architecture)) {
+                Err(FormatError::ParseError(blamed, _)) => assert_eq!(
+                    blamed.name(),
+                    location.name(),
+                    "{} {}",
+                    format,
+                    architecture
+                ),
+                _ => panic!("{} {} was selected", format, architecture),
+            }
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn two_slices_of_one_file() {
+        let path = universal("two-slices");
+        // Only the function in both slices is paired, so each side must have come from a different slice
+        let diffs = match compute_diff_with_formats::<Sides>(
+            "arm64",
+            Some("arm64"),
+            &path,
+            "arm64",
+            Some("arm64e"),
+            &path,
+            FunctionName::Unspecified,
+        ) {
+            Ok((_, diffs)) => diffs,
+            Err(_) => panic!("cannot compare the slices"),
+        };
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].0, "_sq");
+        assert_eq!(diffs[0].1, diffs[0].2);
+        let diffs = match compute_diff_with_formats::<Sides>(
+            "x86_64",
+            Some("x86_64"),
+            &path,
+            "x86_64",
+            Some("x86_64h"),
+            &path,
+            FunctionName::Different("_twice".to_string(), "_sum_squares".to_string()),
+        ) {
+            Ok((_, diffs)) => diffs,
+            Err(_) => panic!("cannot compare the slices"),
+        };
+        assert_eq!(diffs[0].1.first().map(String::as_str), Some("push rax"));
+        assert!(
+            diffs[0].2.contains(&"inc ebx".to_string()),
+            "{:?}",
+            diffs[0].2
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// Assembly that counts how many times it has been parsed
+    struct Counted(asm::Assembly<asm::x86_64::X86_64>);
+    static PARSES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+    impl Program for Counted {
+        const GAP: i32 = asm::Assembly::<asm::x86_64::X86_64>::GAP;
+        type Function = <asm::Assembly<asm::x86_64::X86_64> as Program>::Function;
+        type ParseError = <asm::Assembly<asm::x86_64::X86_64> as Program>::ParseError;
+        type ParseOptions = ();
+        fn parse(file: impl AsRef<Path>, options: ()) -> Result<Self, Self::ParseError> {
+            PARSES.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            asm::Assembly::parse(file, options).map(Counted)
+        }
+        fn parse_bytes(bytes: &[u8], options: ()) -> Result<Self, Self::ParseError> {
+            PARSES.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            asm::Assembly::parse_bytes(bytes, options).map(Counted)
+        }
+        fn get(&self, name: &str) -> Option<&Self::Function> {
+            self.0.get(name)
+        }
+        fn functions<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Self::Function> + 'a> {
+            self.0.functions()
+        }
+    }
+
+    #[test]
+    fn one_file_is_parsed_once() {
+        let path = std::env::temp_dir().join(format!("ctflgrdiff-{}-once.s", std::process::id()));
+        std::fs::write(&path, LEFT).unwrap();
+        let diffs = match compute_diff::<Counted, Sides>(
+            &path,
+            &path,
+            FunctionName::Different("sub_1000".to_string(), "before".to_string()),
             (),
-        )
-        .map_err(|e| e.into()),
-        "x86" | "x86-32" | "x86_32" | "i386" | "i686" => compute_diff::<
-            goblin_yax::GoblinYax<yaxpeax_x86::x86_32>,
-            _,
-        >(
-            left_file, right_file, function_name, ()
-        )
-        .map_err(|e| e.into()),
-        "x64" | "x86-64" | "x86_64" => {
-            compute_diff::<goblin_yax::GoblinYax<yaxpeax_x86::x86_64>, _>(
-                left_file,
-                right_file,
-                function_name,
-                (),
+        ) {
+            Ok((true, diffs)) => diffs,
+            Ok((false, _)) => panic!("different functions compared as the same"),
+            Err(_) => panic!("cannot compare functions in one file"),
+        };
+        assert_eq!(PARSES.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert_eq!(diffs[0].1.last().map(String::as_str), Some("retq"));
+        assert_eq!(
+            diffs[0].2.first().map(String::as_str),
+            Some("imull $2, %edi, %eax")
+        );
+        // A missing file is blamed on both sides, since they are the same
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            compute_diff::<Counted, Sides>(&path, &path, FunctionName::Unspecified, ()),
+            Err(Error::ParseError(FunctionLocation::Both, _))
+        ));
+    }
+
+    #[test]
+    fn bytes_are_parsed_like_files() {
+        let universal = universal("bytes");
+        for (format, architecture, file) in [
+            ("x86_64", None, testdata("x86_64-loop.o")),
+            ("x86_64", None, testdata("x86_64-pe.exe")),
+            ("arm64", None, testdata("arm64-mach.o")),
+            ("x86_64", Some("x86_64h"), universal.clone()),
+            ("asm-x86_64", None, testdata("x86_64-loop.s")),
+            ("ptx", None, testdata("nvptx-scale.ptx")),
+            ("jvm", None, testdata("Counter.class")),
+            ("jar", None, testdata("counter.jar")),
+            ("pyc", None, testdata("greeter-311.pyc")),
+        ] {
+            let bytes = std::fs::read(&file).unwrap();
+            let from_file = compute_diff_with_formats::<Sides>(
+                format,
+                architecture,
+                &file,
+                format,
+                architecture,
+                &file,
+                FunctionName::Unspecified,
+            );
+            let from_bytes = compute_diff_from_bytes_with_formats::<Sides>(
+                format,
+                architecture,
+                &bytes,
+                format,
+                architecture,
+                &bytes,
+                FunctionName::Unspecified,
+            );
+            match (from_file, from_bytes) {
+                (Ok((_, from_file)), Ok((_, from_bytes))) => {
+                    assert!(!from_file.is_empty(), "{}", file.display());
+                    assert_eq!(
+                        from_file
+                            .iter()
+                            .map(|sides| (&sides.0, &sides.1))
+                            .collect::<Vec<_>>(),
+                        from_bytes
+                            .iter()
+                            .map(|sides| (&sides.0, &sides.1))
+                            .collect::<Vec<_>>(),
+                        "{}",
+                        file.display()
+                    );
+                }
+                _ => panic!("cannot compare {} with itself", file.display()),
+            }
+        }
+        std::fs::remove_file(&universal).unwrap();
+    }
+
+    #[test]
+    fn malformed_bytes_are_errors() {
+        let truncated = &std::fs::read(testdata("x86_64-loop.o")).unwrap()[..100];
+        for (format, bytes) in [
+            ("x86_64", truncated),
+            ("x86_64", &b"\xca\xfe\xba\xbe\0\0\0\x10"[..]),
+            ("asm-x86_64", &b"\xff\xfe"[..]),
+            ("jvm", &b"\xca\xfe\xba\xbe"[..]),
+            ("pyc", &b""[..]),
+        ] {
+            assert!(
+                matches!(
+                    compute_diff_from_bytes_with_formats::<Sides>(
+                        format,
+                        None,
+                        bytes,
+                        format,
+                        None,
+                        bytes,
+                        FunctionName::Unspecified
+                    ),
+                    Err(FormatError::ParseError(FunctionLocation::Left, _))
+                ),
+                "{} {:?}",
+                format,
+                bytes
+            );
+        }
+    }
+
+    #[test]
+    fn has_diff_only_when_a_function_differs() {
+        let has_diff = |left: &str, right: &str| {
+            compute_diff_from_bytes_with_formats::<Sides>(
+                "asm-x86_64",
+                None,
+                left.as_bytes(),
+                "asm-x86_64",
+                None,
+                right.as_bytes(),
+                FunctionName::Unspecified,
             )
-            .map_err(|e| e.into())
+            .map(|(has_diff, diffs)| (has_diff, diffs.len()))
+            .ok()
+        };
+        assert_eq!(has_diff(LEFT, LEFT), Some((false, 2)));
+        // One function with an extra instruction is enough, even though the other is the same
+        let changed = LEFT.replace(
+            "\timull\t$2, %edi, %eax\n",
+            "\tmovl\t%edi, %eax\n\timull\t$2, %eax, %eax\n",
+        );
+        assert_eq!(has_diff(LEFT, &changed), Some((true, 2)));
+    }
+
+    /// Every row of a comparison, as text, so comparisons can be checked for being exactly the same
+    struct Rows(Vec<String>);
+    impl IntoDiffResult for Rows {
+        type Row = String;
+        fn block_row(left: Cow<str>, right: Cow<str>) -> Self::Row {
+            format!("{} | {}", left, right)
+        }
+        fn row(left: Cow<str>, right: Cow<str>, kind: MatchDirection) -> Self::Row {
+            format!("  {:?}: {} | {}", kind, left, right)
+        }
+        fn function(left_name: Cow<str>, right_name: Cow<str>, mut rows: Vec<Self::Row>) -> Self {
+            rows.insert(0, format!("{} vs {}", left_name, right_name));
+            Rows(rows)
+        }
+    }
+
+    #[test]
+    fn equally_good_blocks_pick_the_first() {
+        let left = "
+add:
+	addl	$1, %edi
+	retq
+";
+        let right = "
+add:
+	testl	%edi, %edi
+	je	.L1
+	addl	$1, %edi
+	retq
+.L1:
+	addl	$1, %edi
+	retq
+";
+        let rows = match compute_diff_from_bytes_with_formats::<Rows>(
+            "asm-x86_64",
+            None,
+            left.as_bytes(),
+            "asm-x86_64",
+            None,
+            right.as_bytes(),
+            FunctionName::Unspecified,
+        ) {
+            Ok((_, mut diffs)) => diffs.remove(0).0,
+            Err(_) => panic!("cannot compare assembly"),
+        };
+        assert_eq!(rows[1], "0 | 1", "{:#?}", rows);
+        assert!(rows.contains(&" | .L1".to_string()), "{:#?}", rows);
+    }
+
+    /// Comparing in parallel produces exactly the same output as comparing on one thread
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_output_is_the_same_as_serial() {
+        let compare = |threads: usize, left: &Path, right: &Path| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| {
+                    compute_diff_with_format::<Rows>(
+                        "x86_64",
+                        left,
+                        right,
+                        FunctionName::Unspecified,
+                    )
+                })
+                .map(|(has_diff, diffs)| {
+                    (
+                        has_diff,
+                        diffs.into_iter().map(|rows| rows.0).collect::<Vec<_>>(),
+                    )
+                })
+                .ok()
+        };
+        for (left, right) in [
+            ("x86_64-loop.o", "x86_64-loop.o"),
+            ("x86_64-loop.o", "x86_64-loop-debug.o"),
+            ("x86_64-relocations.o", "x86_64-relocations.o"),
+            ("x86_64-stripped", "x86_64-stripped"),
+        ] {
+            let (left, right) = (testdata(left), testdata(right));
+            let serial = compare(1, &left, &right);
+            assert!(serial.is_some(), "{}", left.display());
+            for _ in 0..10 {
+                assert_eq!(compare(8, &left, &right), serial, "{}", left.display());
+            }
+        }
+    }
+
+    /// A diff result that only keeps the names of the functions compared
+    struct Names(String, String);
+    impl IntoDiffResult for Names {
+        type Row = ();
+        fn block_row(_left: Cow<str>, _right: Cow<str>) -> Self::Row {}
+        fn row(_left: Cow<str>, _right: Cow<str>, _kind: MatchDirection) -> Self::Row {}
+        fn function(left_name: Cow<str>, right_name: Cow<str>, _rows: Vec<Self::Row>) -> Self {
+            Names(left_name.into_owned(), right_name.into_owned())
+        }
+    }
+
+    #[test]
+    fn same_collection_on_both_sides() {
+        for input in ["testdata/build", "testdata/build/*.s"] {
+            let input = format!("{}/{}", env!("CARGO_MANIFEST_DIR"), input);
+            match compute_diff_with_format::<Names>(
+                "asm-x86-64",
+                &input,
+                &input,
+                FunctionName::Unspecified,
+            ) {
+                Ok((has_diff, functions)) => {
+                    assert!(!has_diff);
+                    assert_eq!(functions.len(), 2);
+                    assert!(functions.iter().all(|Names(left, right)| left == right));
+                }
+                Err(_) => panic!("{} could not be compared with itself", input),
+            }
+        }
+    }
+
+    #[test]
+    fn ambiguous_names_list_candidates() {
+        let input = format!("{}/testdata/duplicate", env!("CARGO_MANIFEST_DIR"));
+        match compute_diff_with_format::<Names>(
+            "asm-x86-64",
+            &input,
+            &input,
+            FunctionName::Same("square".to_string()),
+        ) {
+            Err(FormatError::Ambiguous(FunctionLocation::Left, name, candidates)) => {
+                assert_eq!(name, "square");
+                assert_eq!(candidates.len(), 2);
+                assert!(candidates
+                    .iter()
+                    .all(|candidate| candidate.ends_with(".s:square")));
+            }
+            _ => panic!("square should be ambiguous"),
         }
-        _ => Err(FormatError::BadFormat),
     }
 }
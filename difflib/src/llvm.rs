@@ -1,4 +1,23 @@
+use llvm_ir::HasDebugLoc;
 use std::borrow::Cow;
+use std::io::Read;
+
+/// The major version of LLVM this library was compiled against
+///
+/// LLVM can read IR produced by older versions, but IR from newer versions may crash it (_e.g._, LLVM 15 switched to opaque pointers), so inputs are checked before being handed to LLVM.
+pub const LLVM_VERSION: u32 = if cfg!(feature = "llvm-19") {
+    19
+} else if cfg!(feature = "llvm-18") {
+    18
+} else if cfg!(feature = "llvm-17") {
+    17
+} else if cfg!(feature = "llvm-16") {
+    16
+} else if cfg!(feature = "llvm-15") {
+    15
+} else {
+    14
+};
 
 impl crate::Program for llvm_ir::Module {
     const GAP: i32 = 2;
@@ -14,12 +33,29 @@ impl crate::Program for llvm_ir::Module {
         options: Self::ParseOptions,
     ) -> Result<Self, Self::ParseError> {
         if options {
+            let text = std::fs::read(file.as_ref()).map_err(|e| e.to_string())?;
+            check_ir_version(&String::from_utf8_lossy(&text))?;
             llvm_ir::Module::from_ir_path(file)
         } else {
+            let mut header = Vec::new();
+            std::fs::File::open(file.as_ref())
+                .and_then(|f| f.take(HEADER_LIMIT).read_to_end(&mut header))
+                .map_err(|e| e.to_string())?;
+            check_bc_version(&header)?;
             llvm_ir::Module::from_bc_path(file)
         }
     }
 
+    fn parse_bytes(bytes: &[u8], options: Self::ParseOptions) -> Result<Self, Self::ParseError> {
+        if options {
+            check_ir_version(&String::from_utf8_lossy(bytes))?;
+            with_temporary_file(bytes, "ll", |path| llvm_ir::Module::from_ir_path(path))
+        } else {
+            check_bc_version(&bytes[..bytes.len().min(HEADER_LIMIT as usize)])?;
+            with_temporary_file(bytes, "bc", |path| llvm_ir::Module::from_bc_path(path))
+        }
+    }
+
     fn get(&self, name: &str) -> Option<&Self::Function> {
         self.get_func_by_name(name)
     }
@@ -29,6 +65,325 @@ impl crate::Program for llvm_ir::Module {
     }
 }
 
+/// LLVM can only read programs from files, so programs in memory are written to a temporary file for it
+fn with_temporary_file(
+    bytes: &[u8],
+    extension: &str,
+    parse: impl FnOnce(&std::path::Path) -> Result<llvm_ir::Module, String>,
+) -> Result<llvm_ir::Module, String> {
+    static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "ctflgrdiff-{}-{}.{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
+        extension
+    ));
+    std::fs::write(&path, bytes).map_err(|e| e.to_string())?;
+    let result = parse(&path);
+    // The module has been read, so failing to clean up is not an error
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+/// The amount of a bitcode file to read for version information; the identification block is at the start of the file
+const HEADER_LIMIT: u64 = 4096;
+
+fn version_error(producer: u32) -> String {
+    format!(
+        "file was produced by LLVM {}, but this program was built for LLVM {}",
+        producer, LLVM_VERSION
+    )
+}
+
+type SyntaxTest = fn(&str) -> bool;
+
+/// Syntax that was introduced in a version of LLVM, used to estimate the version of IR that doesn't record its producer
+///
+/// Each entry is the version, a description for the error, and a test for the syntax.
+const IR_SYNTAX: &[(u32, &str, SyntaxTest)] = &[
+    (15, "opaque pointers", |text| uses_keyword(text, "ptr")),
+    (16, "memory effect attributes", |text| {
+        uses_keyword(text, "memory(")
+    }),
+    (17, "floating-point class attributes", |text| {
+        uses_keyword(text, "nofpclass(")
+    }),
+    (18, "disjoint or and non-negative zext flags", |text| {
+        uses_keyword(text, "or disjoint") || uses_keyword(text, "zext nneg")
+    }),
+    // LLVM 18 aligns i128 to 16 bytes on x86, which changes the data layout
+    (18, "the x86 data layout for 128-bit integers", |text| {
+        text.lines().any(|line| {
+            line.starts_with("target datalayout")
+                && line.contains("-p270:32:32")
+                && line.contains("-i128:128")
+        })
+    }),
+    (19, "debug records", |text| {
+        uses_keyword(text, "#dbg_value(")
+    }),
+];
+
+/// Check that text IR is not from a newer LLVM than the one that will parse it
+///
+/// The version is taken from the producer that clang records and from syntax that newer versions introduced, so IR from front ends that don't record a producer (_e.g._, Numba or rustc) is also checked. IR that has neither is assumed to be readable.
+fn check_ir_version(text: &str) -> Result<(), String> {
+    // Clang records its version in the module identification metadata: !0 = !{!"clang version 16.0.6 ..."}
+    if let Some(producer) = text
+        .match_indices("clang version ")
+        .filter_map(|(start, needle)| {
+            text[start + needle.len()..]
+                .split(|c: char| !c.is_ascii_digit())
+                .next()?
+                .parse::<u32>()
+                .ok()
+        })
+        .max()
+    {
+        if producer > LLVM_VERSION {
+            return Err(version_error(producer));
+        }
+    }
+    if let Some((version, syntax, _)) = IR_SYNTAX
+        .iter()
+        .rev()
+        .find(|(version, _, test)| *version > LLVM_VERSION && test(text))
+    {
+        return Err(format!(
+            "file uses {} (LLVM {} or later), but this program was built for LLVM {}",
+            syntax, version, LLVM_VERSION
+        ));
+    }
+    Ok(())
+}
+
+/// Check if the IR uses a keyword outside of comments, names, and strings (_e.g._, the `ptr` type rather than typed pointers like `i8*`)
+fn uses_keyword(text: &str, keyword: &str) -> bool {
+    text.lines()
+        .map(|line| line.split_once(';').map(|(code, _)| code).unwrap_or(line))
+        .any(|line| {
+            line.match_indices(keyword).any(|(start, _)| {
+                let before = line[..start].chars().next_back();
+                let after = line[start + keyword.len()..].chars().next();
+                let in_name = before
+                    .map(|c| c.is_alphanumeric() || "_.%@$\"-".contains(c))
+                    .unwrap_or(false);
+                // A keyword that ends with punctuation (_e.g._, `memory(`) can be followed by anything
+                let continues = keyword.ends_with(|c: char| c.is_alphanumeric())
+                    && after
+                        .map(|c| c.is_alphanumeric() || "_.\"".contains(c))
+                        .unwrap_or(false);
+                !in_name && !continues
+            })
+        })
+}
+
+/// Check that bitcode is not from a newer LLVM than the one that will parse it
+///
+/// Since LLVM 3.8, bitcode starts with an identification block that has the producer's version (_e.g._, `LLVM15.0.7`). Older bitcode has no producer and is assumed to be readable.
+fn check_bc_version(data: &[u8]) -> Result<(), String> {
+    // Darwin wraps bitcode in a header that gives the offset of the real bitcode
+    let data = if data.starts_with(&[0xDE, 0xC0, 0x17, 0x0B]) {
+        let offset = data
+            .get(8..12)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+            .ok_or_else(|| "bitcode wrapper is truncated".to_string())?;
+        data.get(offset..)
+            .ok_or_else(|| "bitcode wrapper is truncated".to_string())?
+    } else {
+        data
+    };
+    if !data.starts_with(b"BC\xC0\xDE") {
+        return Err("file is not LLVM bitcode".to_string());
+    }
+    let mut reader = BitReader {
+        data: &data[4..],
+        position: 0,
+    };
+    // Bitcode from before LLVM 3.8 starts with the module block instead
+    if reader.fixed(2) != Some(ENTER_SUBBLOCK) || reader.vbr(8) != Some(IDENTIFICATION_BLOCK_ID) {
+        return Ok(());
+    }
+    let producer = read_producer(&mut reader)
+        .ok_or_else(|| "bitcode identification block is malformed".to_string())?;
+    let major = producer
+        .trim_start_matches(|c: char| !c.is_ascii_digit())
+        .split(|c: char| !c.is_ascii_digit())
+        .next()
+        .and_then(|major| major.parse::<u32>().ok());
+    if let Some(major) = major {
+        if major > LLVM_VERSION {
+            return Err(version_error(major));
+        }
+    }
+    Ok(())
+}
+
+/// A minimal reader for the LLVM bitstream container format
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+impl<'a> BitReader<'a> {
+    fn fixed(&mut self, width: u32) -> Option<u64> {
+        if !(1..=64).contains(&width) {
+            return None;
+        }
+        let mut value = 0;
+        for bit in 0..width {
+            let byte = *self.data.get(self.position / 8)?;
+            if byte & (1 << (self.position % 8)) != 0 {
+                value |= 1 << bit;
+            }
+            self.position += 1;
+        }
+        Some(value)
+    }
+    fn vbr(&mut self, width: u32) -> Option<u64> {
+        // Each chunk needs a continuation bit and at least one bit of the value
+        if !(2..=64).contains(&width) {
+            return None;
+        }
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let chunk = self.fixed(width)?;
+            value |= (chunk & ((1 << (width - 1)) - 1)) << shift;
+            if chunk & (1 << (width - 1)) == 0 {
+                return Some(value);
+            }
+            shift += width - 1;
+            if shift >= 64 {
+                return None;
+            }
+        }
+    }
+    fn align(&mut self) {
+        self.position = self.position.div_ceil(32) * 32;
+    }
+}
+
+/// An operand in a bitstream abbreviation definition
+#[derive(Clone, Copy)]
+enum AbbrevOp {
+    Literal(u64),
+    Fixed(u32),
+    Vbr(u32),
+    Array,
+    Char6,
+    Blob,
+}
+
+fn read_abbrev_op(reader: &mut BitReader, op: AbbrevOp) -> Option<u64> {
+    match op {
+        AbbrevOp::Literal(value) => Some(value),
+        AbbrevOp::Fixed(width) => reader.fixed(width),
+        AbbrevOp::Vbr(width) => reader.vbr(width),
+        AbbrevOp::Char6 => {
+            let c = reader.fixed(6)? as u8;
+            Some(match c {
+                0..=25 => b'a' + c,
+                26..=51 => b'A' + c - 26,
+                52..=61 => b'0' + c - 52,
+                62 => b'.',
+                _ => b'_',
+            } as u64)
+        }
+        AbbrevOp::Array | AbbrevOp::Blob => None,
+    }
+}
+
+const ENTER_SUBBLOCK: u64 = 1;
+const IDENTIFICATION_BLOCK_ID: u64 = 13;
+
+/// Read the producer string from the identification block at the start of a bitcode stream, after the block's header
+///
+/// Malformed input, including abbreviations with field widths that can't be read, gives `None`
+fn read_producer(reader: &mut BitReader) -> Option<String> {
+    const END_BLOCK: u64 = 0;
+    const DEFINE_ABBREV: u64 = 2;
+    const UNABBREV_RECORD: u64 = 3;
+    const IDENTIFICATION_CODE_STRING: u64 = 1;
+
+    let width = u32::try_from(reader.vbr(4)?).ok()?;
+    reader.align();
+    reader.fixed(32)?;
+    let mut abbrevs: Vec<Vec<AbbrevOp>> = Vec::new();
+    loop {
+        let record: Vec<u64> = match reader.fixed(width)? {
+            END_BLOCK => return None,
+            ENTER_SUBBLOCK => return None,
+            DEFINE_ABBREV => {
+                let count = reader.vbr(5)?;
+                let mut ops = Vec::new();
+                for _ in 0..count {
+                    let op = if reader.fixed(1)? == 1 {
+                        AbbrevOp::Literal(reader.vbr(8)?)
+                    } else {
+                        match reader.fixed(3)? {
+                            encoding @ (1 | 2) => {
+                                let width = u32::try_from(reader.vbr(5)?).ok()?;
+                                match (encoding, width) {
+                                    // A zero width field is always zero
+                                    (_, 0) => AbbrevOp::Literal(0),
+                                    (1, width) => AbbrevOp::Fixed(width),
+                                    (_, width) => AbbrevOp::Vbr(width),
+                                }
+                            }
+                            3 => AbbrevOp::Array,
+                            4 => AbbrevOp::Char6,
+                            5 => AbbrevOp::Blob,
+                            _ => return None,
+                        }
+                    };
+                    ops.push(op);
+                }
+                abbrevs.push(ops);
+                continue;
+            }
+            UNABBREV_RECORD => {
+                let code = reader.vbr(6)?;
+                let count = reader.vbr(6)?;
+                let mut record = vec![code];
+                for _ in 0..count {
+                    record.push(reader.vbr(6)?);
+                }
+                record
+            }
+            id => {
+                let ops = abbrevs.get((id - 4) as usize)?.clone();
+                let mut record = Vec::new();
+                let mut index = 0;
+                while index < ops.len() {
+                    match ops[index] {
+                        AbbrevOp::Array => {
+                            let element = *ops.get(index + 1)?;
+                            for _ in 0..reader.vbr(6)? {
+                                record.push(read_abbrev_op(reader, element)?);
+                            }
+                            index += 1;
+                        }
+                        AbbrevOp::Blob => {
+                            let length = reader.vbr(6)?;
+                            reader.align();
+                            for _ in 0..length {
+                                record.push(reader.fixed(8)?);
+                            }
+                            reader.align();
+                        }
+                        op => record.push(read_abbrev_op(reader, op)?),
+                    }
+                    index += 1;
+                }
+                record
+            }
+        };
+        if record.first() == Some(&IDENTIFICATION_CODE_STRING) {
+            return Some(record[1..].iter().map(|&c| c as u8 as char).collect());
+        }
+    }
+}
+
 impl crate::Function for llvm_ir::Function {
     type BasicBlock = llvm_ir::BasicBlock;
 
@@ -69,6 +424,7 @@ impl crate::Instruction for llvm_ir::Instruction {
         match (self, other) {
             (llvm_ir::Instruction::Add(_), llvm_ir::Instruction::Add(_))
             | (llvm_ir::Instruction::Sub(_), llvm_ir::Instruction::Sub(_))
+            | (llvm_ir::Instruction::Mul(_), llvm_ir::Instruction::Mul(_))
             | (llvm_ir::Instruction::UDiv(_), llvm_ir::Instruction::UDiv(_))
             | (llvm_ir::Instruction::SDiv(_), llvm_ir::Instruction::SDiv(_))
             | (llvm_ir::Instruction::URem(_), llvm_ir::Instruction::URem(_))
@@ -76,12 +432,14 @@ impl crate::Instruction for llvm_ir::Instruction {
             (
                 llvm_ir::Instruction::Add(_)
                 | llvm_ir::Instruction::Sub(_)
+                | llvm_ir::Instruction::Mul(_)
                 | llvm_ir::Instruction::UDiv(_)
                 | llvm_ir::Instruction::SDiv(_)
                 | llvm_ir::Instruction::URem(_)
                 | llvm_ir::Instruction::SRem(_),
                 llvm_ir::Instruction::Add(_)
                 | llvm_ir::Instruction::Sub(_)
+                | llvm_ir::Instruction::Mul(_)
                 | llvm_ir::Instruction::UDiv(_)
                 | llvm_ir::Instruction::SDiv(_)
                 | llvm_ir::Instruction::URem(_)
@@ -361,6 +719,60 @@ impl crate::Instruction for llvm_ir::Instruction {
     fn render<'a>(&self) -> Cow<'a, str> {
         Cow::Owned(self.to_string())
     }
+    fn class(&self) -> crate::InstructionClass {
+        let opcode = match self {
+            llvm_ir::Instruction::Add(_) => "add",
+            llvm_ir::Instruction::Sub(_) => "sub",
+            llvm_ir::Instruction::Mul(_) => "mul",
+            llvm_ir::Instruction::UDiv(_) => "udiv",
+            llvm_ir::Instruction::SDiv(_) => "sdiv",
+            llvm_ir::Instruction::URem(_) => "urem",
+            llvm_ir::Instruction::SRem(_) => "srem",
+            llvm_ir::Instruction::And(_) => "and",
+            llvm_ir::Instruction::Or(_) => "or",
+            llvm_ir::Instruction::Xor(_) => "xor",
+            llvm_ir::Instruction::Shl(_) => "shl",
+            llvm_ir::Instruction::LShr(_) => "lshr",
+            llvm_ir::Instruction::AShr(_) => "ashr",
+            llvm_ir::Instruction::FAdd(_) => "fadd",
+            llvm_ir::Instruction::FSub(_) => "fsub",
+            llvm_ir::Instruction::FMul(_) => "fmul",
+            llvm_ir::Instruction::FDiv(_) => "fdiv",
+            llvm_ir::Instruction::FRem(_) => "frem",
+            llvm_ir::Instruction::FNeg(_) => "fneg",
+            llvm_ir::Instruction::ExtractElement(_) => "extractelement",
+            llvm_ir::Instruction::InsertElement(_) => "insertelement",
+            llvm_ir::Instruction::ShuffleVector(_) => "shufflevector",
+            llvm_ir::Instruction::Alloca(_) => "alloca",
+            llvm_ir::Instruction::Load(_) => "load",
+            llvm_ir::Instruction::Store(_) => "store",
+            llvm_ir::Instruction::Fence(_) => "fence",
+            llvm_ir::Instruction::CmpXchg(_) => "cmpxchg",
+            llvm_ir::Instruction::AtomicRMW(_) => "atomicrmw",
+            llvm_ir::Instruction::GetElementPtr(_) => "getelementptr",
+            llvm_ir::Instruction::Trunc(_) => "trunc",
+            llvm_ir::Instruction::ZExt(_) => "zext",
+            llvm_ir::Instruction::SExt(_) => "sext",
+            llvm_ir::Instruction::FPTrunc(_) => "fptrunc",
+            llvm_ir::Instruction::FPExt(_) => "fpext",
+            llvm_ir::Instruction::FPToUI(_) => "fptoui",
+            llvm_ir::Instruction::FPToSI(_) => "fptosi",
+            llvm_ir::Instruction::UIToFP(_) => "uitofp",
+            llvm_ir::Instruction::SIToFP(_) => "sitofp",
+            llvm_ir::Instruction::ICmp(_) => "icmp",
+            llvm_ir::Instruction::FCmp(_) => "fcmp",
+            llvm_ir::Instruction::Select(_) => "select",
+            llvm_ir::Instruction::Call(_) => "call",
+            _ => "",
+        };
+        crate::mixed::llvm_class(opcode, &self.to_string())
+    }
+
+    fn location(&self) -> Option<Cow<'_, str>> {
+        self.get_debug_loc()
+            .as_ref()
+            .map(|location| Cow::Owned(format!("{}:{}", location.filename, location.line)))
+    }
 }
 
 impl crate::Instruction for llvm_ir::Terminator {
@@ -386,4 +798,147 @@ impl crate::Instruction for llvm_ir::Terminator {
     fn render<'a>(&self) -> Cow<'a, str> {
         Cow::Owned(self.to_string())
     }
+
+    fn class(&self) -> crate::InstructionClass {
+        match self {
+            llvm_ir::Terminator::Invoke(_) | llvm_ir::Terminator::CallBr(_) => {
+                crate::InstructionClass::Call
+            }
+            _ => crate::InstructionClass::Branch,
+        }
+    }
+
+    fn location(&self) -> Option<Cow<'_, str>> {
+        self.get_debug_loc()
+            .as_ref()
+            .map(|location| Cow::Owned(format!("{}:{}", location.filename, location.line)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_bc_version, check_ir_version, BitReader, LLVM_VERSION};
+
+    /// Check IR that requires a version of LLVM, which is only rejected if it is newer than the one built against
+    fn check(version: u32, text: &str) {
+        assert_eq!(
+            check_ir_version(text).is_ok(),
+            version <= LLVM_VERSION,
+            "LLVM {} IR: {}",
+            version,
+            text
+        );
+    }
+
+    #[test]
+    fn clang_producer() {
+        check(13, "!0 = !{!\"clang version 13.0.1\"}");
+        check(
+            19,
+            "!0 = !{!\"clang version 19.1.0 (Fedora 19.1.0-1.fc41)\"}",
+        );
+    }
+
+    #[test]
+    fn syntax_without_producer() {
+        check(14, "source_filename = \"foo.c\"\ndefine i32 @f(i32* %p) {\n  %v = load i32, i32* %p\n  ret i32 %v\n}");
+        check(
+            15,
+            "define i32 @f(ptr %p) {\n  %v = load i32, ptr %p\n  ret i32 %v\n}",
+        );
+        check(16, "attributes #0 = { nounwind memory(none) }");
+        check(17, "define float @f(float nofpclass(nan) %x)");
+        check(18, "  %c = or disjoint i32 %a, %b");
+        check(18, "target datalayout = \"e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-i128:128-f80:128-n8:16:32:64-S128\"");
+        check(19, "    #dbg_value(i32 %x, !12, !DIExpression(), !15)");
+    }
+
+    #[test]
+    fn keywords_in_names_and_comments() {
+        // Names, strings, and comments that contain a keyword are not syntax
+        check(14, "%ptr = load i32, i32* %memory ; or disjoint ptr");
+        check(14, "@str = constant [4 x i8] c\"ptr\\00\"");
+        check(14, "declare void @memory(i32)");
+        // AArch64 has always aligned i128 to 16 bytes
+        check(
+            14,
+            "target datalayout = \"e-m:e-i8:8:32-i16:16:32-i64:64-i128:128-n32:64-S128\"",
+        );
+    }
+
+    /// Pack fixed width fields into a bitstream, after the bitcode magic number
+    fn bitcode(fields: &[(u64, u32)]) -> Vec<u8> {
+        let mut data = b"BC\xC0\xDE".to_vec();
+        let mut position = 0;
+        for &(value, width) in fields {
+            for bit in 0..width {
+                if position % 8 == 0 {
+                    data.push(0);
+                }
+                if value & (1 << bit) != 0 {
+                    *data.last_mut().unwrap() |= 1 << (position % 8);
+                }
+                position += 1;
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn bitcode_producer() {
+        let data = include_bytes!("../testdata/sq.bc");
+        assert!(check_bc_version(data).is_ok());
+        // A truncated file is an error, not a panic
+        assert!(check_bc_version(&data[..12]).is_err());
+        assert!(check_bc_version(b"BC").is_err());
+    }
+
+    #[test]
+    fn bitstream_widths() {
+        let mut reader = BitReader {
+            data: &[0xFF; 32],
+            position: 0,
+        };
+        assert_eq!(reader.fixed(0), None);
+        assert_eq!(reader.fixed(65), None);
+        assert_eq!(reader.vbr(0), None);
+        assert_eq!(reader.vbr(1), None);
+        assert_eq!(reader.vbr(65), None);
+        assert_eq!(reader.fixed(64), Some(u64::MAX));
+        // Every chunk has the continuation bit set, so the value doesn't fit in 64 bits
+        assert_eq!(reader.vbr(8), None);
+
+        // An identification block with an abbreviation for a 65 bit field, used by the next record
+        let data = bitcode(&[
+            (1, 2),
+            (13, 8),
+            (5, 4),
+            (0, 18),
+            (0, 32),
+            (2, 5),
+            (1, 5),
+            (0, 1),
+            (1, 3),
+            (0x11, 5),
+            (0x4, 5),
+            (4, 5),
+        ]);
+        assert!(check_bc_version(&data).is_err());
+    }
+
+    #[test]
+    fn debug_locations() {
+        use crate::{BasicBlock, Function, Instruction, Program};
+        let module = <llvm_ir::Module as Program>::parse(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/located.ll"),
+            true,
+        )
+        .unwrap();
+        let block = module.get("twice").unwrap().blocks().next().unwrap();
+        assert_eq!(block.get(0).location().as_deref(), Some("located.c:2"));
+        assert_eq!(
+            block.terminator().location().as_deref(),
+            Some("located.c:3")
+        );
+    }
 }
@@ -0,0 +1,700 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+/// LLVM text IR parsed without using LLVM
+///
+/// This only understands enough of the IR to split it into functions, blocks, and instructions and compare instructions by opcode and a few key details (_e.g._, the predicate of a comparison or the target type of a conversion).
+pub struct TextModule {
+    functions: Vec<TextFunction>,
+}
+pub struct TextFunction {
+    blocks: Vec<TextBlock>,
+    name: String,
+}
+pub struct TextBlock {
+    instrs: Vec<TextInstruction>,
+    name: String,
+    term: TextInstruction,
+}
+pub struct TextInstruction {
+    /// The operation, used for scoring
+    ///
+    /// This is the instruction's keyword, except for conditional branches, which are `condbr`, to match how LLVM separates them from unconditional branches.
+    opcode: String,
+    /// Extra information that is used to give partial credit when two instructions have the same opcode
+    detail: String,
+    /// The source location from the `!dbg` attachment, if any
+    location: Option<String>,
+    text: String,
+}
+
+#[derive(PartialEq, Eq)]
+enum Family {
+    IntArith,
+    Bitwise,
+    FloatArith,
+    Extend,
+    FloatResize,
+    FloatToInt,
+    IntToFloat,
+}
+impl Family {
+    fn of(opcode: &str) -> Option<Family> {
+        match opcode {
+            "add" | "sub" | "mul" | "udiv" | "sdiv" | "urem" | "srem" => Some(Family::IntArith),
+            "and" | "or" | "xor" | "shl" | "lshr" | "ashr" => Some(Family::Bitwise),
+            "fadd" | "fsub" | "fmul" | "fdiv" | "frem" | "fneg" => Some(Family::FloatArith),
+            "zext" | "sext" => Some(Family::Extend),
+            "fptrunc" | "fpext" => Some(Family::FloatResize),
+            "fptoui" | "fptosi" => Some(Family::FloatToInt),
+            "uitofp" | "sitofp" => Some(Family::IntToFloat),
+            _ => None,
+        }
+    }
+}
+
+const TERMINATORS: &[&str] = &[
+    "br",
+    "callbr",
+    "catchret",
+    "catchswitch",
+    "cleanupret",
+    "indirectbr",
+    "invoke",
+    "resume",
+    "ret",
+    "switch",
+    "unreachable",
+];
+const CONVERSIONS: &[&str] = &[
+    "addrspacecast",
+    "bitcast",
+    "fpext",
+    "fptosi",
+    "fptoui",
+    "fptrunc",
+    "inttoptr",
+    "ptrtoint",
+    "sext",
+    "sitofp",
+    "trunc",
+    "uitofp",
+    "zext",
+];
+const ORDERINGS: &[&str] = &[
+    "acq_rel",
+    "acquire",
+    "monotonic",
+    "release",
+    "seq_cst",
+    "unordered",
+];
+
+impl crate::Program for TextModule {
+    const GAP: i32 = 2;
+
+    type ParseOptions = ();
+
+    type ParseError = String;
+
+    type Function = TextFunction;
+
+    fn parse(
+        file: impl AsRef<std::path::Path>,
+        _options: Self::ParseOptions,
+    ) -> Result<Self, Self::ParseError> {
+        let text = std::fs::read_to_string(file).map_err(|e| e.to_string())?;
+        parse_module(&text)
+    }
+
+    fn parse_bytes(bytes: &[u8], _options: Self::ParseOptions) -> Result<Self, Self::ParseError> {
+        parse_module(std::str::from_utf8(bytes).map_err(|e| e.to_string())?)
+    }
+
+    fn get(&self, name: &str) -> Option<&Self::Function> {
+        self.functions.iter().find(|f| f.name == name)
+    }
+
+    fn functions<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Self::Function> + 'a> {
+        Box::new(self.functions.iter())
+    }
+}
+
+impl crate::Function for TextFunction {
+    type BasicBlock = TextBlock;
+
+    fn blocks<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Self::BasicBlock> + 'a> {
+        Box::new(self.blocks.iter())
+    }
+
+    fn name<'a>(&'a self) -> Cow<'a, str> {
+        Cow::Borrowed(self.name.as_str())
+    }
+}
+impl crate::BasicBlock for TextBlock {
+    type Instruction = TextInstruction;
+    type Terminator = TextInstruction;
+
+    fn get(&self, index: usize) -> &Self::Instruction {
+        &self.instrs[index]
+    }
+
+    fn len(&self) -> usize {
+        self.instrs.len()
+    }
+
+    fn name<'a>(&'a self) -> Cow<'a, str> {
+        Cow::Borrowed(self.name.as_str())
+    }
+
+    fn terminator(&self) -> &Self::Terminator {
+        &self.term
+    }
+}
+impl crate::Instruction for TextInstruction {
+    const EQUIVALENT: i32 = 4;
+    fn score(&self, other: &Self) -> i32 {
+        if self.opcode == other.opcode {
+            if self.opcode == "atomicrmw" {
+                // The detail is the operation followed by the ordering
+                let (l_op, l_order) = self.detail.split_once(' ').unwrap_or((&self.detail, ""));
+                let (r_op, r_order) = other.detail.split_once(' ').unwrap_or((&other.detail, ""));
+                (if l_order == r_order { 2 } else { 1 }) + (if l_op == r_op { 4 } else { 3 })
+            } else if self.detail == other.detail {
+                4
+            } else {
+                3
+            }
+        } else {
+            match (Family::of(&self.opcode), Family::of(&other.opcode)) {
+                (Some(Family::IntArith), Some(Family::IntArith))
+                | (Some(Family::Bitwise), Some(Family::Bitwise))
+                | (Some(Family::FloatArith), Some(Family::FloatArith)) => 3,
+                (Some(l), Some(r)) if l == r => {
+                    if self.detail == other.detail {
+                        3
+                    } else {
+                        2
+                    }
+                }
+                _ => 0,
+            }
+        }
+    }
+
+    fn render<'a>(&self) -> Cow<'a, str> {
+        Cow::Owned(self.text.clone())
+    }
+
+    fn class(&self) -> crate::InstructionClass {
+        crate::mixed::llvm_class(&self.opcode, &self.text)
+    }
+
+    fn location(&self) -> Option<Cow<'_, str>> {
+        self.location.as_deref().map(Cow::Borrowed)
+    }
+}
+
+fn parse_module(text: &str) -> Result<TextModule, String> {
+    // Metadata is defined after the functions that use it, so it is collected first
+    let metadata: BTreeMap<&str, &str> = text
+        .lines()
+        .filter_map(|line| {
+            let (id, node) = line.split_once(" = ")?;
+            if id.starts_with('!') {
+                Some((id.trim(), node.trim().trim_start_matches("distinct ")))
+            } else {
+                None
+            }
+        })
+        .collect();
+    let mut functions = Vec::new();
+    let mut lines = text.lines().enumerate();
+    while let Some((_, line)) = lines.next() {
+        let line = strip_comment(line).trim();
+        if !line.starts_with("define ") {
+            continue;
+        }
+        let (name, entry) = parse_header(line)?;
+        let mut blocks = Vec::new();
+        let mut block_name = Some(entry);
+        let mut instrs = Vec::new();
+        // Instructions can be split over multiple lines when they have a list of values (e.g., switch)
+        let mut pending = String::new();
+        loop {
+            let (number, line) = lines
+                .next()
+                .ok_or_else(|| format!("function {} is not terminated", name))?;
+            let line = strip_comment(line).trim();
+            if pending.is_empty() {
+                if line == "}" {
+                    break;
+                }
+                if line.is_empty() {
+                    continue;
+                }
+                if let Some(label) = parse_label(line) {
+                    if !instrs.is_empty() {
+                        return Err(format!(
+                            "line {}: block {} does not end with a terminator",
+                            number + 1,
+                            block_name.unwrap_or_default()
+                        ));
+                    }
+                    block_name = Some(label);
+                    continue;
+                }
+            } else {
+                pending.push(' ');
+            }
+            pending.push_str(line);
+            if pending.matches('[').count() > pending.matches(']').count() {
+                continue;
+            }
+            let mut instruction = parse_instruction(&pending);
+            instruction.location = debug_location(&pending, &metadata);
+            pending.clear();
+            if TERMINATORS.contains(&instruction.opcode.as_str()) || instruction.opcode == "condbr"
+            {
+                blocks.push(TextBlock {
+                    instrs: std::mem::take(&mut instrs),
+                    name: block_name.take().ok_or_else(|| {
+                        format!("line {}: instruction is not in a block", number + 1)
+                    })?,
+                    term: instruction,
+                });
+            } else {
+                instrs.push(instruction);
+            }
+        }
+        if !instrs.is_empty() {
+            return Err(format!("function {} ends without a terminator", name));
+        }
+        functions.push(TextFunction { blocks, name });
+    }
+    Ok(TextModule { functions })
+}
+
+/// Remove a comment from a line, respecting quoted strings
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => return &line[..index],
+            _ => (),
+        }
+    }
+    line
+}
+
+/// Find the function name and entry block name from a `define` line
+///
+/// If the entry block has no label, LLVM numbers it after any unnamed parameters
+fn parse_header(line: &str) -> Result<(String, String), String> {
+    let start = line
+        .find('@')
+        .ok_or_else(|| format!("function definition has no name: {}", line))?;
+    let (name, rest) = parse_identifier(&line[start + 1..]);
+    let parameters = rest
+        .strip_prefix('(')
+        .and_then(|rest| split_top_level(rest, ')').map(|(parameters, _)| parameters))
+        .ok_or_else(|| format!("function {} has malformed parameters", name))?;
+    let unnamed = split_operands(parameters)
+        .filter(|parameter| *parameter != "...")
+        .filter(|parameter| {
+            match parameter
+                .rsplit(char::is_whitespace)
+                .next()
+                .and_then(|last| last.strip_prefix('%'))
+            {
+                Some(value) => value.chars().all(|c| c.is_ascii_digit()),
+                None => true,
+            }
+        })
+        .count();
+    Ok((name, format!("%{}", unnamed)))
+}
+
+/// Parse a possibly quoted identifier, returning it and the remainder of the input
+fn parse_identifier(input: &str) -> (String, &str) {
+    if let Some(quoted) = input.strip_prefix('"') {
+        let end = quoted.find('"').unwrap_or(quoted.len());
+        (
+            quoted[..end].to_string(),
+            quoted.get(end + 1..).unwrap_or(""),
+        )
+    } else {
+        let end = input
+            .find(|c: char| !(c.is_alphanumeric() || "-$._".contains(c)))
+            .unwrap_or(input.len());
+        (input[..end].to_string(), &input[end..])
+    }
+}
+
+/// Match a block label (e.g., `entry:` or `3:`), formatting it the way LLVM names blocks
+fn parse_label(line: &str) -> Option<String> {
+    let (label, rest) = parse_identifier(line);
+    if label.is_empty() || !rest.starts_with(':') {
+        return None;
+    }
+    Some(if label.chars().all(|c| c.is_ascii_digit()) {
+        format!("%{}", label)
+    } else {
+        label
+    })
+}
+
+/// Split a string at the first occurrence of a character that is not nested in brackets or quotes
+fn split_top_level(input: &str, needle: char) -> Option<(&str, &str)> {
+    let mut depth = 0;
+    let mut quoted = false;
+    for (index, c) in input.char_indices() {
+        if c == needle && depth == 0 && !quoted {
+            return Some((&input[..index], &input[index + c.len_utf8()..]));
+        }
+        match c {
+            '"' => quoted = !quoted,
+            '(' | '[' | '{' | '<' if !quoted => depth += 1,
+            ')' | ']' | '}' | '>' if !quoted => depth -= 1,
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Split a comma-separated list that is not nested in brackets
+fn split_operands(mut input: &str) -> impl Iterator<Item = &str> {
+    std::iter::from_fn(move || {
+        if input.trim().is_empty() {
+            return None;
+        }
+        let (operand, rest) = split_top_level(input, ',').unwrap_or((input, ""));
+        input = rest;
+        Some(operand.trim())
+    })
+}
+
+fn parse_instruction(line: &str) -> TextInstruction {
+    // Debug and other metadata attachments are not part of the instruction
+    let text = match line.match_indices(", !").find(|(index, needle)| {
+        line[index + needle.len()..]
+            .chars()
+            .next()
+            .map(|c| c.is_alphabetic())
+            .unwrap_or(false)
+    }) {
+        Some((index, _)) => &line[..index],
+        None => line,
+    };
+    let body = match split_top_level(text, '=') {
+        Some((result, body)) if result.trim_start().starts_with('%') => body.trim(),
+        _ => text,
+    };
+    let mut words = body
+        .split_whitespace()
+        .skip_while(|word| matches!(*word, "tail" | "musttail" | "notail"));
+    let opcode = words.next().unwrap_or_default();
+    let (opcode, detail) = match opcode {
+        "icmp" | "fcmp" => (
+            opcode,
+            words
+                .find(|word| {
+                    !matches!(
+                        *word,
+                        "nnan" | "ninf" | "nsz" | "arcp" | "contract" | "afn" | "reassoc" | "fast"
+                    )
+                })
+                .unwrap_or_default()
+                .to_string(),
+        ),
+        "phi" | "alloca" => (
+            opcode,
+            words
+                .next()
+                .unwrap_or_default()
+                .trim_end_matches(',')
+                .to_string(),
+        ),
+        "br" => (
+            if body.contains("i1 ") { "condbr" } else { "br" },
+            String::new(),
+        ),
+        "fence" | "cmpxchg" => (
+            opcode,
+            body.split_whitespace()
+                .filter(|word| ORDERINGS.contains(&word.trim_end_matches(',')))
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+        "atomicrmw" => {
+            let operation = words.find(|word| *word != "volatile").unwrap_or_default();
+            let ordering = body
+                .split_whitespace()
+                .rev()
+                .find(|word| ORDERINGS.contains(word))
+                .unwrap_or_default();
+            (opcode, format!("{} {}", operation, ordering))
+        }
+        "va_arg" => (
+            opcode,
+            body.rsplit(',')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+        ),
+        "call" => (opcode, format!("{}", count_arguments(body))),
+        _ if CONVERSIONS.contains(&opcode) => (
+            opcode,
+            body.rsplit(" to ")
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+        ),
+        _ => (opcode, String::new()),
+    };
+    TextInstruction {
+        opcode: opcode.to_string(),
+        detail,
+        location: None,
+        text: text.trim().to_string(),
+    }
+}
+
+/// Find the source location of an instruction from its `!dbg` attachment
+///
+/// The location refers to a scope (_e.g._, a subprogram or lexical block), which refers to the file.
+fn debug_location(line: &str, metadata: &BTreeMap<&str, &str>) -> Option<String> {
+    let (_, reference) = line.rsplit_once("!dbg ")?;
+    let reference = reference
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .next()?;
+    let location = metadata.get(reference)?;
+    let line = metadata_field(location, "line")?;
+    let mut scope = metadata_field(location, "scope");
+    // Scopes can nest, but there's no point following a cycle
+    for _ in 0..32 {
+        let node = metadata.get(scope?)?;
+        if let Some(file) = metadata_field(node, "file") {
+            let filename = metadata_field(metadata.get(file)?, "filename")?;
+            return Some(format!("{}:{}", filename, line));
+        }
+        scope = metadata_field(node, "scope");
+    }
+    None
+}
+
+/// Get the value of a field in a specialized metadata node (_e.g._, `line` in `!DILocation(line: 3, column: 7, scope: !9)`)
+///
+/// Quotes are removed from string values.
+fn metadata_field<'a>(node: &'a str, name: &str) -> Option<&'a str> {
+    let start = node
+        .match_indices(name)
+        .find(|(index, _)| {
+            node[..*index].ends_with(['(', ' '])
+                && node[index + name.len()..].starts_with(": ")
+        })?
+        .0
+        + name.len()
+        + 2;
+    let value = &node[start..];
+    match value.strip_prefix('"') {
+        Some(value) => value.split('"').next(),
+        None => value.split([',', ')']).next().map(str::trim),
+    }
+}
+
+/// Count the arguments passed to a call
+///
+/// The callee is the first value (global or local) outside of parentheses and the arguments follow it
+fn count_arguments(body: &str) -> usize {
+    let mut depth = 0;
+    for (index, c) in body.char_indices() {
+        match c {
+            '(' | '[' | '{' | '<' => depth += 1,
+            ')' | ']' | '}' | '>' => depth -= 1,
+            '@' | '%' if depth == 0 => {
+                let rest = &body[index + 1..];
+                let (_, rest) = parse_identifier(rest);
+                return rest
+                    .trim_start()
+                    .strip_prefix('(')
+                    .and_then(|rest| split_top_level(rest, ')'))
+                    .map(|(arguments, _)| split_operands(arguments).count())
+                    .unwrap_or(0);
+            }
+            _ => (),
+        }
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BasicBlock, Function, Instruction, Program};
+
+    fn parse(file: &str) -> super::TextModule {
+        super::TextModule::parse(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("testdata")
+                .join(file),
+            (),
+        )
+        .unwrap()
+    }
+
+    fn instruction(line: &str) -> super::TextInstruction {
+        super::parse_instruction(line)
+    }
+
+    #[test]
+    fn blocks_and_terminators() {
+        let module = parse("branches.ll");
+        let names: Vec<_> = module
+            .functions()
+            .map(|function| function.name().into_owned())
+            .collect();
+        assert_eq!(names, ["classify", "scale value"]);
+        let classify = module.get("classify").unwrap();
+        // The entry block is numbered after the unnamed parameter
+        let blocks: Vec<_> = classify
+            .blocks()
+            .map(|block| {
+                (
+                    block.name().into_owned(),
+                    block.len(),
+                    block.terminator().opcode.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            blocks,
+            [
+                ("%1".to_string(), 1, "condbr".to_string()),
+                ("%3".to_string(), 0, "ret".to_string()),
+                ("%4".to_string(), 0, "switch".to_string()),
+                ("%5".to_string(), 0, "ret".to_string()),
+                ("%6".to_string(), 1, "ret".to_string()),
+            ]
+        );
+        let scale = module.get("scale value").unwrap();
+        let entry = scale.blocks().next().unwrap();
+        assert_eq!(entry.name(), "entry");
+        assert_eq!(entry.get(0).render(), "%product = mul nsw i32 %x, %factor");
+    }
+
+    #[test]
+    fn details_give_partial_credit() {
+        let slt = instruction("%2 = icmp slt i32 %0, 0");
+        assert_eq!(slt.score(&instruction("%c = icmp slt i32 %a, %b")), 4);
+        assert_eq!(slt.score(&instruction("%c = icmp eq i32 %a, %b")), 3);
+        let add = instruction("%c = add i32 %a, %b");
+        assert_eq!(add.score(&instruction("%c = mul i32 %a, %b")), 3);
+        assert_eq!(add.score(&instruction("%c = fadd double %a, %b")), 0);
+        let call = instruction("%7 = tail call i32 @\"scale value\"(i32 %0, i32 2)");
+        assert_eq!(call.detail, "2");
+        let rmw = instruction("%old = atomicrmw add ptr @counter, i64 %wide seq_cst");
+        assert_eq!(
+            rmw.score(&instruction(
+                "%old = atomicrmw sub ptr @counter, i64 1 seq_cst"
+            )),
+            5
+        );
+        assert_eq!(
+            rmw.score(&instruction(
+                "%old = atomicrmw add ptr @counter, i64 1 monotonic"
+            )),
+            5
+        );
+    }
+
+    #[test]
+    fn debug_locations() {
+        let module = parse("branches.ll");
+        let locations: Vec<_> = module
+            .get("classify")
+            .unwrap()
+            .blocks()
+            .map(|block| block.terminator().location().map(|l| l.into_owned()))
+            .collect();
+        // Locations in a lexical block find the file through the enclosing scopes
+        assert_eq!(
+            locations,
+            [
+                Some("branches.c:2".to_string()),
+                Some("branches.c:3".to_string()),
+                Some("branches.c:4".to_string()),
+                None,
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_input() {
+        for text in [
+            "define i32 @f(i32 %x) {\n  ret i32 %x\n",
+            "define i32 @f(i32 %x) {\n  %y = add i32 %x, 1\n}\n",
+            "define i32 @f(i32 %x) {\n  %y = add i32 %x, 1\nnext:\n  ret i32 %y\n}\n",
+            "define i32 @f(i32 %x) {\n  ret i32 %x\n  ret i32 %x\n}\n",
+            "define i32 @f(i32 %x) {\n  switch i32 %x, label %a [\n",
+        ] {
+            assert!(super::parse_module(text).is_err(), "{}", text);
+        }
+        // Lines that are not instructions must not panic
+        for line in [
+            "",
+            "=",
+            "%x =",
+            "call",
+            "atomicrmw",
+            "br i1",
+            "\"unterminated",
+        ] {
+            super::parse_instruction(line);
+        }
+    }
+
+    /// Every pair of instructions scores the same with this parser as with LLVM
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn scores_match_llvm() {
+        let llvm = llvm_ir::Module::parse(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/scoring.ll"),
+            true,
+        )
+        .unwrap();
+        let llvm: Vec<_> = llvm
+            .get("ops")
+            .unwrap()
+            .blocks()
+            .flat_map(|block| (0..block.len()).map(move |index| block.get(index)))
+            .collect();
+        let text = parse("scoring.ll");
+        let text: Vec<_> = text
+            .get("ops")
+            .unwrap()
+            .blocks()
+            .flat_map(|block| (0..block.len()).map(move |index| block.get(index)))
+            .collect();
+        assert_eq!(llvm.len(), text.len());
+        let mut mismatches = Vec::new();
+        for (l_llvm, l_text) in llvm.iter().zip(&text) {
+            for (r_llvm, r_text) in llvm.iter().zip(&text) {
+                let (expected, actual) = (l_llvm.score(r_llvm), l_text.score(r_text));
+                if expected != actual {
+                    mismatches.push(format!(
+                        "{} | {}: {} != {}",
+                        l_text.render(),
+                        r_text.render(),
+                        expected,
+                        actual
+                    ));
+                }
+            }
+        }
+        assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+    }
+}
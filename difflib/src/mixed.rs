@@ -0,0 +1,331 @@
+use crate::{BasicBlock, Function, Instruction, InstructionClass, Program};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The cost of inserting a gap when aligning programs of different formats
+///
+/// Different formats rarely have the same number of instructions (_e.g._, machine code has register moves and spills that IR does not), so gaps are free and instructions of different classes are left unmatched rather than forced into alignment.
+pub const GAP: i32 = 0;
+
+/// A program in any format reduced to the class of each instruction
+///
+/// The structure of functions and blocks is kept, along with the rendered instructions for display, but instructions are only compared by their [InstructionClass].
+pub struct ClassifiedProgram {
+    funcs: BTreeMap<String, ClassifiedFunction>,
+}
+pub struct ClassifiedFunction {
+    blocks: Vec<ClassifiedBlock>,
+    name: String,
+}
+pub struct ClassifiedBlock {
+    instructions: Vec<ClassifiedInstruction>,
+    name: String,
+    terminator: ClassifiedInstruction,
+}
+pub struct ClassifiedInstruction {
+    class: InstructionClass,
+    location: Option<String>,
+    text: String,
+}
+
+impl ClassifiedProgram {
+    /// Classify all the instructions in a program
+    pub fn new<P: Program>(program: &P) -> Self {
+        ClassifiedProgram {
+            funcs: program
+                .functions()
+                .map(|func| {
+                    let name = func.name().into_owned();
+                    let blocks = func
+                        .blocks()
+                        .map(|block| ClassifiedBlock {
+                            instructions: (0..block.len())
+                                .map(|index| ClassifiedInstruction::new(block.get(index)))
+                                .collect(),
+                            name: block.name().into_owned(),
+                            terminator: ClassifiedInstruction::new(block.terminator()),
+                        })
+                        .collect();
+                    (name.clone(), ClassifiedFunction { blocks, name })
+                })
+                .collect(),
+        }
+    }
+    /// Parse a file in the named format and classify it
+    ///
+    /// If the format is not recognized, `None` is returned. If the file contains several architectures, one can be selected by name.
+    pub fn parse(
+        format: &str,
+        architecture: Option<&str>,
+        file: impl AsRef<Path>,
+    ) -> Option<Result<Self, String>> {
+        Self::classify(format, architecture, crate::Source::File(file.as_ref()))
+    }
+    /// Parse a program in memory in the named format and classify it
+    ///
+    /// If the format is not recognized, `None` is returned. If the program contains several architectures, one can be selected by name.
+    pub fn parse_bytes(
+        format: &str,
+        architecture: Option<&str>,
+        bytes: &[u8],
+    ) -> Option<Result<Self, String>> {
+        Self::classify(format, architecture, crate::Source::Bytes(bytes))
+    }
+    /// Parse a program in a format that is known to be recognized
+    pub(crate) fn parse_source(
+        format: &str,
+        architecture: Option<&str>,
+        source: crate::Source,
+    ) -> Result<Self, String> {
+        Self::classify(format, architecture, source)
+            .unwrap_or_else(|| Err(format!("cannot parse “{}” files", format)))
+    }
+    fn classify(
+        format: &str,
+        architecture: Option<&str>,
+        source: crate::Source,
+    ) -> Option<Result<Self, String>> {
+        struct Classify<'a>(Option<&'a str>, crate::Source<'a>);
+        impl<'a> crate::FormatVisitor for Classify<'a> {
+            type Output = Result<ClassifiedProgram, String>;
+            fn visit<P: Program>(self, options: P::ParseOptions) -> Self::Output {
+                let options = crate::select_architecture::<P>(options, self.0)?;
+                self.1
+                    .parse::<P>(options)
+                    .map(|program| ClassifiedProgram::new(&program))
+                    .map_err(|e| e.to_string())
+            }
+        }
+        crate::visit_format(format, Classify(architecture, source))
+    }
+    /// Retrive a function by name, if it exists
+    pub fn get(&self, name: &str) -> Option<&ClassifiedFunction> {
+        self.funcs.get(name)
+    }
+    /// Iterate over all functions available
+    pub fn functions(&self) -> impl Iterator<Item = &ClassifiedFunction> {
+        self.funcs.values()
+    }
+}
+
+impl ClassifiedInstruction {
+    fn new<I: Instruction>(instruction: &I) -> Self {
+        ClassifiedInstruction {
+            class: instruction.class(),
+            location: instruction.location().map(Cow::into_owned),
+            text: instruction.render().into_owned(),
+        }
+    }
+}
+
+impl Function for ClassifiedFunction {
+    type BasicBlock = ClassifiedBlock;
+
+    fn blocks<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Self::BasicBlock> + 'a> {
+        Box::new(self.blocks.iter())
+    }
+
+    fn name<'a>(&'a self) -> Cow<'a, str> {
+        Cow::Borrowed(&self.name)
+    }
+}
+impl BasicBlock for ClassifiedBlock {
+    type Instruction = ClassifiedInstruction;
+
+    type Terminator = ClassifiedInstruction;
+
+    fn get(&self, index: usize) -> &Self::Instruction {
+        &self.instructions[index]
+    }
+
+    fn len(&self) -> usize {
+        self.instructions.len()
+    }
+
+    fn name<'a>(&'a self) -> Cow<'a, str> {
+        Cow::Borrowed(&self.name)
+    }
+
+    fn terminator(&self) -> &Self::Terminator {
+        &self.terminator
+    }
+}
+impl Instruction for ClassifiedInstruction {
+    const EQUIVALENT: i32 = 4;
+    fn score(&self, other: &Self) -> i32 {
+        use InstructionClass::*;
+        match (self.class, other.class) {
+            (Other, Other) => 1,
+            (l, r) if l == r => 4,
+            // Operations that are commonly implemented in terms of each other get partial credit
+            (Arith, Logic)
+            | (Logic, Arith)
+            | (Float, Vector)
+            | (Vector, Float)
+            | (Compare, Branch)
+            | (Branch, Compare) => 1,
+            _ => 0,
+        }
+    }
+
+    fn render<'a>(&self) -> Cow<'a, str> {
+        Cow::Owned(self.text.clone())
+    }
+
+    fn class(&self) -> InstructionClass {
+        self.class
+    }
+
+    fn location(&self) -> Option<Cow<'_, str>> {
+        self.location.as_deref().map(Cow::Borrowed)
+    }
+}
+
+/// Classify an LLVM IR instruction by its opcode keyword
+///
+/// The text of the instruction is used to find vector types (_e.g._, `<4 x i32>`).
+#[cfg_attr(not(any(feature = "llvm", feature = "llvm-text")), allow(dead_code))]
+pub(crate) fn llvm_class(opcode: &str, text: &str) -> InstructionClass {
+    let vector = text.split('<').skip(1).any(|rest| {
+        rest.split_once(" x ")
+            .map(|(count, _)| count.trim().parse::<u32>().is_ok())
+            .unwrap_or(false)
+    });
+    match opcode {
+        "getelementptr" => InstructionClass::Arith,
+        "add" | "sub" | "mul" | "udiv" | "sdiv" | "urem" | "srem" | "trunc" | "zext" | "sext"
+            if !vector =>
+        {
+            InstructionClass::Arith
+        }
+        "and" | "or" | "xor" | "shl" | "lshr" | "ashr" | "select" if !vector => {
+            InstructionClass::Logic
+        }
+        "fadd" | "fsub" | "fmul" | "fdiv" | "frem" | "fneg" | "fptrunc" | "fpext" | "fptoui"
+        | "fptosi" | "uitofp" | "sitofp"
+            if !vector =>
+        {
+            InstructionClass::Float
+        }
+        "add" | "sub" | "mul" | "udiv" | "sdiv" | "urem" | "srem" | "trunc" | "zext" | "sext"
+        | "and" | "or" | "xor" | "shl" | "lshr" | "ashr" | "select" | "fadd" | "fsub" | "fmul"
+        | "fdiv" | "frem" | "fneg" | "fptrunc" | "fpext" | "fptoui" | "fptosi" | "uitofp"
+        | "sitofp" | "extractelement" | "insertelement" | "shufflevector" => {
+            InstructionClass::Vector
+        }
+        "alloca" | "load" | "store" | "fence" | "cmpxchg" | "atomicrmw" => InstructionClass::Memory,
+        "icmp" | "fcmp" => InstructionClass::Compare,
+        "call" | "invoke" | "callbr" => InstructionClass::Call,
+        "br" | "condbr" | "switch" | "indirectbr" | "ret" | "resume" | "unreachable"
+        | "catchret" | "cleanupret" | "catchswitch" => InstructionClass::Branch,
+        _ => InstructionClass::Other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::llvm_class;
+    use crate::{InstructionClass, IntoDiffResult, MatchDirection};
+    use std::borrow::Cow;
+
+    /// The instructions that were aligned, ignoring gaps and block names
+    struct Aligned(Vec<(String, String)>);
+    impl IntoDiffResult for Aligned {
+        type Row = Option<(String, String)>;
+        fn block_row(_left: Cow<str>, _right: Cow<str>) -> Self::Row {
+            None
+        }
+        fn row(left: Cow<str>, right: Cow<str>, kind: MatchDirection) -> Self::Row {
+            match kind {
+                MatchDirection::Align(_) => Some((left.into_owned(), right.into_owned())),
+                _ => None,
+            }
+        }
+        fn function(_left_name: Cow<str>, _right_name: Cow<str>, rows: Vec<Self::Row>) -> Self {
+            Aligned(rows.into_iter().flatten().collect())
+        }
+    }
+
+    fn align(format: &str, file: &str, function: &str) -> Vec<(String, String)> {
+        let testdata = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let (_, mut diffs) = crate::compute_diff_with_formats::<Aligned>(
+            "ll",
+            None,
+            testdata.join("sq.ll"),
+            format,
+            None,
+            testdata.join(file),
+            crate::FunctionName::Same(function.to_string()),
+        )
+        .unwrap_or_else(|_| panic!("cannot compare {}", file));
+        diffs.remove(0).0
+    }
+
+    #[cfg(feature = "llvm")]
+    #[test]
+    fn ir_aligns_with_compiled_code() {
+        let pairs = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(left, right)| (left.to_string(), right.to_string()))
+                .collect::<Vec<_>>()
+        };
+        // Register moves, spills, and stack adjustments have no counterpart in the IR and are left unmatched
+        assert_eq!(
+            align("asm-x64", "x86_64-sq.s", "sq"),
+            pairs(&[
+                ("%y = mul i32 %x, i32 %x", "imull %edi, %eax"),
+                ("ret i32 %y", "retq")
+            ])
+        );
+        assert_eq!(
+            align("asm-x64", "x86_64-sq.s", "twice"),
+            pairs(&[
+                ("%y = call @sq(i32 %x)", "callq sq@PLT"),
+                ("%z = add i32 %y, i32 %y", "addl %eax, %eax"),
+                ("ret i32 %z", "retq")
+            ])
+        );
+        assert_eq!(
+            align("asm-arm64", "aarch64-sq.s", "sq"),
+            pairs(&[
+                ("%y = mul i32 %x, i32 %x", "mul w0, w0, w0"),
+                ("ret i32 %y", "ret")
+            ])
+        );
+        // A call ends a block in AArch64 code, so it is in a different block from the IR's call
+        assert_eq!(
+            align("asm-arm64", "aarch64-sq.s", "twice"),
+            pairs(&[
+                ("%z = add i32 %y, i32 %y", "add w0, w0, w0"),
+                ("ret i32 %z", "ret")
+            ])
+        );
+    }
+
+    #[test]
+    fn vector_types() {
+        assert_eq!(
+            llvm_class("add", "%v = add <4 x i32> %a, %b"),
+            InstructionClass::Vector
+        );
+        assert_eq!(
+            llvm_class("sitofp", "%v = sitofp <2 x i64> %a to <2 x double>"),
+            InstructionClass::Vector
+        );
+        assert_eq!(
+            llvm_class("add", "%s = add i32 %a, %b"),
+            InstructionClass::Arith
+        );
+        // Angle brackets that are not around a vector type
+        assert_eq!(
+            llvm_class("xor", "%x = xor i32 %a, %b ; <label>:3"),
+            InstructionClass::Logic
+        );
+        assert_eq!(
+            llvm_class("load", "%l = load <4 x i32>, ptr %p"),
+            InstructionClass::Memory
+        );
+    }
+}
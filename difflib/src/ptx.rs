@@ -0,0 +1,542 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+/// NVIDIA PTX assembly text
+///
+/// Functions are `.entry` (kernels) and `.func` definitions. Instructions are compared by their operation and modifiers, so that virtual register numbers do not affect the diff.
+pub struct Ptx {
+    funcs: BTreeMap<String, PtxFunction>,
+}
+pub struct PtxFunction {
+    blocks: Vec<PtxBlock>,
+    name: String,
+}
+pub struct PtxBlock {
+    instructions: Vec<PtxInstruction>,
+    name: String,
+    terminator: Option<PtxInstruction>,
+}
+pub struct PtxInstruction {
+    /// The base operation (_e.g._, `ld` for `ld.global.f32`)
+    family: String,
+    /// The modifiers on the operation that are not types (_e.g._, `global` or `rn`)
+    modifiers: Vec<String>,
+    /// The type suffixes on the operation (_e.g._, `f32`)
+    types: Vec<String>,
+    operands: Vec<String>,
+    predicate: Option<String>,
+}
+
+/// Groups of operations that are interchangeable enough to get partial credit
+const GROUPS: &[&[&str]] = &[
+    &[
+        "abs", "add", "addc", "div", "fma", "mad", "max", "min", "mul", "mul24", "neg", "rem",
+        "sad", "sub", "subc",
+    ],
+    &[
+        "and", "bfe", "bfi", "brev", "clz", "cnot", "not", "or", "popc", "shf", "shl", "shr", "xor",
+    ],
+    &["cos", "ex2", "lg2", "rcp", "rsqrt", "sin", "sqrt", "tanh"],
+    &["atom", "ld", "ldu", "red", "st"],
+    &["cvt", "cvta", "mov"],
+    &["selp", "set", "setp", "slct"],
+];
+
+const TYPES: &[&str] = &[
+    "b8", "b16", "b32", "b64", "b128", "bf16", "bf16x2", "e4m3", "e5m2", "f16", "f16x2", "f32",
+    "f64", "pred", "s8", "s16", "s32", "s64", "tf32", "u8", "u16", "u32", "u64",
+];
+
+impl crate::Program for Ptx {
+    const GAP: i32 = 2;
+
+    type ParseError = String;
+
+    type ParseOptions = ();
+
+    type Function = PtxFunction;
+
+    fn parse(
+        file: impl AsRef<std::path::Path>,
+        _options: Self::ParseOptions,
+    ) -> Result<Self, Self::ParseError> {
+        let text = std::fs::read_to_string(file).map_err(|e| e.to_string())?;
+        Ok(Ptx {
+            funcs: parse_module(&strip_comments(&text))?,
+        })
+    }
+
+    fn parse_bytes(bytes: &[u8], _options: Self::ParseOptions) -> Result<Self, Self::ParseError> {
+        let text = std::str::from_utf8(bytes).map_err(|e| e.to_string())?;
+        Ok(Ptx {
+            funcs: parse_module(&strip_comments(text))?,
+        })
+    }
+
+    fn get(&self, name: &str) -> Option<&Self::Function> {
+        self.funcs.get(name)
+    }
+
+    fn functions<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Self::Function> + 'a> {
+        Box::new(self.funcs.values())
+    }
+}
+impl crate::Function for PtxFunction {
+    type BasicBlock = PtxBlock;
+
+    fn blocks<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Self::BasicBlock> + 'a> {
+        Box::new(self.blocks.iter())
+    }
+
+    fn name<'a>(&'a self) -> Cow<'a, str> {
+        Cow::Borrowed(&self.name)
+    }
+}
+impl crate::BasicBlock for PtxBlock {
+    type Instruction = PtxInstruction;
+
+    type Terminator = Option<PtxInstruction>;
+
+    fn get(&self, index: usize) -> &Self::Instruction {
+        &self.instructions[index]
+    }
+
+    fn len(&self) -> usize {
+        self.instructions.len()
+    }
+
+    fn name<'a>(&'a self) -> Cow<'a, str> {
+        Cow::Borrowed(&self.name)
+    }
+
+    fn terminator(&self) -> &Self::Terminator {
+        &self.terminator
+    }
+}
+impl crate::Instruction for PtxInstruction {
+    const EQUIVALENT: i32 = 4;
+    fn score(&self, other: &Self) -> i32 {
+        if self.family == other.family {
+            // Matching types and matching modifiers and operand shapes each add partial credit
+            2 + (if self.types == other.types { 1 } else { 0 })
+                + (if self.modifiers == other.modifiers
+                    && self.predicate.is_some() == other.predicate.is_some()
+                    && self.operands.len() == other.operands.len()
+                    && self
+                        .operands
+                        .iter()
+                        .zip(other.operands.iter())
+                        .all(|(l, r)| operand_shape(l) == operand_shape(r))
+                {
+                    1
+                } else {
+                    0
+                })
+        } else if GROUPS.iter().any(|group| {
+            group.contains(&self.family.as_str()) && group.contains(&other.family.as_str())
+        }) {
+            1
+        } else {
+            0
+        }
+    }
+
+    fn render<'a>(&self) -> Cow<'a, str> {
+        let mut result = String::new();
+        if let Some(predicate) = &self.predicate {
+            result.push('@');
+            result.push_str(predicate);
+            result.push(' ');
+        }
+        result.push_str(&self.family);
+        for modifier in self.modifiers.iter().chain(self.types.iter()) {
+            result.push('.');
+            result.push_str(modifier);
+        }
+        if !self.operands.is_empty() {
+            result.push(' ');
+            result.push_str(&self.operands.join(", "));
+        }
+        Cow::Owned(result)
+    }
+
+    fn class(&self) -> crate::InstructionClass {
+        let float = self
+            .types
+            .iter()
+            .any(|t| t.starts_with('f') || t.starts_with("bf") || t == "tf32");
+        if self.types.iter().any(|t| t.ends_with("x2")) {
+            return crate::InstructionClass::Vector;
+        }
+        match self.family.as_str() {
+            "call" => crate::InstructionClass::Call,
+            "bra" | "brx" | "exit" | "ret" | "trap" => crate::InstructionClass::Branch,
+            "atom" | "ld" | "ldu" | "red" | "st" | "mov" | "cvta" | "prefetch" => {
+                crate::InstructionClass::Memory
+            }
+            "set" | "setp" => crate::InstructionClass::Compare,
+            "selp" | "slct" => crate::InstructionClass::Logic,
+            "cvt" | "abs" | "add" | "div" | "fma" | "mad" | "max" | "min" | "mul" | "neg"
+            | "rem" | "sub"
+                if float =>
+            {
+                crate::InstructionClass::Float
+            }
+            "mma" | "wmma" | "shfl" | "vote" | "dp4a" | "dp2a" => crate::InstructionClass::Vector,
+            family if GROUPS[0].contains(&family) || family == "cvt" => {
+                crate::InstructionClass::Arith
+            }
+            family if GROUPS[1].contains(&family) => crate::InstructionClass::Logic,
+            family if GROUPS[2].contains(&family) => crate::InstructionClass::Float,
+            _ => crate::InstructionClass::Other,
+        }
+    }
+}
+
+/// Reduce an operand to the kind of value it is so that register numbers are ignored (_e.g._, `%rd12` becomes `%rd`)
+fn operand_shape(operand: &str) -> Cow<'_, str> {
+    if operand.starts_with('%') {
+        Cow::Borrowed(operand.trim_end_matches(|c: char| c.is_ascii_digit()))
+    } else if operand.starts_with('[') {
+        Cow::Borrowed("[]")
+    } else if operand.starts_with('{') {
+        Cow::Borrowed("{}")
+    } else if operand.starts_with(|c: char| c.is_ascii_digit() || c == '-') {
+        Cow::Borrowed("0")
+    } else {
+        Cow::Borrowed("_")
+    }
+}
+
+fn strip_comments(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('/') {
+        let (before, comment) = rest.split_at(start);
+        result.push_str(before);
+        if comment.starts_with("//") {
+            rest = comment.find('\n').map(|end| &comment[end..]).unwrap_or("");
+        } else if comment.starts_with("/*") {
+            result.push(' ');
+            rest = comment
+                .find("*/")
+                .map(|end| &comment[end + 2..])
+                .unwrap_or("");
+        } else {
+            result.push('/');
+            rest = &comment[1..];
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Find the end of a bracketed region, given the text after the opening bracket
+fn find_close(text: &str, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    for (index, c) in text.char_indices() {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            if depth == 0 {
+                return Some(index);
+            }
+            depth -= 1;
+        }
+    }
+    None
+}
+
+fn parse_module(text: &str) -> Result<BTreeMap<String, PtxFunction>, String> {
+    let mut funcs = BTreeMap::new();
+    let mut rest = text;
+    while let Some(start) = [".entry", ".func"]
+        .iter()
+        .filter_map(|keyword| {
+            rest.match_indices(keyword)
+                .find(|(index, keyword)| {
+                    rest[index + keyword.len()..].starts_with(char::is_whitespace)
+                })
+                .map(|(index, keyword)| index + keyword.len())
+        })
+        .min()
+    {
+        let mut header = rest[start..].trim_start();
+        // Device functions can have a return value before the name
+        if let Some(returns) = header.strip_prefix('(') {
+            let end = find_close(returns, '(', ')')
+                .ok_or_else(|| "unterminated return value declaration".to_string())?;
+            header = returns[end + 1..].trim_start();
+        }
+        let name_end = header
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$' || c == '%'))
+            .unwrap_or(header.len());
+        let name = &header[..name_end];
+        if name.is_empty() {
+            return Err("function has no name".to_string());
+        }
+        let body_start = header
+            .find(['{', ';'])
+            .ok_or_else(|| format!("function {} is not terminated", name))?;
+        if header[body_start..].starts_with(';') {
+            // A prototype or external declaration
+            rest = &header[body_start + 1..];
+            continue;
+        }
+        let body = &header[body_start + 1..];
+        let body_end =
+            find_close(body, '{', '}').ok_or_else(|| format!("function {} is not closed", name))?;
+        let name = crate::demangle(name);
+        funcs.insert(name.clone(), parse_body(name, &body[..body_end]));
+        rest = &body[body_end + 1..];
+    }
+    Ok(funcs)
+}
+
+fn parse_body(name: String, body: &str) -> PtxFunction {
+    let mut blocks = Vec::new();
+    let mut label = None;
+    let mut instructions = Vec::new();
+    let mut flush = |label: &mut Option<String>,
+                     instructions: &mut Vec<PtxInstruction>,
+                     terminator: Option<PtxInstruction>| {
+        if instructions.is_empty() && terminator.is_none() {
+            return;
+        }
+        let id = blocks.len();
+        blocks.push(PtxBlock {
+            instructions: std::mem::take(instructions),
+            name: label.take().unwrap_or_else(|| format!("{}", id)),
+            terminator,
+        });
+    };
+    let mut statement = String::new();
+    let mut depth = 0;
+    for c in body.chars() {
+        match c {
+            // Braces that are not inside a statement are scopes (e.g., around a call sequence) and are ignored
+            '{' | '}' if statement.trim().is_empty() => {
+                statement.clear();
+            }
+            '{' => {
+                depth += 1;
+                statement.push(c);
+            }
+            '}' => {
+                depth -= 1;
+                statement.push(c);
+            }
+            ':' if depth == 0
+                && !statement.trim().is_empty()
+                && statement
+                    .trim()
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '_' || c == '$') =>
+            {
+                flush(&mut label, &mut instructions, None);
+                label = Some(statement.trim().to_string());
+                statement.clear();
+            }
+            ';' if depth == 0 => {
+                if let Some(instruction) = parse_statement(statement.trim()) {
+                    if matches!(
+                        instruction.family.as_str(),
+                        "bra" | "brx" | "exit" | "ret" | "trap"
+                    ) {
+                        flush(&mut label, &mut instructions, Some(instruction));
+                    } else {
+                        instructions.push(instruction);
+                    }
+                }
+                statement.clear();
+            }
+            c => statement.push(c),
+        }
+    }
+    // A chunk of code with no terminal flow control
+    flush(&mut label, &mut instructions, None);
+    PtxFunction { blocks, name }
+}
+
+fn parse_statement(statement: &str) -> Option<PtxInstruction> {
+    // Declarations and other directives (e.g., `.reg`, `.loc`) are not instructions
+    if statement.is_empty() || statement.starts_with('.') {
+        return None;
+    }
+    let (predicate, statement) = match statement.strip_prefix('@') {
+        Some(rest) => {
+            let (predicate, rest) = rest.split_once(char::is_whitespace)?;
+            (Some(predicate.to_string()), rest.trim_start())
+        }
+        None => (None, statement),
+    };
+    let (opcode, operands) = statement
+        .split_once(char::is_whitespace)
+        .unwrap_or((statement, ""));
+    let mut parts = opcode.split('.');
+    let family = parts.next()?.to_string();
+    let mut modifiers = Vec::new();
+    let mut types = Vec::new();
+    for part in parts {
+        if TYPES.contains(&part) {
+            types.push(part.to_string());
+        } else {
+            modifiers.push(part.to_string());
+        }
+    }
+    let mut operands_list = Vec::new();
+    let mut depth = 0;
+    let mut current = String::new();
+    for c in operands.chars() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            _ => (),
+        }
+        if c == ',' && depth == 0 {
+            operands_list.push(normalize_whitespace(&current));
+            current.clear();
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.trim().is_empty() {
+        operands_list.push(normalize_whitespace(&current));
+    }
+    Some(PtxInstruction {
+        family,
+        modifiers,
+        types,
+        operands: operands_list,
+        predicate,
+    })
+}
+
+/// Collapse whitespace (including line breaks in multi-line statements like calls) into single spaces
+fn normalize_whitespace(text: &str) -> String {
+    let mut result = String::new();
+    for word in text.split_whitespace() {
+        if !result.is_empty()
+            && !result.ends_with(['(', '[', '{'])
+            && !word.starts_with([')', ']', '}'])
+        {
+            result.push(' ');
+        }
+        result.push_str(word);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BasicBlock, Function, Instruction, Program};
+
+    const SCALE: &str = include_str!("../testdata/nvptx-scale.ptx");
+
+    fn instruction(statement: &str) -> super::PtxInstruction {
+        super::parse_statement(statement).expect("not an instruction")
+    }
+
+    /// Render every instruction in each block, with the terminator last
+    fn rendered(function: &super::PtxFunction) -> Vec<(String, Vec<String>)> {
+        function
+            .blocks()
+            .map(|block| {
+                (
+                    block.name().into_owned(),
+                    (0..block.len())
+                        .map(|index| block.get(index).render().into_owned())
+                        .chain(std::iter::once(block.terminator().render().into_owned()))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn functions_and_blocks() {
+        let program = super::Ptx::parse(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/nvptx-scale.ptx"),
+            (),
+        )
+        .unwrap();
+        let names: Vec<_> = program
+            .functions()
+            .map(|function| function.name().into_owned())
+            .collect();
+        assert_eq!(names, ["scale", "square"]);
+        assert_eq!(
+            rendered(program.get("square").unwrap()),
+            [(
+                "0".to_string(),
+                vec![
+                    "ld.param.f32 %f1, [square_param_0]".to_string(),
+                    "mul.rn.f32 %f2, %f1, %f1".to_string(),
+                    "st.param.f32 [func_retval0+0], %f2".to_string(),
+                    "ret".to_string(),
+                ]
+            )]
+        );
+        let scale = rendered(program.get("scale").unwrap());
+        let names: Vec<_> = scale.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["0", "1", "LBB1_2"]);
+        assert_eq!(scale[0].1.last().unwrap(), "@%p1 bra LBB1_2");
+        // The call sequence's scope and parameter declarations are not instructions
+        assert!(scale[1]
+            .1
+            .contains(&"call.uni (retval0), square, (param0)".to_string()));
+        assert_eq!(scale[1].1.last().unwrap(), "<no instruction>");
+    }
+
+    #[test]
+    fn virtual_registers_are_ignored() {
+        let left = super::parse_module(SCALE).unwrap();
+        let renumbered = SCALE.replace("%rd", "%rd9").replace("%f", "%f7");
+        let right = super::parse_module(&renumbered).unwrap();
+        for (left, right) in left.values().zip(right.values()) {
+            for (left, right) in left.blocks().zip(right.blocks()) {
+                for index in 0..left.len() {
+                    assert_eq!(
+                        left.get(index).score(right.get(index)),
+                        super::PtxInstruction::EQUIVALENT
+                    );
+                }
+                assert_eq!(
+                    left.terminator().score(right.terminator()),
+                    super::PtxInstruction::EQUIVALENT
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn types_and_families_give_partial_credit() {
+        let add = instruction("add.s64 %rd1, %rd5, %rd7");
+        assert_eq!(add.score(&instruction("add.s64 %rd2, %rd3, 4")), 3);
+        assert_eq!(add.score(&instruction("add.u32 %r1, %r2, %r3")), 2);
+        assert_eq!(add.score(&instruction("mul.lo.s64 %rd1, %rd5, %rd7")), 1);
+        assert_eq!(add.score(&instruction("ld.global.f32 %f1, [%rd1]")), 0);
+        assert!(super::parse_statement(".reg .b32 %r<3>").is_none());
+    }
+
+    #[test]
+    fn malformed_input() {
+        for text in [
+            ".visible .entry {",
+            ".visible .func (.param .b32 r) square(",
+            ".visible .func (.param .b32 r",
+            ".visible .entry scale(.param .u64 p)\n{\n\tret;\n",
+        ] {
+            assert!(super::parse_module(text).is_err(), "{}", text);
+        }
+        // Bodies that are not well formed must not panic
+        for text in [
+            ".entry f()\n{\n$L:\n}",
+            ".entry f()\n{\n\t@%p1 bra\n\t{ }\n\tld.global [;\n}",
+            ".func f\n{\n\tcall.uni (, f, (;\n}",
+        ] {
+            super::parse_module(text).unwrap();
+        }
+        assert!(super::parse_statement("").is_none());
+    }
+}
@@ -0,0 +1,1434 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::rc::Rc;
+
+/// CPython bytecode from a compiled module (`.pyc`)
+///
+/// Every code object in the module is a function, named by its qualified name (_e.g._, `Foo.bar.<locals>.baz`); the module body itself is `<module>`. CPython 3.7 through 3.13 are supported.
+pub struct PythonBytecode {
+    funcs: BTreeMap<String, PythonFunction>,
+}
+pub struct PythonFunction {
+    blocks: Vec<PythonBlock>,
+    name: String,
+}
+pub struct PythonBlock {
+    instructions: Vec<PythonInstruction>,
+    offset: usize,
+    terminator: Option<PythonInstruction>,
+}
+pub struct PythonInstruction {
+    opname: &'static str,
+    /// The resolved argument (_e.g._, the name being loaded or the value of a constant); this is compared when scoring
+    argument: Option<String>,
+    /// The offset of a jump target; this is only displayed since offsets shift between versions
+    target: Option<usize>,
+}
+pub enum PycError {
+    Io(std::io::Error),
+    Malformed(&'static str),
+    UnsupportedVersion(u16),
+}
+impl Display for PycError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PycError::Io(i) => i.fmt(f),
+            PycError::Malformed(m) => write!(f, "compiled Python file is malformed: {}", m),
+            PycError::UnsupportedVersion(magic) => write!(
+                f,
+                "file is not a compiled Python file from a supported version (magic number {})",
+                magic
+            ),
+        }
+    }
+}
+
+/// Groups of related operations; two different operations in the same group get partial credit
+#[derive(PartialEq, Eq)]
+enum Family {
+    Arith,
+    Build,
+    Call,
+    Compare,
+    Import,
+    Jump,
+    Load,
+    Return,
+    Stack,
+    Store,
+    Other,
+}
+
+/// The operators for `BINARY_OP` (3.11 and later), in argument order
+const BINARY_OPERATORS: [&str; 26] = [
+    "+", "&", "//", "<<", "@", "*", "%", "|", "**", ">>", "-", "/", "^", "+=", "&=", "//=", "<<=",
+    "@=", "*=", "%=", "|=", "**=", ">>=", "-=", "/=", "^=",
+];
+/// The operations that were replaced by `BINARY_OP` in 3.11 and the operator each is equivalent to
+const LEGACY_BINARY_OPERATIONS: [(&str, &str); 26] = [
+    ("BINARY_ADD", "+"),
+    ("BINARY_AND", "&"),
+    ("BINARY_FLOOR_DIVIDE", "//"),
+    ("BINARY_LSHIFT", "<<"),
+    ("BINARY_MATRIX_MULTIPLY", "@"),
+    ("BINARY_MULTIPLY", "*"),
+    ("BINARY_MODULO", "%"),
+    ("BINARY_OR", "|"),
+    ("BINARY_POWER", "**"),
+    ("BINARY_RSHIFT", ">>"),
+    ("BINARY_SUBTRACT", "-"),
+    ("BINARY_TRUE_DIVIDE", "/"),
+    ("BINARY_XOR", "^"),
+    ("INPLACE_ADD", "+="),
+    ("INPLACE_AND", "&="),
+    ("INPLACE_FLOOR_DIVIDE", "//="),
+    ("INPLACE_LSHIFT", "<<="),
+    ("INPLACE_MATRIX_MULTIPLY", "@="),
+    ("INPLACE_MULTIPLY", "*="),
+    ("INPLACE_MODULO", "%="),
+    ("INPLACE_OR", "|="),
+    ("INPLACE_POWER", "**="),
+    ("INPLACE_RSHIFT", ">>="),
+    ("INPLACE_SUBTRACT", "-="),
+    ("INPLACE_TRUE_DIVIDE", "/="),
+    ("INPLACE_XOR", "^="),
+];
+/// The comparisons for `COMPARE_OP`, in argument order; the ones after `>=` only exist before 3.9
+const COMPARISONS: [&str; 11] = [
+    "<",
+    "<=",
+    "==",
+    "!=",
+    ">",
+    ">=",
+    "in",
+    "not in",
+    "is",
+    "is not",
+    "exception match",
+];
+/// Operations whose argument is an index into the names of the code object
+const NAME_OPERATIONS: &[&str] = &[
+    "DELETE_ATTR",
+    "DELETE_GLOBAL",
+    "DELETE_NAME",
+    "IMPORT_FROM",
+    "IMPORT_NAME",
+    "LOAD_ATTR",
+    "LOAD_FROM_DICT_OR_GLOBALS",
+    "LOAD_GLOBAL",
+    "LOAD_METHOD",
+    "LOAD_NAME",
+    "STORE_ATTR",
+    "STORE_GLOBAL",
+    "STORE_NAME",
+];
+/// Operations whose argument is an index into the local variables of the code object
+const LOCAL_OPERATIONS: &[&str] = &[
+    "DELETE_FAST",
+    "LOAD_FAST",
+    "LOAD_FAST_AND_CLEAR",
+    "LOAD_FAST_CHECK",
+    "STORE_FAST",
+];
+/// Operations whose argument is an index into the cell and free variables of the code object
+const CELL_OPERATIONS: &[&str] = &[
+    "DELETE_DEREF",
+    "LOAD_CLASSDEREF",
+    "LOAD_CLOSURE",
+    "LOAD_DEREF",
+    "LOAD_FROM_DICT_OR_DEREF",
+    "MAKE_CELL",
+    "STORE_DEREF",
+];
+
+// The operation names for each version, indexed by opcode, as given by `opcode.opname`
+/// Python 3.7
+const OPNAMES_3_7: &str = "\
+    <0> POP_TOP ROT_TWO ROT_THREE DUP_TOP DUP_TOP_TWO <6> <7> <8> NOP UNARY_POSITIVE \
+    UNARY_NEGATIVE UNARY_NOT <13> <14> UNARY_INVERT BINARY_MATRIX_MULTIPLY \
+    INPLACE_MATRIX_MULTIPLY <18> BINARY_POWER BINARY_MULTIPLY <21> BINARY_MODULO BINARY_ADD \
+    BINARY_SUBTRACT BINARY_SUBSCR BINARY_FLOOR_DIVIDE BINARY_TRUE_DIVIDE \
+    INPLACE_FLOOR_DIVIDE INPLACE_TRUE_DIVIDE <30> <31> <32> <33> <34> <35> <36> <37> <38> \
+    <39> <40> <41> <42> <43> <44> <45> <46> <47> <48> <49> GET_AITER GET_ANEXT \
+    BEFORE_ASYNC_WITH <53> <54> INPLACE_ADD INPLACE_SUBTRACT INPLACE_MULTIPLY <58> \
+    INPLACE_MODULO STORE_SUBSCR DELETE_SUBSCR BINARY_LSHIFT BINARY_RSHIFT BINARY_AND \
+    BINARY_XOR BINARY_OR INPLACE_POWER GET_ITER GET_YIELD_FROM_ITER PRINT_EXPR \
+    LOAD_BUILD_CLASS YIELD_FROM GET_AWAITABLE <74> INPLACE_LSHIFT INPLACE_RSHIFT INPLACE_AND \
+    INPLACE_XOR INPLACE_OR BREAK_LOOP WITH_CLEANUP_START WITH_CLEANUP_FINISH RETURN_VALUE \
+    IMPORT_STAR SETUP_ANNOTATIONS YIELD_VALUE POP_BLOCK END_FINALLY POP_EXCEPT STORE_NAME \
+    DELETE_NAME UNPACK_SEQUENCE FOR_ITER UNPACK_EX STORE_ATTR DELETE_ATTR STORE_GLOBAL \
+    DELETE_GLOBAL <99> LOAD_CONST LOAD_NAME BUILD_TUPLE BUILD_LIST BUILD_SET BUILD_MAP \
+    LOAD_ATTR COMPARE_OP IMPORT_NAME IMPORT_FROM JUMP_FORWARD JUMP_IF_FALSE_OR_POP \
+    JUMP_IF_TRUE_OR_POP JUMP_ABSOLUTE POP_JUMP_IF_FALSE POP_JUMP_IF_TRUE LOAD_GLOBAL <117> \
+    <118> CONTINUE_LOOP SETUP_LOOP SETUP_EXCEPT SETUP_FINALLY <123> LOAD_FAST STORE_FAST \
+    DELETE_FAST <127> <128> <129> RAISE_VARARGS CALL_FUNCTION MAKE_FUNCTION BUILD_SLICE \
+    <134> LOAD_CLOSURE LOAD_DEREF STORE_DEREF DELETE_DEREF <139> <140> CALL_FUNCTION_KW \
+    CALL_FUNCTION_EX SETUP_WITH EXTENDED_ARG LIST_APPEND SET_ADD MAP_ADD LOAD_CLASSDEREF \
+    BUILD_LIST_UNPACK BUILD_MAP_UNPACK BUILD_MAP_UNPACK_WITH_CALL BUILD_TUPLE_UNPACK \
+    BUILD_SET_UNPACK SETUP_ASYNC_WITH FORMAT_VALUE BUILD_CONST_KEY_MAP BUILD_STRING \
+    BUILD_TUPLE_UNPACK_WITH_CALL <159> LOAD_METHOD CALL_METHOD <162> <163> <164> <165> <166> \
+    <167> <168> <169> <170> <171> <172> <173> <174> <175> <176> <177> <178> <179> <180> \
+    <181> <182> <183> <184> <185> <186> <187> <188> <189> <190> <191> <192> <193> <194> \
+    <195> <196> <197> <198> <199> <200> <201> <202> <203> <204> <205> <206> <207> <208> \
+    <209> <210> <211> <212> <213> <214> <215> <216> <217> <218> <219> <220> <221> <222> \
+    <223> <224> <225> <226> <227> <228> <229> <230> <231> <232> <233> <234> <235> <236> \
+    <237> <238> <239> <240> <241> <242> <243> <244> <245> <246> <247> <248> <249> <250> \
+    <251> <252> <253> <254> <255>";
+/// Python 3.8
+const OPNAMES_3_8: &str = "\
+    <0> POP_TOP ROT_TWO ROT_THREE DUP_TOP DUP_TOP_TWO ROT_FOUR <7> <8> NOP UNARY_POSITIVE \
+    UNARY_NEGATIVE UNARY_NOT <13> <14> UNARY_INVERT BINARY_MATRIX_MULTIPLY \
+    INPLACE_MATRIX_MULTIPLY <18> BINARY_POWER BINARY_MULTIPLY <21> BINARY_MODULO BINARY_ADD \
+    BINARY_SUBTRACT BINARY_SUBSCR BINARY_FLOOR_DIVIDE BINARY_TRUE_DIVIDE \
+    INPLACE_FLOOR_DIVIDE INPLACE_TRUE_DIVIDE <30> <31> <32> <33> <34> <35> <36> <37> <38> \
+    <39> <40> <41> <42> <43> <44> <45> <46> <47> <48> <49> GET_AITER GET_ANEXT \
+    BEFORE_ASYNC_WITH BEGIN_FINALLY END_ASYNC_FOR INPLACE_ADD INPLACE_SUBTRACT \
+    INPLACE_MULTIPLY <58> INPLACE_MODULO STORE_SUBSCR DELETE_SUBSCR BINARY_LSHIFT \
+    BINARY_RSHIFT BINARY_AND BINARY_XOR BINARY_OR INPLACE_POWER GET_ITER GET_YIELD_FROM_ITER \
+    PRINT_EXPR LOAD_BUILD_CLASS YIELD_FROM GET_AWAITABLE <74> INPLACE_LSHIFT INPLACE_RSHIFT \
+    INPLACE_AND INPLACE_XOR INPLACE_OR <80> WITH_CLEANUP_START WITH_CLEANUP_FINISH \
+    RETURN_VALUE IMPORT_STAR SETUP_ANNOTATIONS YIELD_VALUE POP_BLOCK END_FINALLY POP_EXCEPT \
+    STORE_NAME DELETE_NAME UNPACK_SEQUENCE FOR_ITER UNPACK_EX STORE_ATTR DELETE_ATTR \
+    STORE_GLOBAL DELETE_GLOBAL <99> LOAD_CONST LOAD_NAME BUILD_TUPLE BUILD_LIST BUILD_SET \
+    BUILD_MAP LOAD_ATTR COMPARE_OP IMPORT_NAME IMPORT_FROM JUMP_FORWARD JUMP_IF_FALSE_OR_POP \
+    JUMP_IF_TRUE_OR_POP JUMP_ABSOLUTE POP_JUMP_IF_FALSE POP_JUMP_IF_TRUE LOAD_GLOBAL <117> \
+    <118> <119> <120> <121> SETUP_FINALLY <123> LOAD_FAST STORE_FAST DELETE_FAST <127> <128> \
+    <129> RAISE_VARARGS CALL_FUNCTION MAKE_FUNCTION BUILD_SLICE <134> LOAD_CLOSURE \
+    LOAD_DEREF STORE_DEREF DELETE_DEREF <139> <140> CALL_FUNCTION_KW CALL_FUNCTION_EX \
+    SETUP_WITH EXTENDED_ARG LIST_APPEND SET_ADD MAP_ADD LOAD_CLASSDEREF BUILD_LIST_UNPACK \
+    BUILD_MAP_UNPACK BUILD_MAP_UNPACK_WITH_CALL BUILD_TUPLE_UNPACK BUILD_SET_UNPACK \
+    SETUP_ASYNC_WITH FORMAT_VALUE BUILD_CONST_KEY_MAP BUILD_STRING \
+    BUILD_TUPLE_UNPACK_WITH_CALL <159> LOAD_METHOD CALL_METHOD CALL_FINALLY POP_FINALLY \
+    <164> <165> <166> <167> <168> <169> <170> <171> <172> <173> <174> <175> <176> <177> \
+    <178> <179> <180> <181> <182> <183> <184> <185> <186> <187> <188> <189> <190> <191> \
+    <192> <193> <194> <195> <196> <197> <198> <199> <200> <201> <202> <203> <204> <205> \
+    <206> <207> <208> <209> <210> <211> <212> <213> <214> <215> <216> <217> <218> <219> \
+    <220> <221> <222> <223> <224> <225> <226> <227> <228> <229> <230> <231> <232> <233> \
+    <234> <235> <236> <237> <238> <239> <240> <241> <242> <243> <244> <245> <246> <247> \
+    <248> <249> <250> <251> <252> <253> <254> <255>";
+/// Python 3.9
+const OPNAMES_3_9: &str = "\
+    <0> POP_TOP ROT_TWO ROT_THREE DUP_TOP DUP_TOP_TWO ROT_FOUR <7> <8> NOP UNARY_POSITIVE \
+    UNARY_NEGATIVE UNARY_NOT <13> <14> UNARY_INVERT BINARY_MATRIX_MULTIPLY \
+    INPLACE_MATRIX_MULTIPLY <18> BINARY_POWER BINARY_MULTIPLY <21> BINARY_MODULO BINARY_ADD \
+    BINARY_SUBTRACT BINARY_SUBSCR BINARY_FLOOR_DIVIDE BINARY_TRUE_DIVIDE \
+    INPLACE_FLOOR_DIVIDE INPLACE_TRUE_DIVIDE <30> <31> <32> <33> <34> <35> <36> <37> <38> \
+    <39> <40> <41> <42> <43> <44> <45> <46> <47> RERAISE WITH_EXCEPT_START GET_AITER \
+    GET_ANEXT BEFORE_ASYNC_WITH <53> END_ASYNC_FOR INPLACE_ADD INPLACE_SUBTRACT \
+    INPLACE_MULTIPLY <58> INPLACE_MODULO STORE_SUBSCR DELETE_SUBSCR BINARY_LSHIFT \
+    BINARY_RSHIFT BINARY_AND BINARY_XOR BINARY_OR INPLACE_POWER GET_ITER GET_YIELD_FROM_ITER \
+    PRINT_EXPR LOAD_BUILD_CLASS YIELD_FROM GET_AWAITABLE LOAD_ASSERTION_ERROR INPLACE_LSHIFT \
+    INPLACE_RSHIFT INPLACE_AND INPLACE_XOR INPLACE_OR <80> <81> LIST_TO_TUPLE RETURN_VALUE \
+    IMPORT_STAR SETUP_ANNOTATIONS YIELD_VALUE POP_BLOCK <88> POP_EXCEPT STORE_NAME \
+    DELETE_NAME UNPACK_SEQUENCE FOR_ITER UNPACK_EX STORE_ATTR DELETE_ATTR STORE_GLOBAL \
+    DELETE_GLOBAL <99> LOAD_CONST LOAD_NAME BUILD_TUPLE BUILD_LIST BUILD_SET BUILD_MAP \
+    LOAD_ATTR COMPARE_OP IMPORT_NAME IMPORT_FROM JUMP_FORWARD JUMP_IF_FALSE_OR_POP \
+    JUMP_IF_TRUE_OR_POP JUMP_ABSOLUTE POP_JUMP_IF_FALSE POP_JUMP_IF_TRUE LOAD_GLOBAL IS_OP \
+    CONTAINS_OP <119> <120> JUMP_IF_NOT_EXC_MATCH SETUP_FINALLY <123> LOAD_FAST STORE_FAST \
+    DELETE_FAST <127> <128> <129> RAISE_VARARGS CALL_FUNCTION MAKE_FUNCTION BUILD_SLICE \
+    <134> LOAD_CLOSURE LOAD_DEREF STORE_DEREF DELETE_DEREF <139> <140> CALL_FUNCTION_KW \
+    CALL_FUNCTION_EX SETUP_WITH EXTENDED_ARG LIST_APPEND SET_ADD MAP_ADD LOAD_CLASSDEREF \
+    <149> <150> <151> <152> <153> SETUP_ASYNC_WITH FORMAT_VALUE BUILD_CONST_KEY_MAP \
+    BUILD_STRING <158> <159> LOAD_METHOD CALL_METHOD LIST_EXTEND SET_UPDATE DICT_MERGE \
+    DICT_UPDATE <166> <167> <168> <169> <170> <171> <172> <173> <174> <175> <176> <177> \
+    <178> <179> <180> <181> <182> <183> <184> <185> <186> <187> <188> <189> <190> <191> \
+    <192> <193> <194> <195> <196> <197> <198> <199> <200> <201> <202> <203> <204> <205> \
+    <206> <207> <208> <209> <210> <211> <212> <213> <214> <215> <216> <217> <218> <219> \
+    <220> <221> <222> <223> <224> <225> <226> <227> <228> <229> <230> <231> <232> <233> \
+    <234> <235> <236> <237> <238> <239> <240> <241> <242> <243> <244> <245> <246> <247> \
+    <248> <249> <250> <251> <252> <253> <254> <255>";
+/// Python 3.10
+const OPNAMES_3_10: &str = "\
+    <0> POP_TOP ROT_TWO ROT_THREE DUP_TOP DUP_TOP_TWO ROT_FOUR <7> <8> NOP UNARY_POSITIVE \
+    UNARY_NEGATIVE UNARY_NOT <13> <14> UNARY_INVERT BINARY_MATRIX_MULTIPLY \
+    INPLACE_MATRIX_MULTIPLY <18> BINARY_POWER BINARY_MULTIPLY <21> BINARY_MODULO BINARY_ADD \
+    BINARY_SUBTRACT BINARY_SUBSCR BINARY_FLOOR_DIVIDE BINARY_TRUE_DIVIDE \
+    INPLACE_FLOOR_DIVIDE INPLACE_TRUE_DIVIDE GET_LEN MATCH_MAPPING MATCH_SEQUENCE MATCH_KEYS \
+    COPY_DICT_WITHOUT_KEYS <35> <36> <37> <38> <39> <40> <41> <42> <43> <44> <45> <46> <47> \
+    <48> WITH_EXCEPT_START GET_AITER GET_ANEXT BEFORE_ASYNC_WITH <53> END_ASYNC_FOR \
+    INPLACE_ADD INPLACE_SUBTRACT INPLACE_MULTIPLY <58> INPLACE_MODULO STORE_SUBSCR \
+    DELETE_SUBSCR BINARY_LSHIFT BINARY_RSHIFT BINARY_AND BINARY_XOR BINARY_OR INPLACE_POWER \
+    GET_ITER GET_YIELD_FROM_ITER PRINT_EXPR LOAD_BUILD_CLASS YIELD_FROM GET_AWAITABLE \
+    LOAD_ASSERTION_ERROR INPLACE_LSHIFT INPLACE_RSHIFT INPLACE_AND INPLACE_XOR INPLACE_OR \
+    <80> <81> LIST_TO_TUPLE RETURN_VALUE IMPORT_STAR SETUP_ANNOTATIONS YIELD_VALUE POP_BLOCK \
+    <88> POP_EXCEPT STORE_NAME DELETE_NAME UNPACK_SEQUENCE FOR_ITER UNPACK_EX STORE_ATTR \
+    DELETE_ATTR STORE_GLOBAL DELETE_GLOBAL ROT_N LOAD_CONST LOAD_NAME BUILD_TUPLE BUILD_LIST \
+    BUILD_SET BUILD_MAP LOAD_ATTR COMPARE_OP IMPORT_NAME IMPORT_FROM JUMP_FORWARD \
+    JUMP_IF_FALSE_OR_POP JUMP_IF_TRUE_OR_POP JUMP_ABSOLUTE POP_JUMP_IF_FALSE \
+    POP_JUMP_IF_TRUE LOAD_GLOBAL IS_OP CONTAINS_OP RERAISE <120> JUMP_IF_NOT_EXC_MATCH \
+    SETUP_FINALLY <123> LOAD_FAST STORE_FAST DELETE_FAST <127> <128> GEN_START RAISE_VARARGS \
+    CALL_FUNCTION MAKE_FUNCTION BUILD_SLICE <134> LOAD_CLOSURE LOAD_DEREF STORE_DEREF \
+    DELETE_DEREF <139> <140> CALL_FUNCTION_KW CALL_FUNCTION_EX SETUP_WITH EXTENDED_ARG \
+    LIST_APPEND SET_ADD MAP_ADD LOAD_CLASSDEREF <149> <150> <151> MATCH_CLASS <153> \
+    SETUP_ASYNC_WITH FORMAT_VALUE BUILD_CONST_KEY_MAP BUILD_STRING <158> <159> LOAD_METHOD \
+    CALL_METHOD LIST_EXTEND SET_UPDATE DICT_MERGE DICT_UPDATE <166> <167> <168> <169> <170> \
+    <171> <172> <173> <174> <175> <176> <177> <178> <179> <180> <181> <182> <183> <184> \
+    <185> <186> <187> <188> <189> <190> <191> <192> <193> <194> <195> <196> <197> <198> \
+    <199> <200> <201> <202> <203> <204> <205> <206> <207> <208> <209> <210> <211> <212> \
+    <213> <214> <215> <216> <217> <218> <219> <220> <221> <222> <223> <224> <225> <226> \
+    <227> <228> <229> <230> <231> <232> <233> <234> <235> <236> <237> <238> <239> <240> \
+    <241> <242> <243> <244> <245> <246> <247> <248> <249> <250> <251> <252> <253> <254> \
+    <255>";
+/// Python 3.11
+const OPNAMES_3_11: &str = "\
+    CACHE POP_TOP PUSH_NULL <3> <4> <5> <6> <7> <8> NOP UNARY_POSITIVE UNARY_NEGATIVE \
+    UNARY_NOT <13> <14> UNARY_INVERT <16> <17> <18> <19> <20> <21> <22> <23> <24> \
+    BINARY_SUBSCR <26> <27> <28> <29> GET_LEN MATCH_MAPPING MATCH_SEQUENCE MATCH_KEYS <34> \
+    PUSH_EXC_INFO CHECK_EXC_MATCH CHECK_EG_MATCH <38> <39> <40> <41> <42> <43> <44> <45> \
+    <46> <47> <48> WITH_EXCEPT_START GET_AITER GET_ANEXT BEFORE_ASYNC_WITH BEFORE_WITH \
+    END_ASYNC_FOR <55> <56> <57> <58> <59> STORE_SUBSCR DELETE_SUBSCR <62> <63> <64> <65> \
+    <66> <67> GET_ITER GET_YIELD_FROM_ITER PRINT_EXPR LOAD_BUILD_CLASS <72> <73> \
+    LOAD_ASSERTION_ERROR RETURN_GENERATOR <76> <77> <78> <79> <80> <81> LIST_TO_TUPLE \
+    RETURN_VALUE IMPORT_STAR SETUP_ANNOTATIONS YIELD_VALUE ASYNC_GEN_WRAP PREP_RERAISE_STAR \
+    POP_EXCEPT STORE_NAME DELETE_NAME UNPACK_SEQUENCE FOR_ITER UNPACK_EX STORE_ATTR \
+    DELETE_ATTR STORE_GLOBAL DELETE_GLOBAL SWAP LOAD_CONST LOAD_NAME BUILD_TUPLE BUILD_LIST \
+    BUILD_SET BUILD_MAP LOAD_ATTR COMPARE_OP IMPORT_NAME IMPORT_FROM JUMP_FORWARD \
+    JUMP_IF_FALSE_OR_POP JUMP_IF_TRUE_OR_POP <113> POP_JUMP_FORWARD_IF_FALSE \
+    POP_JUMP_FORWARD_IF_TRUE LOAD_GLOBAL IS_OP CONTAINS_OP RERAISE COPY <121> BINARY_OP SEND \
+    LOAD_FAST STORE_FAST DELETE_FAST <127> POP_JUMP_FORWARD_IF_NOT_NONE \
+    POP_JUMP_FORWARD_IF_NONE RAISE_VARARGS GET_AWAITABLE MAKE_FUNCTION BUILD_SLICE \
+    JUMP_BACKWARD_NO_INTERRUPT MAKE_CELL LOAD_CLOSURE LOAD_DEREF STORE_DEREF DELETE_DEREF \
+    JUMP_BACKWARD <141> CALL_FUNCTION_EX <143> EXTENDED_ARG LIST_APPEND SET_ADD MAP_ADD \
+    LOAD_CLASSDEREF COPY_FREE_VARS <150> RESUME MATCH_CLASS <153> <154> FORMAT_VALUE \
+    BUILD_CONST_KEY_MAP BUILD_STRING <158> <159> LOAD_METHOD <161> LIST_EXTEND SET_UPDATE \
+    DICT_MERGE DICT_UPDATE PRECALL <167> <168> <169> <170> CALL KW_NAMES \
+    POP_JUMP_BACKWARD_IF_NOT_NONE POP_JUMP_BACKWARD_IF_NONE POP_JUMP_BACKWARD_IF_FALSE \
+    POP_JUMP_BACKWARD_IF_TRUE <177> <178> <179> <180> <181> <182> <183> <184> <185> <186> \
+    <187> <188> <189> <190> <191> <192> <193> <194> <195> <196> <197> <198> <199> <200> \
+    <201> <202> <203> <204> <205> <206> <207> <208> <209> <210> <211> <212> <213> <214> \
+    <215> <216> <217> <218> <219> <220> <221> <222> <223> <224> <225> <226> <227> <228> \
+    <229> <230> <231> <232> <233> <234> <235> <236> <237> <238> <239> <240> <241> <242> \
+    <243> <244> <245> <246> <247> <248> <249> <250> <251> <252> <253> <254> <255>";
+/// Python 3.12
+const OPNAMES_3_12: &str = "\
+    CACHE POP_TOP PUSH_NULL INTERPRETER_EXIT END_FOR END_SEND <6> <7> <8> NOP <10> \
+    UNARY_NEGATIVE UNARY_NOT <13> <14> UNARY_INVERT <16> RESERVED <18> <19> <20> <21> <22> \
+    <23> <24> BINARY_SUBSCR BINARY_SLICE STORE_SLICE <28> <29> GET_LEN MATCH_MAPPING \
+    MATCH_SEQUENCE MATCH_KEYS <34> PUSH_EXC_INFO CHECK_EXC_MATCH CHECK_EG_MATCH <38> <39> \
+    <40> <41> <42> <43> <44> <45> <46> <47> <48> WITH_EXCEPT_START GET_AITER GET_ANEXT \
+    BEFORE_ASYNC_WITH BEFORE_WITH END_ASYNC_FOR CLEANUP_THROW <56> <57> <58> <59> \
+    STORE_SUBSCR DELETE_SUBSCR <62> <63> <64> <65> <66> <67> GET_ITER GET_YIELD_FROM_ITER \
+    <70> LOAD_BUILD_CLASS <72> <73> LOAD_ASSERTION_ERROR RETURN_GENERATOR <76> <77> <78> \
+    <79> <80> <81> <82> RETURN_VALUE <84> SETUP_ANNOTATIONS <86> LOAD_LOCALS <88> POP_EXCEPT \
+    STORE_NAME DELETE_NAME UNPACK_SEQUENCE FOR_ITER UNPACK_EX STORE_ATTR DELETE_ATTR \
+    STORE_GLOBAL DELETE_GLOBAL SWAP LOAD_CONST LOAD_NAME BUILD_TUPLE BUILD_LIST BUILD_SET \
+    BUILD_MAP LOAD_ATTR COMPARE_OP IMPORT_NAME IMPORT_FROM JUMP_FORWARD <111> <112> <113> \
+    POP_JUMP_IF_FALSE POP_JUMP_IF_TRUE LOAD_GLOBAL IS_OP CONTAINS_OP RERAISE COPY \
+    RETURN_CONST BINARY_OP SEND LOAD_FAST STORE_FAST DELETE_FAST LOAD_FAST_CHECK \
+    POP_JUMP_IF_NOT_NONE POP_JUMP_IF_NONE RAISE_VARARGS GET_AWAITABLE MAKE_FUNCTION \
+    BUILD_SLICE JUMP_BACKWARD_NO_INTERRUPT MAKE_CELL LOAD_CLOSURE LOAD_DEREF STORE_DEREF \
+    DELETE_DEREF JUMP_BACKWARD LOAD_SUPER_ATTR CALL_FUNCTION_EX LOAD_FAST_AND_CLEAR \
+    EXTENDED_ARG LIST_APPEND SET_ADD MAP_ADD <148> COPY_FREE_VARS YIELD_VALUE RESUME \
+    MATCH_CLASS <153> <154> FORMAT_VALUE BUILD_CONST_KEY_MAP BUILD_STRING <158> <159> <160> \
+    <161> LIST_EXTEND SET_UPDATE DICT_MERGE DICT_UPDATE <166> <167> <168> <169> <170> CALL \
+    KW_NAMES CALL_INTRINSIC_1 CALL_INTRINSIC_2 LOAD_FROM_DICT_OR_GLOBALS \
+    LOAD_FROM_DICT_OR_DEREF <177> <178> <179> <180> <181> <182> <183> <184> <185> <186> \
+    <187> <188> <189> <190> <191> <192> <193> <194> <195> <196> <197> <198> <199> <200> \
+    <201> <202> <203> <204> <205> <206> <207> <208> <209> <210> <211> <212> <213> <214> \
+    <215> <216> <217> <218> <219> <220> <221> <222> <223> <224> <225> <226> <227> <228> \
+    <229> <230> <231> <232> <233> <234> <235> <236> INSTRUMENTED_LOAD_SUPER_ATTR \
+    INSTRUMENTED_POP_JUMP_IF_NONE INSTRUMENTED_POP_JUMP_IF_NOT_NONE INSTRUMENTED_RESUME \
+    INSTRUMENTED_CALL INSTRUMENTED_RETURN_VALUE INSTRUMENTED_YIELD_VALUE \
+    INSTRUMENTED_CALL_FUNCTION_EX INSTRUMENTED_JUMP_FORWARD INSTRUMENTED_JUMP_BACKWARD \
+    INSTRUMENTED_RETURN_CONST INSTRUMENTED_FOR_ITER INSTRUMENTED_POP_JUMP_IF_FALSE \
+    INSTRUMENTED_POP_JUMP_IF_TRUE INSTRUMENTED_END_FOR INSTRUMENTED_END_SEND \
+    INSTRUMENTED_INSTRUCTION INSTRUMENTED_LINE <255>";
+/// Python 3.13
+const OPNAMES_3_13: &str = "\
+    CACHE BEFORE_ASYNC_WITH BEFORE_WITH <3> BINARY_SLICE BINARY_SUBSCR CHECK_EG_MATCH \
+    CHECK_EXC_MATCH CLEANUP_THROW DELETE_SUBSCR END_ASYNC_FOR END_FOR END_SEND \
+    EXIT_INIT_CHECK FORMAT_SIMPLE FORMAT_WITH_SPEC GET_AITER RESERVED GET_ANEXT GET_ITER \
+    GET_LEN GET_YIELD_FROM_ITER INTERPRETER_EXIT LOAD_ASSERTION_ERROR LOAD_BUILD_CLASS \
+    LOAD_LOCALS MAKE_FUNCTION MATCH_KEYS MATCH_MAPPING MATCH_SEQUENCE NOP POP_EXCEPT POP_TOP \
+    PUSH_EXC_INFO PUSH_NULL RETURN_GENERATOR RETURN_VALUE SETUP_ANNOTATIONS STORE_SLICE \
+    STORE_SUBSCR TO_BOOL UNARY_INVERT UNARY_NEGATIVE UNARY_NOT WITH_EXCEPT_START BINARY_OP \
+    BUILD_CONST_KEY_MAP BUILD_LIST BUILD_MAP BUILD_SET BUILD_SLICE BUILD_STRING BUILD_TUPLE \
+    CALL CALL_FUNCTION_EX CALL_INTRINSIC_1 CALL_INTRINSIC_2 CALL_KW COMPARE_OP CONTAINS_OP \
+    CONVERT_VALUE COPY COPY_FREE_VARS DELETE_ATTR DELETE_DEREF DELETE_FAST DELETE_GLOBAL \
+    DELETE_NAME DICT_MERGE DICT_UPDATE ENTER_EXECUTOR EXTENDED_ARG FOR_ITER GET_AWAITABLE \
+    IMPORT_FROM IMPORT_NAME IS_OP JUMP_BACKWARD JUMP_BACKWARD_NO_INTERRUPT JUMP_FORWARD \
+    LIST_APPEND LIST_EXTEND LOAD_ATTR LOAD_CONST LOAD_DEREF LOAD_FAST LOAD_FAST_AND_CLEAR \
+    LOAD_FAST_CHECK LOAD_FAST_LOAD_FAST LOAD_FROM_DICT_OR_DEREF LOAD_FROM_DICT_OR_GLOBALS \
+    LOAD_GLOBAL LOAD_NAME LOAD_SUPER_ATTR MAKE_CELL MAP_ADD MATCH_CLASS POP_JUMP_IF_FALSE \
+    POP_JUMP_IF_NONE POP_JUMP_IF_NOT_NONE POP_JUMP_IF_TRUE RAISE_VARARGS RERAISE \
+    RETURN_CONST SEND SET_ADD SET_FUNCTION_ATTRIBUTE SET_UPDATE STORE_ATTR STORE_DEREF \
+    STORE_FAST STORE_FAST_LOAD_FAST STORE_FAST_STORE_FAST STORE_GLOBAL STORE_NAME SWAP \
+    UNPACK_EX UNPACK_SEQUENCE YIELD_VALUE <119> <120> <121> <122> <123> <124> <125> <126> \
+    <127> <128> <129> <130> <131> <132> <133> <134> <135> <136> <137> <138> <139> <140> \
+    <141> <142> <143> <144> <145> <146> <147> <148> RESUME <150> <151> <152> <153> <154> \
+    <155> <156> <157> <158> <159> <160> <161> <162> <163> <164> <165> <166> <167> <168> \
+    <169> <170> <171> <172> <173> <174> <175> <176> <177> <178> <179> <180> <181> <182> \
+    <183> <184> <185> <186> <187> <188> <189> <190> <191> <192> <193> <194> <195> <196> \
+    <197> <198> <199> <200> <201> <202> <203> <204> <205> <206> <207> <208> <209> <210> \
+    <211> <212> <213> <214> <215> <216> <217> <218> <219> <220> <221> <222> <223> <224> \
+    <225> <226> <227> <228> <229> <230> <231> <232> <233> <234> <235> INSTRUMENTED_RESUME \
+    INSTRUMENTED_END_FOR INSTRUMENTED_END_SEND INSTRUMENTED_RETURN_VALUE \
+    INSTRUMENTED_RETURN_CONST INSTRUMENTED_YIELD_VALUE INSTRUMENTED_LOAD_SUPER_ATTR \
+    INSTRUMENTED_FOR_ITER INSTRUMENTED_CALL INSTRUMENTED_CALL_KW \
+    INSTRUMENTED_CALL_FUNCTION_EX INSTRUMENTED_INSTRUCTION INSTRUMENTED_JUMP_FORWARD \
+    INSTRUMENTED_JUMP_BACKWARD INSTRUMENTED_POP_JUMP_IF_TRUE INSTRUMENTED_POP_JUMP_IF_FALSE \
+    INSTRUMENTED_POP_JUMP_IF_NONE INSTRUMENTED_POP_JUMP_IF_NOT_NONE INSTRUMENTED_LINE <255>";
+
+fn version(magic: u16) -> Option<u8> {
+    match magic {
+        3390..=3399 => Some(7),
+        3400..=3419 => Some(8),
+        3420..=3429 => Some(9),
+        3430..=3449 => Some(10),
+        3450..=3499 => Some(11),
+        3500..=3549 => Some(12),
+        3550..=3599 => Some(13),
+        _ => None,
+    }
+}
+
+impl PythonInstruction {
+    /// The operation with renamed or split variants folded together (_e.g._, `BINARY_ADD` is `BINARY_OP`), so that output from different versions can match
+    fn base(&self) -> &'static str {
+        match self.opname {
+            "CALL_FUNCTION" | "CALL_METHOD" => "CALL",
+            "JUMP_ABSOLUTE" | "JUMP_BACKWARD_NO_INTERRUPT" => "JUMP_BACKWARD",
+            "LOAD_FAST_CHECK" => "LOAD_FAST",
+            "LOAD_METHOD" => "LOAD_ATTR",
+            "POP_JUMP_FORWARD_IF_FALSE" | "POP_JUMP_BACKWARD_IF_FALSE" => "POP_JUMP_IF_FALSE",
+            "POP_JUMP_FORWARD_IF_TRUE" | "POP_JUMP_BACKWARD_IF_TRUE" => "POP_JUMP_IF_TRUE",
+            "POP_JUMP_FORWARD_IF_NONE" | "POP_JUMP_BACKWARD_IF_NONE" => "POP_JUMP_IF_NONE",
+            "POP_JUMP_FORWARD_IF_NOT_NONE" | "POP_JUMP_BACKWARD_IF_NOT_NONE" => {
+                "POP_JUMP_IF_NOT_NONE"
+            }
+            opname
+                if LEGACY_BINARY_OPERATIONS
+                    .iter()
+                    .any(|(legacy, _)| *legacy == opname) =>
+            {
+                "BINARY_OP"
+            }
+            opname => opname,
+        }
+    }
+    fn family(&self) -> Family {
+        let base = self.base();
+        if is_jump(base) {
+            Family::Jump
+        } else if base.starts_with("BINARY_") || base.starts_with("UNARY_") {
+            Family::Arith
+        } else if base.starts_with("BUILD_")
+            || base.starts_with("LIST_")
+            || base.starts_with("SET_")
+            || base.starts_with("DICT_")
+            || base == "MAP_ADD"
+        {
+            Family::Build
+        } else if base.starts_with("CALL") || base == "PRECALL" {
+            Family::Call
+        } else if matches!(
+            base,
+            "COMPARE_OP" | "CONTAINS_OP" | "IS_OP" | "CHECK_EXC_MATCH"
+        ) {
+            Family::Compare
+        } else if base.starts_with("IMPORT_") {
+            Family::Import
+        } else if base.starts_with("LOAD_") {
+            Family::Load
+        } else if is_return(base) {
+            Family::Return
+        } else if base.starts_with("STORE_") || base.starts_with("DELETE_") {
+            Family::Store
+        } else if base.starts_with("ROT_")
+            || base.starts_with("DUP_TOP")
+            || matches!(base, "COPY" | "NOP" | "POP_TOP" | "PUSH_NULL" | "SWAP")
+        {
+            Family::Stack
+        } else {
+            Family::Other
+        }
+    }
+    fn is_flow_control(&self) -> bool {
+        (is_jump(self.opname) && !self.opname.starts_with("SETUP_")) || is_return(self.opname)
+    }
+}
+
+/// Whether an operation has a jump target; the `SETUP_` operations of 3.10 and earlier only push a handler but their targets still start blocks
+fn is_jump(opname: &str) -> bool {
+    opname.contains("JUMP")
+        || opname.starts_with("SETUP_")
+        || matches!(
+            opname,
+            "CALL_FINALLY" | "CONTINUE_LOOP" | "FOR_ITER" | "SEND"
+        )
+}
+fn is_return(opname: &str) -> bool {
+    matches!(
+        opname,
+        "BREAK_LOOP" | "RAISE_VARARGS" | "RERAISE" | "RETURN_CONST" | "RETURN_VALUE"
+    )
+}
+
+impl crate::Program for PythonBytecode {
+    const GAP: i32 = 2;
+
+    type ParseError = PycError;
+
+    type ParseOptions = ();
+
+    type Function = PythonFunction;
+
+    fn parse(
+        file: impl AsRef<std::path::Path>,
+        _options: Self::ParseOptions,
+    ) -> Result<Self, Self::ParseError> {
+        let buffer = std::fs::read(file).map_err(PycError::Io)?;
+        Self::parse_bytes(&buffer, ())
+    }
+
+    fn parse_bytes(buffer: &[u8], _options: Self::ParseOptions) -> Result<Self, Self::ParseError> {
+        let mut cursor = Cursor {
+            data: buffer,
+            position: 0,
+        };
+        let magic = cursor.u16()?;
+        let minor = match version(magic) {
+            Some(minor) if cursor.bytes(2)? == b"\r\n" => minor,
+            _ => return Err(PycError::UnsupportedVersion(magic)),
+        };
+        // The rest of the header is flags and either a timestamp and size or a source hash
+        cursor.bytes(12)?;
+        let mut reader = Unmarshaller {
+            cursor,
+            minor,
+            references: Vec::new(),
+            depth: 0,
+        };
+        let module = match &*reader.object()? {
+            Object::Code(code) => code.clone(),
+            _ => return Err(PycError::Malformed("module is not a code object")),
+        };
+        let opnames: Vec<&'static str> = match minor {
+            7 => OPNAMES_3_7,
+            8 => OPNAMES_3_8,
+            9 => OPNAMES_3_9,
+            10 => OPNAMES_3_10,
+            11 => OPNAMES_3_11,
+            12 => OPNAMES_3_12,
+            _ => OPNAMES_3_13,
+        }
+        .split_whitespace()
+        .collect();
+        let mut funcs = BTreeMap::new();
+        collect_functions(&module, &Scope::Top, minor, &opnames, &mut funcs)?;
+        Ok(PythonBytecode { funcs })
+    }
+
+    fn get(&self, name: &str) -> Option<&Self::Function> {
+        self.funcs.get(name)
+    }
+
+    fn functions<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Self::Function> + 'a> {
+        Box::new(self.funcs.values())
+    }
+}
+impl crate::Function for PythonFunction {
+    type BasicBlock = PythonBlock;
+
+    fn blocks<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Self::BasicBlock> + 'a> {
+        Box::new(self.blocks.iter())
+    }
+
+    fn name<'a>(&'a self) -> Cow<'a, str> {
+        Cow::Borrowed(&self.name)
+    }
+}
+impl crate::BasicBlock for PythonBlock {
+    type Instruction = PythonInstruction;
+
+    type Terminator = Option<PythonInstruction>;
+
+    fn get(&self, index: usize) -> &Self::Instruction {
+        &self.instructions[index]
+    }
+
+    fn len(&self) -> usize {
+        self.instructions.len()
+    }
+
+    fn name<'a>(&'a self) -> Cow<'a, str> {
+        Cow::Owned(format!("{}", self.offset))
+    }
+
+    fn terminator(&self) -> &Self::Terminator {
+        &self.terminator
+    }
+}
+impl crate::Instruction for PythonInstruction {
+    const EQUIVALENT: i32 = 4;
+    fn score(&self, other: &Self) -> i32 {
+        if self.base() == other.base() {
+            if self.argument == other.argument {
+                4
+            } else {
+                3
+            }
+        } else if self.family() == other.family() && self.family() != Family::Other {
+            2
+        } else {
+            0
+        }
+    }
+
+    fn render<'a>(&self) -> Cow<'a, str> {
+        let mut result = self.opname.to_string();
+        if let Some(argument) = &self.argument {
+            result.push(' ');
+            result.push_str(argument);
+        }
+        if let Some(target) = self.target {
+            result.push_str(&format!(" to {}", target));
+        }
+        Cow::Owned(result)
+    }
+
+    fn class(&self) -> crate::InstructionClass {
+        match self.family() {
+            Family::Arith
+                if matches!(
+                    self.argument.as_deref(),
+                    Some("&" | "|" | "^" | "<<" | ">>" | "&=" | "|=" | "^=" | "<<=" | ">>=")
+                ) || self.base() == "UNARY_INVERT" =>
+            {
+                crate::InstructionClass::Logic
+            }
+            Family::Arith => crate::InstructionClass::Arith,
+            Family::Call => crate::InstructionClass::Call,
+            Family::Compare => crate::InstructionClass::Compare,
+            Family::Jump | Family::Return => crate::InstructionClass::Branch,
+            Family::Load | Family::Store | Family::Stack => crate::InstructionClass::Memory,
+            Family::Build | Family::Import | Family::Other => crate::InstructionClass::Other,
+        }
+    }
+}
+
+struct Cursor<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+impl<'a> Cursor<'a> {
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8], PycError> {
+        let result = self
+            .data
+            .get(self.position..self.position + length)
+            .ok_or(PycError::Malformed("unexpected end of data"))?;
+        self.position += length;
+        Ok(result)
+    }
+    fn u8(&mut self) -> Result<u8, PycError> {
+        Ok(self.bytes(1)?[0])
+    }
+    fn u16(&mut self) -> Result<u16, PycError> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+    fn i32(&mut self) -> Result<i32, PycError> {
+        let b = self.bytes(4)?;
+        Ok(i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+    fn f64(&mut self) -> Result<f64, PycError> {
+        let mut b = [0; 8];
+        b.copy_from_slice(self.bytes(8)?);
+        Ok(f64::from_le_bytes(b))
+    }
+    fn length(&mut self) -> Result<usize, PycError> {
+        usize::try_from(self.i32()?).map_err(|_| PycError::Malformed("negative length"))
+    }
+}
+
+/// A value stored in a marshal stream
+enum Object {
+    /// The end of a dictionary
+    Null,
+    None,
+    Bool(bool),
+    Int(i128),
+    Float(f64),
+    Complex(f64, f64),
+    Bytes(Vec<u8>),
+    Str(String),
+    Tuple(Vec<Rc<Object>>),
+    List(Vec<Rc<Object>>),
+    Set(Vec<Rc<Object>>),
+    FrozenSet(Vec<Rc<Object>>),
+    Dict(Vec<(Rc<Object>, Rc<Object>)>),
+    Code(Rc<Code>),
+    /// A value that is only displayed by name (_e.g._, `Ellipsis`)
+    Opaque(&'static str),
+}
+impl Display for Object {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn items(f: &mut std::fmt::Formatter<'_>, items: &[Rc<Object>]) -> std::fmt::Result {
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    f.write_str(", ")?;
+                }
+                item.fmt(f)?;
+            }
+            Ok(())
+        }
+        match self {
+            Object::Null => f.write_str("NULL"),
+            Object::None => f.write_str("None"),
+            Object::Bool(true) => f.write_str("True"),
+            Object::Bool(false) => f.write_str("False"),
+            Object::Int(value) => write!(f, "{}", value),
+            Object::Float(value) => write!(f, "{:?}", value),
+            Object::Complex(real, imaginary) => write!(f, "({:?}{:+?}j)", real, imaginary),
+            Object::Bytes(bytes) => {
+                f.write_str("b'")?;
+                for &b in bytes {
+                    match b {
+                        b'\'' | b'\\' => write!(f, "\\{}", b as char)?,
+                        b'\n' => f.write_str("\\n")?,
+                        b'\r' => f.write_str("\\r")?,
+                        b'\t' => f.write_str("\\t")?,
+                        0x20..=0x7e => write!(f, "{}", b as char)?,
+                        _ => write!(f, "\\x{:02x}", b)?,
+                    }
+                }
+                f.write_str("'")
+            }
+            Object::Str(s) => {
+                f.write_str("'")?;
+                for c in s.chars() {
+                    match c {
+                        '\'' | '\\' => write!(f, "\\{}", c)?,
+                        '\n' => f.write_str("\\n")?,
+                        '\r' => f.write_str("\\r")?,
+                        '\t' => f.write_str("\\t")?,
+                        c if c.is_control() => write!(f, "\\x{:02x}", c as u32)?,
+                        c => write!(f, "{}", c)?,
+                    }
+                }
+                f.write_str("'")
+            }
+            Object::Tuple(values) => {
+                f.write_str("(")?;
+                items(f, values)?;
+                f.write_str(if values.len() == 1 { ",)" } else { ")" })
+            }
+            Object::List(values) => {
+                f.write_str("[")?;
+                items(f, values)?;
+                f.write_str("]")
+            }
+            Object::Set(values) => {
+                f.write_str("{")?;
+                items(f, values)?;
+                f.write_str("}")
+            }
+            Object::FrozenSet(values) => {
+                f.write_str("frozenset({")?;
+                items(f, values)?;
+                f.write_str("})")
+            }
+            Object::Dict(entries) => {
+                f.write_str("{")?;
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}: {}", key, value)?;
+                }
+                f.write_str("}")
+            }
+            Object::Code(code) => write!(f, "<code object {}>", code.name),
+            Object::Opaque(name) => f.write_str(name),
+        }
+    }
+}
+
+struct Code {
+    flags: i32,
+    code: Vec<u8>,
+    constants: Vec<Rc<Object>>,
+    names: Vec<String>,
+    /// The variables indexed by the `_FAST` operations
+    locals: Vec<String>,
+    /// The variables indexed by the `_DEREF` operations; in 3.11 and later, these are the same as the locals
+    cells: Vec<String>,
+    name: String,
+    /// The qualified name, which is only stored in 3.11 and later
+    qualified_name: Option<String>,
+    exception_table: Vec<u8>,
+}
+
+/// The flag on a code object for a function body (as opposed to a module or class body)
+const CO_OPTIMIZED: i32 = 1;
+/// The flag on a marshalled object that adds it to the table of objects that later objects can refer to
+const FLAG_REF: u8 = 0x80;
+
+/// The deepest nesting of objects that can be read
+///
+/// CPython has the same kind of limit (`MAX_MARSHAL_STACK_DEPTH`, 2000) to stop a malformed file from exhausting the stack. Each level takes more stack here, so the limit is lower, but it is still far deeper than any compiled module, where each nested function only adds a code object and its constants.
+const MAX_MARSHAL_DEPTH: usize = 200;
+
+struct Unmarshaller<'a> {
+    cursor: Cursor<'a>,
+    minor: u8,
+    references: Vec<Rc<Object>>,
+    /// The number of objects currently being read that contain the next one
+    depth: usize,
+}
+impl Unmarshaller<'_> {
+    fn object(&mut self) -> Result<Rc<Object>, PycError> {
+        if self.depth >= MAX_MARSHAL_DEPTH {
+            return Err(PycError::Malformed("objects are nested too deeply"));
+        }
+        self.depth += 1;
+        let object = self.nested_object();
+        self.depth -= 1;
+        object
+    }
+    fn nested_object(&mut self) -> Result<Rc<Object>, PycError> {
+        let code = self.cursor.u8()?;
+        if code & !FLAG_REF == b'r' {
+            let index = self.cursor.length()?;
+            return self
+                .references
+                .get(index)
+                .cloned()
+                .ok_or(PycError::Malformed("reference to unknown object"));
+        }
+        // The slot is reserved before reading the contents since objects are numbered in the order they start
+        let reference = if code & FLAG_REF != 0 {
+            self.references.push(Rc::new(Object::Null));
+            Some(self.references.len() - 1)
+        } else {
+            None
+        };
+        let object = Rc::new(match code & !FLAG_REF {
+            b'0' => Object::Null,
+            b'N' => Object::None,
+            b'F' => Object::Bool(false),
+            b'T' => Object::Bool(true),
+            b'S' => Object::Opaque("StopIteration"),
+            b'.' => Object::Opaque("Ellipsis"),
+            b'i' => Object::Int(self.cursor.i32()? as i128),
+            b'l' => {
+                // Arbitrary precision integers are stored as 15-bit digits, least significant first
+                let size = self.cursor.i32()?;
+                let digits = size.unsigned_abs() as usize;
+                let mut value: i128 = 0;
+                for index in 0..digits {
+                    let digit = self.cursor.u16()? as i128;
+                    if index < 8 {
+                        value |= digit << (15 * index);
+                    }
+                }
+                if digits > 8 {
+                    Object::Opaque("<int>")
+                } else if size < 0 {
+                    Object::Int(-value)
+                } else {
+                    Object::Int(value)
+                }
+            }
+            b'f' => Object::Float(self.short_float()?),
+            b'g' => Object::Float(self.cursor.f64()?),
+            b'x' => Object::Complex(self.short_float()?, self.short_float()?),
+            b'y' => Object::Complex(self.cursor.f64()?, self.cursor.f64()?),
+            b's' => {
+                let length = self.cursor.length()?;
+                Object::Bytes(self.cursor.bytes(length)?.to_vec())
+            }
+            b't' | b'u' | b'a' | b'A' => {
+                let length = self.cursor.length()?;
+                Object::Str(String::from_utf8_lossy(self.cursor.bytes(length)?).into_owned())
+            }
+            b'z' | b'Z' => {
+                let length = self.cursor.u8()? as usize;
+                Object::Str(String::from_utf8_lossy(self.cursor.bytes(length)?).into_owned())
+            }
+            b'(' => {
+                let length = self.cursor.length()?;
+                Object::Tuple(self.objects(length)?)
+            }
+            b')' => {
+                let length = self.cursor.u8()? as usize;
+                Object::Tuple(self.objects(length)?)
+            }
+            b'[' => {
+                let length = self.cursor.length()?;
+                Object::List(self.objects(length)?)
+            }
+            b'<' => {
+                let length = self.cursor.length()?;
+                Object::Set(self.objects(length)?)
+            }
+            b'>' => {
+                let length = self.cursor.length()?;
+                Object::FrozenSet(self.objects(length)?)
+            }
+            b'{' => {
+                let mut entries = Vec::new();
+                loop {
+                    let key = self.object()?;
+                    if let Object::Null = *key {
+                        break;
+                    }
+                    entries.push((key, self.object()?));
+                }
+                Object::Dict(entries)
+            }
+            b'c' => Object::Code(Rc::new(self.code()?)),
+            _ => return Err(PycError::Malformed("unknown marshal type")),
+        });
+        if let Some(index) = reference {
+            self.references[index] = object.clone();
+        }
+        Ok(object)
+    }
+    fn objects(&mut self, length: usize) -> Result<Vec<Rc<Object>>, PycError> {
+        (0..length).map(|_| self.object()).collect()
+    }
+    fn short_float(&mut self) -> Result<f64, PycError> {
+        let length = self.cursor.u8()? as usize;
+        std::str::from_utf8(self.cursor.bytes(length)?)
+            .ok()
+            .and_then(|text| text.parse().ok())
+            .ok_or(PycError::Malformed("invalid float"))
+    }
+    fn bytes(&mut self) -> Result<Vec<u8>, PycError> {
+        match &*self.object()? {
+            Object::Bytes(bytes) => Ok(bytes.clone()),
+            _ => Err(PycError::Malformed("expected bytes")),
+        }
+    }
+    fn string(&mut self) -> Result<String, PycError> {
+        match &*self.object()? {
+            Object::Str(s) => Ok(s.clone()),
+            _ => Err(PycError::Malformed("expected a string")),
+        }
+    }
+    fn tuple(&mut self) -> Result<Vec<Rc<Object>>, PycError> {
+        match &*self.object()? {
+            Object::Tuple(values) => Ok(values.clone()),
+            _ => Err(PycError::Malformed("expected a tuple")),
+        }
+    }
+    fn strings(&mut self) -> Result<Vec<String>, PycError> {
+        self.tuple()?
+            .iter()
+            .map(|value| match &**value {
+                Object::Str(s) => Ok(s.clone()),
+                _ => Err(PycError::Malformed("expected a string")),
+            })
+            .collect()
+    }
+    /// Read a code object, whose fields have changed between versions
+    fn code(&mut self) -> Result<Code, PycError> {
+        // The argument counts, the number of locals (before 3.11), the stack size, and the flags
+        let counts = match self.minor {
+            7 => 5,
+            8..=10 => 6,
+            _ => 5,
+        };
+        let mut flags = 0;
+        for _ in 0..counts {
+            flags = self.cursor.i32()?;
+        }
+        let code = self.bytes()?;
+        let constants = self.tuple()?;
+        let names = self.strings()?;
+        if self.minor < 11 {
+            let locals = self.strings()?;
+            let free = self.strings()?;
+            let mut cells = self.strings()?;
+            cells.extend(free);
+            self.string()?;
+            let name = self.string()?;
+            self.cursor.i32()?;
+            self.bytes()?;
+            Ok(Code {
+                flags,
+                code,
+                constants,
+                names,
+                locals,
+                cells,
+                name,
+                qualified_name: None,
+                exception_table: Vec::new(),
+            })
+        } else {
+            let locals = self.strings()?;
+            self.bytes()?;
+            self.string()?;
+            let name = self.string()?;
+            let qualified_name = self.string()?;
+            self.cursor.i32()?;
+            self.bytes()?;
+            let exception_table = self.bytes()?;
+            Ok(Code {
+                flags,
+                code,
+                constants,
+                names,
+                cells: locals.clone(),
+                locals,
+                name,
+                qualified_name: Some(qualified_name),
+                exception_table,
+            })
+        }
+    }
+}
+
+/// The kind of code object that contains another, which determines how the nested one is named before 3.11
+enum Scope {
+    Top,
+    Module,
+    Class(String),
+    Function(String),
+}
+
+fn collect_functions(
+    code: &Code,
+    scope: &Scope,
+    minor: u8,
+    opnames: &[&'static str],
+    funcs: &mut BTreeMap<String, PythonFunction>,
+) -> Result<(), PycError> {
+    let qualified_name = match (&code.qualified_name, scope) {
+        (Some(name), _) => name.clone(),
+        (None, Scope::Top | Scope::Module) => code.name.clone(),
+        (None, Scope::Class(parent)) => format!("{}.{}", parent, code.name),
+        (None, Scope::Function(parent)) => format!("{}.<locals>.{}", parent, code.name),
+    };
+    // Lambdas and redefined functions can share a name, so later ones are numbered
+    let mut name = qualified_name.clone();
+    let mut count = 1;
+    while funcs.contains_key(&name) {
+        count += 1;
+        name = format!("{}#{}", qualified_name, count);
+    }
+    let blocks = parse_code(code, minor, opnames)?;
+    funcs.insert(name.clone(), PythonFunction { blocks, name });
+
+    let inner = match scope {
+        Scope::Top => Scope::Module,
+        _ if code.flags & CO_OPTIMIZED != 0 => Scope::Function(qualified_name),
+        _ => Scope::Class(qualified_name),
+    };
+    for constant in &code.constants {
+        if let Object::Code(nested) = &**constant {
+            collect_functions(nested, &inner, minor, opnames, funcs)?;
+        }
+    }
+    Ok(())
+}
+
+/// Find the protected ranges and handlers in a 3.11 and later exception table, which is a sequence of 6-bit variable length integers counting code units
+fn exception_boundaries(table: &[u8]) -> Result<Vec<usize>, PycError> {
+    fn varint(bytes: &mut std::slice::Iter<'_, u8>) -> Result<usize, PycError> {
+        let mut value = 0;
+        loop {
+            let b = *bytes
+                .next()
+                .ok_or(PycError::Malformed("truncated exception table"))?;
+            value = (value << 6) | (b & 63) as usize;
+            if b & 64 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+    let mut bytes = table.iter();
+    let mut boundaries = Vec::new();
+    while !bytes.as_slice().is_empty() {
+        let start = varint(&mut bytes)?;
+        let length = varint(&mut bytes)?;
+        let target = varint(&mut bytes)?;
+        // The stack depth of the handler
+        varint(&mut bytes)?;
+        let end = start
+            .checked_add(length)
+            .ok_or(PycError::Malformed("exception table range is too large"))?;
+        for units in [start, end, target] {
+            boundaries.push(
+                units
+                    .checked_mul(2)
+                    .ok_or(PycError::Malformed("exception table range is too large"))?,
+            );
+        }
+    }
+    Ok(boundaries)
+}
+
+fn parse_code(
+    code: &Code,
+    minor: u8,
+    opnames: &[&'static str],
+) -> Result<Vec<PythonBlock>, PycError> {
+    let lookup = |table: &[String], index: usize| {
+        table
+            .get(index)
+            .cloned()
+            .ok_or(PycError::Malformed("argument out of range"))
+    };
+    let have_argument = if minor >= 13 { 44 } else { 90 };
+    let mut leaders: BTreeSet<usize> = exception_boundaries(&code.exception_table)?
+        .into_iter()
+        .collect();
+    let mut instructions = Vec::new();
+    let mut extended = 0;
+    let mut prefix = None;
+    for (unit, pair) in code.code.chunks_exact(2).enumerate() {
+        let offset = unit * 2;
+        let (opcode, raw) = (pair[0], pair[1] as usize);
+        // Inline caches for the specializing interpreter are stored as extra code units after the operation
+        if minor >= 11 && opcode == 0 {
+            continue;
+        }
+        let opname = opnames[opcode as usize];
+        // Large arguments are built up by prefixes which are folded into the operation they apply to
+        if opname == "EXTENDED_ARG" {
+            extended = (extended | raw) << 8;
+            prefix.get_or_insert(offset);
+            continue;
+        }
+        let arg = extended | raw;
+        extended = 0;
+        let start = prefix.take().unwrap_or(offset);
+        let caches = if minor >= 11 {
+            code.code[offset + 2..]
+                .chunks_exact(2)
+                .take_while(|pair| pair[0] == 0)
+                .count()
+        } else {
+            0
+        };
+        let mut target = None;
+        let argument = match opname {
+            _ if is_jump(opname) => {
+                // Chains of extended arguments can make a jump that is beyond any code
+                let out_of_range = || PycError::Malformed("jump target out of range");
+                target = Some(if minor >= 11 {
+                    let next = offset + 2 + caches * 2;
+                    let distance = arg.checked_mul(2).ok_or_else(out_of_range)?;
+                    if opname.contains("BACKWARD") {
+                        next.checked_sub(distance)
+                            .ok_or(PycError::Malformed("jump before start of code"))?
+                    } else {
+                        next.checked_add(distance).ok_or_else(out_of_range)?
+                    }
+                } else {
+                    // Before 3.10, arguments count bytes rather than code units
+                    let scale = if minor >= 10 { 2 } else { 1 };
+                    let distance = arg.checked_mul(scale).ok_or_else(out_of_range)?;
+                    if opname == "CONTINUE_LOOP"
+                        || (opname.contains("JUMP") && opname != "JUMP_FORWARD")
+                    {
+                        distance
+                    } else {
+                        (offset + 2)
+                            .checked_add(distance)
+                            .ok_or_else(out_of_range)?
+                    }
+                });
+                None
+            }
+            "LOAD_CONST" | "RETURN_CONST" | "KW_NAMES" => Some(format!(
+                "{}",
+                code.constants
+                    .get(arg)
+                    .ok_or(PycError::Malformed("argument out of range"))?
+            )),
+            // The low bits of these arguments are flags for the calling convention
+            "LOAD_GLOBAL" if minor >= 11 => Some(lookup(&code.names, arg >> 1)?),
+            "LOAD_ATTR" if minor >= 12 => Some(lookup(&code.names, arg >> 1)?),
+            "LOAD_SUPER_ATTR" => Some(lookup(&code.names, arg >> 2)?),
+            _ if NAME_OPERATIONS.contains(&opname) => Some(lookup(&code.names, arg)?),
+            // Pairs of locals are packed into one argument
+            "LOAD_FAST_LOAD_FAST" | "STORE_FAST_LOAD_FAST" | "STORE_FAST_STORE_FAST" => {
+                Some(format!(
+                    "{}, {}",
+                    lookup(&code.locals, arg >> 4)?,
+                    lookup(&code.locals, arg & 15)?
+                ))
+            }
+            _ if LOCAL_OPERATIONS.contains(&opname) => Some(lookup(&code.locals, arg)?),
+            _ if CELL_OPERATIONS.contains(&opname) => Some(lookup(&code.cells, arg)?),
+            "COMPARE_OP" => {
+                let shift = match minor {
+                    7..=11 => 0,
+                    12 => 4,
+                    _ => 5,
+                };
+                Some(
+                    COMPARISONS
+                        .get(arg >> shift)
+                        .ok_or(PycError::Malformed("unknown comparison"))?
+                        .to_string(),
+                )
+            }
+            "BINARY_OP" => Some(
+                BINARY_OPERATORS
+                    .get(arg)
+                    .ok_or(PycError::Malformed("unknown binary operator"))?
+                    .to_string(),
+            ),
+            _ => match LEGACY_BINARY_OPERATIONS
+                .iter()
+                .find(|(legacy, _)| *legacy == opname)
+            {
+                Some((_, operator)) => Some(operator.to_string()),
+                None if opcode >= have_argument => Some(format!("{}", arg)),
+                None => None,
+            },
+        };
+        let instruction = PythonInstruction {
+            opname,
+            argument,
+            target,
+        };
+        if instruction.is_flow_control() {
+            leaders.insert(offset + 2 + caches * 2);
+        }
+        leaders.extend(target);
+        instructions.push((start, offset, instruction));
+    }
+
+    let mut blocks = Vec::new();
+    let mut current = PythonBlock {
+        instructions: Vec::new(),
+        offset: 0,
+        terminator: None,
+    };
+    for (start, offset, instruction) in instructions {
+        // A jump to an instruction with an extended argument lands on its first prefix
+        if start != current.offset && leaders.range(start..=offset).next().is_some() {
+            blocks.push(std::mem::replace(
+                &mut current,
+                PythonBlock {
+                    instructions: Vec::new(),
+                    offset: start,
+                    terminator: None,
+                },
+            ));
+        }
+        if instruction.is_flow_control() {
+            current.terminator = Some(instruction);
+        } else {
+            current.instructions.push(instruction);
+        }
+    }
+    blocks.push(current);
+    Ok(blocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BasicBlock, Function, Instruction, Program};
+
+    const GREETER: &[u8] = include_bytes!("../testdata/greeter-311.pyc");
+
+    fn try_parse(buffer: &[u8]) -> Result<super::PythonBytecode, super::PycError> {
+        super::PythonBytecode::parse_bytes(buffer, ())
+    }
+
+    fn parse(buffer: &[u8]) -> super::PythonBytecode {
+        try_parse(buffer).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// The blocks of a function, with the rendered instructions in each, the terminator last
+    fn rendered(function: &super::PythonFunction) -> Vec<(String, Vec<String>)> {
+        function
+            .blocks()
+            .map(|block| {
+                (
+                    block.name().into_owned(),
+                    (0..block.len())
+                        .map(|index| block.get(index).render().into_owned())
+                        .chain(block.terminator().iter().map(|t| t.render().into_owned()))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    fn instruction(opname: &'static str, argument: Option<&str>) -> super::PythonInstruction {
+        super::PythonInstruction {
+            opname,
+            argument: argument.map(str::to_string),
+            target: None,
+        }
+    }
+
+    #[test]
+    fn code_objects_and_blocks() {
+        let program = parse(GREETER);
+        let names: Vec<_> = program
+            .functions()
+            .map(|function| function.name().into_owned())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "<module>",
+                "Greeter",
+                "Greeter.greet",
+                "outer",
+                "outer.<locals>.inner"
+            ]
+        );
+        // Inline caches are skipped, so the loop body is the same instructions `dis` shows
+        let greet = rendered(program.get("Greeter.greet").unwrap());
+        assert_eq!(
+            greet,
+            [
+                (
+                    "0".to_string(),
+                    vec![
+                        "RESUME 0".to_string(),
+                        "LOAD_FAST names".to_string(),
+                        "GET_ITER".to_string()
+                    ]
+                ),
+                ("6".to_string(), vec!["FOR_ITER to 48".to_string()]),
+                (
+                    "8".to_string(),
+                    [
+                        "STORE_FAST name",
+                        "LOAD_GLOBAL print",
+                        "LOAD_CONST 'Hello, '",
+                        "LOAD_FAST name",
+                        "BINARY_OP +",
+                        "PRECALL 1",
+                        "CALL 1",
+                        "POP_TOP",
+                        "JUMP_BACKWARD to 6"
+                    ]
+                    .map(str::to_string)
+                    .to_vec()
+                ),
+                (
+                    "48".to_string(),
+                    vec!["LOAD_CONST None".to_string(), "RETURN_VALUE".to_string()]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn exception_table_starts_blocks() {
+        let program = parse(GREETER);
+        let blocks: Vec<_> = program
+            .get("outer")
+            .unwrap()
+            .blocks()
+            .map(|block| block.name().into_owned())
+            .collect();
+        assert_eq!(blocks, ["0", "16", "36", "38", "56", "58", "64", "66"]);
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let mut buffer = GREETER.to_vec();
+        buffer[..2].copy_from_slice(&3000u16.to_le_bytes());
+        assert!(matches!(
+            try_parse(&buffer),
+            Err(super::PycError::UnsupportedVersion(3000))
+        ));
+    }
+
+    #[test]
+    fn renamed_operations_match() {
+        let add = instruction("BINARY_OP", Some("+"));
+        assert_eq!(add.score(&instruction("BINARY_ADD", Some("+"))), 4);
+        assert_eq!(add.score(&instruction("BINARY_SUBTRACT", Some("-"))), 3);
+        assert_eq!(add.score(&instruction("UNARY_NEGATIVE", None)), 2);
+        assert_eq!(
+            instruction("CALL", Some("1")).score(&instruction("CALL_FUNCTION", Some("1"))),
+            4
+        );
+        assert_eq!(
+            instruction("JUMP_BACKWARD", None).score(&instruction("JUMP_ABSOLUTE", None)),
+            4
+        );
+        assert_eq!(
+            instruction("LOAD_ATTR", Some("greet"))
+                .score(&instruction("LOAD_METHOD", Some("greet"))),
+            4
+        );
+        assert_eq!(add.score(&instruction("LOAD_FAST", Some("x"))), 0);
+    }
+
+    #[test]
+    fn truncated_file() {
+        for length in 0..GREETER.len() {
+            assert!(try_parse(&GREETER[..length]).is_err(), "{} bytes", length);
+        }
+        // An object type that doesn't exist after a valid header
+        let mut buffer = GREETER[..16].to_vec();
+        buffer.push(b'?');
+        assert!(matches!(
+            try_parse(&buffer),
+            Err(super::PycError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn deeply_nested_objects() {
+        let nested = |depth| {
+            let mut buffer = GREETER[..16].to_vec();
+            for _ in 0..depth {
+                buffer.extend([b')', 1]);
+            }
+            buffer.push(b'N');
+            try_parse(&buffer)
+        };
+        assert!(matches!(
+            nested(100_000),
+            Err(super::PycError::Malformed("objects are nested too deeply"))
+        ));
+        assert!(matches!(
+            nested(super::MAX_MARSHAL_DEPTH - 1),
+            Err(super::PycError::Malformed("module is not a code object"))
+        ));
+    }
+
+    #[test]
+    fn huge_offsets() {
+        let opnames: Vec<_> = super::OPNAMES_3_11.split_whitespace().collect();
+        let extended = opnames
+            .iter()
+            .position(|opname| *opname == "EXTENDED_ARG")
+            .unwrap() as u8;
+        let jump = opnames
+            .iter()
+            .position(|opname| *opname == "JUMP_FORWARD")
+            .unwrap() as u8;
+        let code = |code: Vec<u8>, exception_table: Vec<u8>| super::Code {
+            flags: 0,
+            code,
+            constants: Vec::new(),
+            names: Vec::new(),
+            locals: Vec::new(),
+            cells: Vec::new(),
+            name: "f".to_string(),
+            qualified_name: None,
+            exception_table,
+        };
+        // Enough extended arguments to fill every bit of the jump's argument
+        let mut jumps: Vec<u8> =
+            std::iter::repeat_n([extended, 0xFF], std::mem::size_of::<usize>())
+                .flatten()
+                .collect();
+        jumps.extend([jump, 0xFF]);
+        assert!(super::parse_code(&code(jumps, Vec::new()), 11, &opnames).is_err());
+        // A range whose start is as large as the variable length integer can hold
+        let mut table = vec![0x7F; 11];
+        table.extend([0x3F, 1, 0, 0]);
+        assert!(super::parse_code(&code(Vec::new(), table), 11, &opnames).is_err());
+    }
+}
@@ -0,0 +1,31 @@
+// Source of Counter.class and counter.jar, a class file and a JAR containing it:
+// javac --release 8 -g:none -d out Counter.java && cp out/example/Counter.class . && jar cfM counter.jar -C out example/Counter.class
+package example;
+
+public class Counter {
+  private int count;
+
+  public int add(int amount) {
+    count += amount;
+    return count;
+  }
+
+  public static String describe(int value) {
+    switch (value) {
+      case 0:
+        return "none";
+      case 1:
+        return "one";
+      default:
+        return value < 0 ? "negative" : "many";
+    }
+  }
+
+  public static long sum(long[] values) {
+    long total = 0;
+    for (long value : values) {
+      total += value;
+    }
+    return total;
+  }
+}
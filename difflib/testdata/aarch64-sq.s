@@ -0,0 +1,32 @@
+	.text
+	.file	"sq.ll"
+	.globl	sq                              // -- Begin function sq
+	.p2align	2
+	.type	sq,@function
+sq:                                     // @sq
+	.cfi_startproc
+// %bb.0:
+	mul	w0, w0, w0
+	ret
+.Lfunc_end0:
+	.size	sq, .Lfunc_end0-sq
+	.cfi_endproc
+                                        // -- End function
+	.globl	twice                           // -- Begin function twice
+	.p2align	2
+	.type	twice,@function
+twice:                                  // @twice
+	.cfi_startproc
+// %bb.0:
+	str	x30, [sp, #-16]!                // 8-byte Folded Spill
+	.cfi_def_cfa_offset 16
+	.cfi_offset w30, -16
+	bl	sq
+	add	w0, w0, w0
+	ldr	x30, [sp], #16                  // 8-byte Folded Reload
+	ret
+.Lfunc_end1:
+	.size	twice, .Lfunc_end1-twice
+	.cfi_endproc
+                                        // -- End function
+	.section	".note.GNU-stack","",@progbits
@@ -0,0 +1,8 @@
+// Source of x86_64-addresses, a position-independent executable with no C library:
+// gcc -O1 -fpie -pie -nostdlib -Wl,--build-id=none -Wl,-e,third -o x86_64-addresses addresses.c
+
+int table[4] = {1, 2, 3, 4};
+
+int third(void) { return table[2]; }
+
+const char *greeting(void) { return "hello"; }
@@ -0,0 +1,46 @@
+; Input for the built-in LLVM text IR parser, with unnamed blocks and parameters, a multi-line switch, and debug locations; check it with:
+; llvm-as branches.ll -o /dev/null (LLVM 15 or newer, since it uses opaque pointers)
+define i32 @classify(i32 %0) !dbg !6 {
+  %2 = icmp slt i32 %0, 0, !dbg !9
+  br i1 %2, label %3, label %4, !dbg !9
+
+3:
+  ret i32 -1, !dbg !10
+
+4:
+  switch i32 %0, label %6 [
+    i32 0, label %5
+    i32 1, label %5
+  ], !dbg !11
+
+5:
+  ret i32 0
+
+6:
+  %7 = tail call i32 @"scale value"(i32 %0, i32 2)
+  ret i32 %7
+}
+
+define i32 @"scale value"(i32 %x, i32 %factor) {
+entry:
+  %product = mul nsw i32 %x, %factor ; the comment is not part of the instruction
+  %wide = sext i32 %product to i64
+  %old = atomicrmw add ptr @counter, i64 %wide seq_cst
+  ret i32 %product
+}
+
+@counter = global i64 0
+
+!llvm.dbg.cu = !{!0}
+!llvm.module.flags = !{!3}
+
+!0 = distinct !DICompileUnit(language: DW_LANG_C99, file: !1, producer: "handwritten", isOptimized: true, runtimeVersion: 0, emissionKind: FullDebug, enums: !2)
+!1 = !DIFile(filename: "branches.c", directory: "/tmp")
+!2 = !{}
+!3 = !{i32 2, !"Debug Info Version", i32 3}
+!6 = distinct !DISubprogram(name: "classify", scope: !1, file: !1, line: 1, type: !7, scopeLine: 1, spFlags: DISPFlagDefinition | DISPFlagOptimized, unit: !0, retainedNodes: !2)
+!7 = !DISubroutineType(types: !2)
+!8 = distinct !DILexicalBlock(scope: !6, line: 2, column: 3)
+!9 = !DILocation(line: 2, column: 7, scope: !6)
+!10 = !DILocation(line: 3, column: 5, scope: !8)
+!11 = !DILocation(line: 4, column: 3, scope: !6)
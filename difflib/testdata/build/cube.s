@@ -0,0 +1,5 @@
+cube:
+	movl	%edi, %eax
+	imull	%edi, %eax
+	imull	%edi, %eax
+	retq
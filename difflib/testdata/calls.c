@@ -0,0 +1,9 @@
+// Source of x86_64-stripped, a binary with no symbols or frame descriptions, so functions can only be found from calls:
+// gcc -O1 -nostdlib -static -no-pie -fno-pie -fcf-protection=none -fno-asynchronous-unwind-tables -Wl,--build-id=none -Wl,-n -o x86_64-stripped calls.c && strip -R .comment x86_64-stripped
+static int __attribute__((noinline)) square(int x) { return x * x; }
+static int __attribute__((noinline)) sum(int a, int b) { return square(a) + square(b); }
+void _start(void) {
+    volatile int result = sum(3, 4);
+    for (;;) {
+    }
+}
@@ -0,0 +1,4 @@
+square:
+	imull	%edi, %edi
+	movl	%edi, %eax
+	retq
@@ -0,0 +1,4 @@
+square:
+	movl	%edi, %eax
+	imull	%eax, %eax
+	retq
@@ -0,0 +1,16 @@
+# Source of greeter-311.pyc, a module compiled by CPython 3.11:
+# python3.11 -c "import py_compile; py_compile.compile('greeter.py', 'greeter-311.pyc', invalidation_mode=py_compile.PycInvalidationMode.UNCHECKED_HASH)"
+class Greeter:
+    def greet(self, names):
+        for name in names:
+            print("Hello, " + name)
+
+
+def outer(x):
+    def inner(y):
+        return x + y
+
+    try:
+        return inner(1)
+    except TypeError:
+        return None
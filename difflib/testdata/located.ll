@@ -0,0 +1,20 @@
+; Input with debug locations and no pointers, so it is valid IR for every supported version of LLVM; check it with:
+; llvm-as located.ll -o /dev/null
+define i32 @twice(i32 %x) !dbg !5 {
+  %y = shl i32 %x, 1, !dbg !8
+  ret i32 %y, !dbg !9
+}
+
+!llvm.dbg.cu = !{!0}
+!llvm.module.flags = !{!3, !4}
+
+!0 = distinct !DICompileUnit(language: DW_LANG_C99, file: !1, producer: "clang", isOptimized: true, runtimeVersion: 0, emissionKind: FullDebug, enums: !2)
+!1 = !DIFile(filename: "located.c", directory: "/src")
+!2 = !{}
+!3 = !{i32 2, !"Debug Info Version", i32 3}
+!4 = !{i32 7, !"Dwarf Version", i32 4}
+!5 = distinct !DISubprogram(name: "twice", scope: !1, file: !1, line: 1, type: !6, scopeLine: 1, spFlags: DISPFlagDefinition | DISPFlagOptimized, unit: !0, retainedNodes: !2)
+!6 = !DISubroutineType(types: !7)
+!7 = !{null}
+!8 = !DILocation(line: 2, column: 10, scope: !5)
+!9 = !DILocation(line: 3, column: 3, scope: !5)
@@ -0,0 +1,12 @@
+// Source of x86_64-loop.s, x86_64-loop.o, and x86_64-loop.objdump, the same functions as compiler output, an object file, and a disassembly listing:
+// gcc -O1 -fno-asynchronous-unwind-tables -fcf-protection=none -S loop.c -o x86_64-loop.s
+// gcc -O1 -fno-asynchronous-unwind-tables -fcf-protection=none -c loop.c -o x86_64-loop.o && objdump -d x86_64-loop.o > x86_64-loop.objdump
+// and of x86_64-loop-debug.o, the same object file with DWARF line tables:
+// gcc -O1 -g -fdebug-prefix-map=$PWD=. -fno-asynchronous-unwind-tables -fcf-protection=none -c loop.c -o x86_64-loop-debug.o
+int sum(const int *values, int count) {
+  int total = 0;
+  for (int i = 0; i < count; i++)
+    total += values[i];
+  return total;
+}
+int clamp(int x) { return x < 0 ? 0 : x; }
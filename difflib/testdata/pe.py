@@ -0,0 +1,50 @@
+# Generates x86_64-pe.exe, a minimal PE image with an unnamed entry point, an internal function only found from the
+# exception table (.pdata), and a function only named by the COFF symbol table, since there is no PE linker to hand:
+# python3 pe.py
+import struct
+
+IMAGE_BASE = 0x140000000
+TEXT, PDATA, XDATA = 0x1000, 0x2000, 0x3000
+
+# entry: sub rsp, 0x28; call internal; add rsp, 0x28; ret
+entry = bytes.fromhex("4883ec28 e807000000 4883c428 c3")
+# internal: lea eax, [rcx + 1]; ret
+internal = bytes.fromhex("8d4101 c3")
+# square: mov eax, ecx; imul eax, ecx; ret
+square = bytes.fromhex("89c8 0fafc1 c3")
+text = entry.ljust(0x10, b"\xcc") + internal.ljust(0x10, b"\xcc") + square
+
+# RUNTIME_FUNCTION entries; the leaf function after the padding has none
+pdata = struct.pack("<6I", TEXT, TEXT + len(entry), XDATA, TEXT + 0x10, TEXT + 0x10 + len(internal), XDATA + 8)
+# Unwind information: a 4-byte prologue allocating 0x28 bytes, and an empty one
+xdata = bytes.fromhex("01040100 04420000 01000000")
+
+sections = [
+    (b".text", TEXT, text, 0x60000020),
+    (b".pdata", PDATA, pdata, 0x40000040),
+    (b".xdata", XDATA, xdata, 0x40000040),
+]
+symbol_table = 0x200 * (len(sections) + 1)
+
+headers = bytearray(b"MZ".ljust(0x3C, b"\0") + struct.pack("<I", 0x40))
+headers += b"PE\0\0"
+headers += struct.pack("<HHIIIHH", 0x8664, len(sections), 0, symbol_table, 1, 240, 0x22)
+headers += struct.pack("<HBBIIIII", 0x20B, 0, 0, 0x200, 0x400, 0, TEXT, TEXT)
+headers += struct.pack("<QIIHHHHHHIIIIHHQQQQII", IMAGE_BASE, 0x1000, 0x200, 6, 0, 0, 0, 6, 0, 0, 0x4000, 0x200, 0, 3,
+                       0x8160, 0x100000, 0x1000, 0x100000, 0x1000, 0, 16)
+directories = [(0, 0)] * 16
+directories[3] = (PDATA, len(pdata))
+for directory in directories:
+    headers += struct.pack("<II", *directory)
+for index, (name, address, data, characteristics) in enumerate(sections):
+    headers += struct.pack("<8sIIIIIIHHI", name, len(data), address, 0x200, 0x200 * (index + 1), 0, 0, 0, 0,
+                           characteristics)
+
+image = bytes(headers).ljust(0x200, b"\0")
+for _, _, data, _ in sections:
+    image += data.ljust(0x200, b"\0")
+# One external function symbol in .text, then an empty string table
+image += struct.pack("<8sIhHBB", b"square", 0x20, 1, 0x20, 2, 0) + struct.pack("<I", 4)
+
+with open("x86_64-pe.exe", "wb") as output:
+    output.write(image)
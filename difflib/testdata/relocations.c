@@ -0,0 +1,7 @@
+// Source of x86_64-relocations.o, an object file where calls and references to data are filled in by relocations:
+// gcc -O1 -c -fno-pie -fcf-protection=none -fno-asynchronous-unwind-tables -fno-inline -o x86_64-relocations.o relocations.c && objcopy -R .comment -R .note.GNU-stack x86_64-relocations.o
+extern int g;
+int foo(int);
+int _Z3bazi(int x) { return x; }
+int bar(int x) { return foo(x) + g + _Z3bazi(x); }
+int *address(void) { return &g; }
@@ -0,0 +1,30 @@
+; Source of nvptx-scale.ptx, a kernel that calls a device function:
+; llc -O1 -march=nvptx64 scale.ll -o nvptx-scale.ptx
+target triple = "nvptx64-nvidia-cuda"
+
+define float @square(float %x) {
+  %y = fmul float %x, %x
+  ret float %y
+}
+
+define void @scale(float* %out, float* %in, i32 %n) {
+entry:
+  %tid = call i32 @llvm.nvvm.read.ptx.sreg.tid.x()
+  %inside = icmp slt i32 %tid, %n
+  br i1 %inside, label %body, label %done
+body:
+  %idx = sext i32 %tid to i64
+  %src = getelementptr float, float* %in, i64 %idx
+  %v = load float, float* %src
+  %s = call float @square(float %v)
+  %dst = getelementptr float, float* %out, i64 %idx
+  store float %s, float* %dst
+  br label %done
+done:
+  ret void
+}
+
+declare i32 @llvm.nvvm.read.ptx.sreg.tid.x()
+
+!nvvm.annotations = !{!0}
+!0 = !{void (float*, float*, i32)* @scale, !"kernel", i32 1}
@@ -0,0 +1,76 @@
+; One of each kind of instruction, to check that the llvm-ir and llvm-text backends score every pair the same way:
+; llvm-as-14 scoring.ll -o /dev/null
+@counter = global i64 0
+
+define double @ops(i32 %a, i32 %b, double %x, double %y, i32* %p, <4 x i32> %v) {
+entry:
+  %add = add i32 %a, %b
+  %sub = sub nsw i32 %a, %b
+  %mul = mul i32 %a, %b
+  %udiv = udiv i32 %a, %b
+  %sdiv = sdiv i32 %a, %b
+  %urem = urem i32 %a, %b
+  %srem = srem i32 %a, %b
+  %and = and i32 %a, %b
+  %or = or i32 %a, %b
+  %xor = xor i32 %a, %b
+  %shl = shl i32 %a, 1
+  %lshr = lshr i32 %a, 1
+  %ashr = ashr i32 %a, 1
+  %fadd = fadd double %x, %y
+  %fsub = fsub double %x, %y
+  %fmul = fmul fast double %x, %y
+  %fdiv = fdiv double %x, %y
+  %frem = frem double %x, %y
+  %fneg = fneg double %x
+  %ee = extractelement <4 x i32> %v, i32 0
+  %ie = insertelement <4 x i32> %v, i32 %a, i32 1
+  %sv = shufflevector <4 x i32> %v, <4 x i32> %ie, <4 x i32> <i32 0, i32 4, i32 1, i32 5>
+  %slot = alloca i32
+  %wide = alloca i64
+  %ld = load i32, i32* %p
+  store i32 %ld, i32* %slot
+  fence acquire
+  fence seq_cst
+  %cx = cmpxchg i32* %p, i32 %a, i32 %b seq_cst seq_cst
+  %cx2 = cmpxchg i32* %p, i32 %a, i32 %b acquire monotonic
+  %rmw = atomicrmw add i64* @counter, i64 1 seq_cst
+  %rmw2 = atomicrmw sub i64* @counter, i64 1 seq_cst
+  %rmw3 = atomicrmw add i64* @counter, i64 1 monotonic
+  %gep = getelementptr i32, i32* %p, i64 1
+  %tr = trunc i32 %a to i8
+  %tr2 = trunc i32 %a to i16
+  %ze = zext i32 %a to i64
+  %ze2 = zext i8 %tr to i32
+  %se = sext i32 %a to i64
+  %se2 = sext i8 %tr to i32
+  %ft = fptrunc double %x to float
+  %fe = fpext float %ft to double
+  %fu = fptoui double %x to i32
+  %fs = fptosi double %x to i32
+  %fs2 = fptosi double %x to i64
+  %uf = uitofp i32 %a to double
+  %sf = sitofp i32 %a to double
+  %sf2 = sitofp i32 %a to float
+  %pi = ptrtoint i32* %p to i64
+  %ip = inttoptr i64 %pi to i32*
+  %bc = bitcast i32* %p to i8*
+  %ieq = icmp eq i32 %a, %b
+  %islt = icmp slt i32 %a, %b
+  %foeq = fcmp oeq double %x, %y
+  %folt = fcmp fast olt double %x, %y
+  %sel = select i1 %ieq, i32 %a, i32 %b
+  %fr = freeze i32 %a
+  %c1 = call i32 @f(i32 %a)
+  %c2 = tail call i32 @f(i32 %b)
+  %c3 = call i32 @g(i32 %a, i32 %b)
+  br label %exit
+
+exit:
+  %phi = phi i32 [ %a, %entry ]
+  %phi2 = phi double [ %x, %entry ]
+  ret double %fadd
+}
+
+declare i32 @f(i32)
+declare i32 @g(i32, i32)
@@ -0,0 +1,21 @@
+; Source of aarch64-sq.s, the same functions as AArch64 assembly:
+; llc -mtriple=aarch64-linux-gnu -O1 sq.ll -o aarch64-sq.s
+; and of x86_64-sq.s, the same functions as x86-64 assembly:
+; llc -mtriple=x86_64-linux-gnu -O1 sq.ll -o x86_64-sq.s
+; It is also the source of sq.bc, the same module as bitcode:
+; llvm-as-14 sq.ll -o sq.bc
+; It is also the source of arm64-mach.o, a MachO object file where the assembler's temporary label ltmp0 shares an address with _sq:
+; llc -mtriple=arm64-apple-macos -O1 -filetype=obj sq.ll -o arm64-mach.o
+; and of x86_64-mach.o, the same for x86-64, which together with arm64e-mach.o and x86_64h-mach.o make up a universal binary in the tests:
+; llc -mtriple=x86_64-apple-macos -O1 -filetype=obj sq.ll -o x86_64-mach.o
+; and of armv7-sq.o, the same functions as a 32-bit ARM object file:
+; llc -mtriple=armv7-linux-gnueabihf -O1 -filetype=obj sq.ll -o armv7-sq.o
+define i32 @sq(i32 %x) {
+  %y = mul i32 %x, %x
+  ret i32 %y
+}
+define i32 @twice(i32 %x) {
+  %y = call i32 @sq(i32 %x)
+  %z = add i32 %y, %y
+  ret i32 %z
+}
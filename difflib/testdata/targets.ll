@@ -0,0 +1,25 @@
+; Source of aarch64-targets.o, an object file where the call to an internal function needs no relocation:
+; llc -mtriple=aarch64-linux-gnu -O1 -filetype=obj targets.ll -o aarch64-targets.o
+; and of arm64e-mach.o and x86_64h-mach.o, MachO object files for the architecture variants, so that they differ from the slices built from sq.ll:
+; llc -mtriple=arm64e-apple-macos -O1 -filetype=obj targets.ll -o arm64e-mach.o
+; llc -mtriple=x86_64h-apple-macos -O1 -filetype=obj targets.ll -o x86_64h-mach.o
+define internal i32 @sq(i32 %x) {
+  %y = mul i32 %x, %x
+  ret i32 %y
+}
+define i32 @sum_squares(i32 %n) {
+entry:
+  %empty = icmp slt i32 %n, 1
+  br i1 %empty, label %done, label %loop
+loop:
+  %i = phi i32 [ 0, %entry ], [ %next, %loop ]
+  %total = phi i32 [ 0, %entry ], [ %sum, %loop ]
+  %square = call i32 @sq(i32 %i)
+  %sum = add i32 %total, %square
+  %next = add i32 %i, 1
+  %more = icmp slt i32 %next, %n
+  br i1 %more, label %loop, label %done
+done:
+  %result = phi i32 [ 0, %entry ], [ %sum, %loop ]
+  ret i32 %result
+}
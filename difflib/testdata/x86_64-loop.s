@@ -0,0 +1,33 @@
+	.file	"loop.c"
+	.text
+	.globl	sum
+	.type	sum, @function
+sum:
+	testl	%esi, %esi
+	jle	.L4
+	movq	%rdi, %rax
+	movslq	%esi, %rsi
+	leaq	(%rdi,%rsi,4), %rcx
+	movl	$0, %edx
+.L3:
+	addl	(%rax), %edx
+	addq	$4, %rax
+	cmpq	%rcx, %rax
+	jne	.L3
+.L1:
+	movl	%edx, %eax
+	ret
+.L4:
+	movl	$0, %edx
+	jmp	.L1
+	.size	sum, .-sum
+	.globl	clamp
+	.type	clamp, @function
+clamp:
+	testl	%edi, %edi
+	movl	$0, %eax
+	cmovns	%edi, %eax
+	ret
+	.size	clamp, .-clamp
+	.ident	"GCC: (Debian 12.2.0-14+deb12u1) 12.2.0"
+	.section	.note.GNU-stack,"",@progbits
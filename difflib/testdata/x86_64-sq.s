@@ -0,0 +1,33 @@
+	.text
+	.file	"sq.ll"
+	.globl	sq                              # -- Begin function sq
+	.p2align	4, 0x90
+	.type	sq,@function
+sq:                                     # @sq
+	.cfi_startproc
+# %bb.0:
+	movl	%edi, %eax
+	imull	%edi, %eax
+	retq
+.Lfunc_end0:
+	.size	sq, .Lfunc_end0-sq
+	.cfi_endproc
+                                        # -- End function
+	.globl	twice                           # -- Begin function twice
+	.p2align	4, 0x90
+	.type	twice,@function
+twice:                                  # @twice
+	.cfi_startproc
+# %bb.0:
+	pushq	%rax
+	.cfi_def_cfa_offset 16
+	callq	sq@PLT
+	addl	%eax, %eax
+	popq	%rcx
+	.cfi_def_cfa_offset 8
+	retq
+.Lfunc_end1:
+	.size	twice, .Lfunc_end1-twice
+	.cfi_endproc
+                                        # -- End function
+	.section	".note.GNU-stack","",@progbits
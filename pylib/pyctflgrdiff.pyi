@@ -0,0 +1,158 @@
+from typing import Any, Dict, Iterator, List, Literal, Optional, Tuple
+
+Kind = Literal["match", "mismatch", "left", "right"]
+
+Side = Literal["left", "right", "both"]
+
+class CtflgrdiffError(Exception): ...
+
+class UnknownFormatError(CtflgrdiffError, ValueError):
+    left_format: str
+    right_format: str
+
+class FunctionNotFoundError(CtflgrdiffError, IndexError):
+    side: Side
+    name: Optional[str]
+
+class AmbiguousFunctionError(FunctionNotFoundError):
+    candidates: List[str]
+
+class ParseError(CtflgrdiffError, ValueError):
+    side: Side
+    path: Optional[str]
+    message: str
+
+class Row:
+    """One aligned pair of instructions
+
+    The kind is `left` if there is no left-hand instruction and `right` if there is no right-hand instruction.
+    """
+
+    kind: Kind
+    left: str
+    right: str
+    left_location: Optional[str]
+    right_location: Optional[str]
+    def to_dict(self) -> Dict[str, Any]: ...
+    def to_json(self, indent: Optional[int] = None) -> str: ...
+    def _repr_html_(self) -> str: ...
+
+class BlockPair:
+    """A pair of aligned blocks and the instructions in them
+
+    A block that is only in one function has an empty name on the other side.
+    """
+
+    left: str
+    right: str
+    rows: List[Row]
+    def __len__(self) -> int: ...
+    def __iter__(self) -> Iterator[Row]: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    def to_json(self, indent: Optional[int] = None) -> str: ...
+    def _repr_html_(self) -> str: ...
+
+class FunctionDiff:
+    """The comparison of one pair of functions"""
+
+    left_name: str
+    right_name: str
+    blocks: List[BlockPair]
+    @property
+    def has_diff(self) -> bool: ...
+    def __len__(self) -> int: ...
+    def __iter__(self) -> Iterator[BlockPair]: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    def to_json(self, indent: Optional[int] = None) -> str: ...
+    def _repr_html_(self) -> str: ...
+
+class DiffResult:
+    """The result of comparing two programs"""
+
+    has_diff: bool
+    functions: List[FunctionDiff]
+    def __len__(self) -> int: ...
+    def __iter__(self) -> Iterator[FunctionDiff]: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    def to_json(self, indent: Optional[int] = None) -> str: ...
+    def _repr_html_(self) -> str: ...
+
+def diff_files(
+    format: str,
+    left_file: str,
+    right_file: Optional[str] = None,
+    left_name: Optional[str] = None,
+    right_name: Optional[str] = None,
+    right_format: Optional[str] = None,
+    left_arch: Optional[str] = None,
+    right_arch: Optional[str] = None,
+) -> DiffResult: ...
+def diff_bytes(
+    format: str,
+    left: bytes,
+    right: Optional[bytes] = None,
+    left_name: Optional[str] = None,
+    right_name: Optional[str] = None,
+    right_format: Optional[str] = None,
+    left_arch: Optional[str] = None,
+    right_arch: Optional[str] = None,
+) -> DiffResult: ...
+def diff_strings(
+    format: str,
+    left: str,
+    right: Optional[str] = None,
+    left_name: Optional[str] = None,
+    right_name: Optional[str] = None,
+    right_format: Optional[str] = None,
+) -> DiffResult: ...
+def diff_files_batch(
+    format: str,
+    pairs: List[Tuple[str, str]],
+    left_name: Optional[str] = None,
+    right_name: Optional[str] = None,
+    right_format: Optional[str] = None,
+    left_arch: Optional[str] = None,
+    right_arch: Optional[str] = None,
+    threads: Optional[int] = None,
+) -> List[DiffResult]: ...
+def diff_numba(
+    left: Any,
+    right: Optional[Any] = None,
+    left_signature: Optional[Tuple[Any, ...]] = None,
+    right_signature: Optional[Tuple[Any, ...]] = None,
+    format: Optional[str] = None,
+    left_name: Optional[str] = None,
+    right_name: Optional[str] = None,
+) -> DiffResult: ...
+
+LegacyRow = Tuple[Optional[Kind], str, str]
+LegacyResult = Tuple[bool, Dict[Tuple[str, str], List[LegacyRow]]]
+
+def make_diff(
+    format: str,
+    left_file: str,
+    right_file: Optional[str] = None,
+    left_name: Optional[str] = None,
+    right_name: Optional[str] = None,
+    right_format: Optional[str] = None,
+    left_arch: Optional[str] = None,
+    right_arch: Optional[str] = None,
+) -> LegacyResult: ...
+def make_diff_from_bytes(
+    format: str,
+    left: bytes,
+    right: Optional[bytes] = None,
+    left_name: Optional[str] = None,
+    right_name: Optional[str] = None,
+    right_format: Optional[str] = None,
+    left_arch: Optional[str] = None,
+    right_arch: Optional[str] = None,
+) -> LegacyResult: ...
+def make_diff_from_strings(
+    format: str,
+    left: str,
+    right: Optional[str] = None,
+    left_name: Optional[str] = None,
+    right_name: Optional[str] = None,
+    right_format: Optional[str] = None,
+) -> LegacyResult: ...
@@ -0,0 +1,216 @@
+use ctflgrdifflib::{FormatError, FunctionLocation, FunctionName};
+use pyo3::{
+    create_exception,
+    exceptions::{PyException, PyIndexError, PyValueError},
+    once_cell::GILOnceCell,
+    prelude::*,
+    types::{PyDict, PyTuple, PyType},
+};
+
+create_exception!(
+    pyctflgrdiff,
+    CtflgrdiffError,
+    PyException,
+    "The base class of all errors raised while comparing programs"
+);
+
+/// Create an exception class that can have several bases, which `create_exception!` can't do
+///
+/// Most exceptions derive from `CtflgrdiffError` and the built-in exception raised for the same error before these classes existed, so existing handlers still catch them.
+fn subclass(py: Python, name: &str, bases: &[&PyType], doc: &str) -> PyResult<Py<PyType>> {
+    let attributes = PyDict::new(py);
+    attributes.set_item("__doc__", doc)?;
+    attributes.set_item("__module__", "pyctflgrdiff")?;
+    Ok(py
+        .get_type::<PyType>()
+        .call1((name, PyTuple::new(py, bases), attributes))?
+        .downcast::<PyType>()?
+        .into())
+}
+
+/// A format name is not recognized; also a `ValueError`
+fn unknown_format_error(py: Python<'_>) -> &PyType {
+    static TYPE: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+    TYPE.get_or_init(py, || {
+        subclass(
+            py,
+            "UnknownFormatError",
+            &[py.get_type::<CtflgrdiffError>(), py.get_type::<PyValueError>()],
+            "A format name is not recognized; `left_format` and `right_format` are the formats requested",
+        )
+        .expect("Failed to initialize new exception type.")
+    })
+    .as_ref(py)
+}
+
+/// A function to compare could not be found; also an `IndexError`, and so a `LookupError`
+fn function_not_found_error(py: Python<'_>) -> &PyType {
+    static TYPE: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+    TYPE.get_or_init(py, || {
+        subclass(
+            py,
+            "FunctionNotFoundError",
+            &[py.get_type::<CtflgrdiffError>(), py.get_type::<PyIndexError>()],
+            "A function to compare could not be found; `side` is `left`, `right`, or `both` and `name` is the function requested, or `None` if no functions were common to both programs",
+        )
+        .expect("Failed to initialize new exception type.")
+    })
+    .as_ref(py)
+}
+
+/// A function name matches functions in several files or archive members; a `FunctionNotFoundError`
+fn ambiguous_function_error(py: Python<'_>) -> &PyType {
+    static TYPE: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+    TYPE.get_or_init(py, || {
+        subclass(
+            py,
+            "AmbiguousFunctionError",
+            &[function_not_found_error(py)],
+            "A function name matches several functions; `side` is `left` or `right`, `name` is the function requested, and `candidates` are the names of the functions it could be",
+        )
+        .expect("Failed to initialize new exception type.")
+    })
+    .as_ref(py)
+}
+
+/// A program could not be parsed; also a `ValueError`
+fn parse_error(py: Python<'_>) -> &PyType {
+    static TYPE: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+    TYPE.get_or_init(py, || {
+        subclass(
+            py,
+            "ParseError",
+            &[py.get_type::<CtflgrdiffError>(), py.get_type::<PyValueError>()],
+            "A program could not be parsed; `side` is `left`, `right`, or `both`, `path` is the file, or `None` if the program was in memory, and `message` is the parser's error",
+        )
+        .expect("Failed to initialize new exception type.")
+    })
+    .as_ref(py)
+}
+
+/// The name of the side of a diff as used in exception attributes
+fn side(location: FunctionLocation) -> &'static str {
+    match location {
+        FunctionLocation::Left => "left",
+        FunctionLocation::Right => "right",
+        FunctionLocation::Both => "both",
+    }
+}
+
+/// Describe the program or programs at a location
+fn program(location: FunctionLocation) -> &'static str {
+    match location {
+        FunctionLocation::Left => "the left-hand program",
+        FunctionLocation::Right => "the right-hand program",
+        FunctionLocation::Both => "either program",
+    }
+}
+
+/// Create an exception with some attributes set on it
+fn with_attributes(
+    py: Python,
+    exception: &PyType,
+    message: String,
+    attributes: &[(&str, PyObject)],
+) -> PyErr {
+    let error = PyErr::from_type(exception, message);
+    for (name, value) in attributes {
+        if let Err(e) = error.value(py).setattr(*name, value) {
+            return e;
+        }
+    }
+    error
+}
+
+/// Convert a diff error to a Python exception
+///
+/// The paths are the files compared, if they were files.
+pub fn diff_error(
+    py: Python,
+    format: &str,
+    right_format: &str,
+    function_name: &FunctionName,
+    left_path: Option<&str>,
+    right_path: Option<&str>,
+    error: FormatError,
+) -> PyErr {
+    match error {
+        FormatError::BadFormat => with_attributes(
+            py,
+            unknown_format_error(py),
+            if format == right_format {
+                format!("Unknown assembly format {}", format)
+            } else {
+                format!("Unknown assembly format {} or {}", format, right_format)
+            },
+            &[
+                ("left_format", format.into_py(py)),
+                ("right_format", right_format.into_py(py)),
+            ],
+        ),
+        FormatError::NoMatch(location) => {
+            let name = match (location, function_name) {
+                (_, FunctionName::Unspecified) => None,
+                (_, FunctionName::Same(name)) => Some(name),
+                (FunctionLocation::Right, FunctionName::Different(_, name)) => Some(name),
+                (_, FunctionName::Different(name, _)) => Some(name),
+            };
+            with_attributes(
+                py,
+                function_not_found_error(py),
+                match name {
+                    Some(name) => format!("{} is not in {}", name, program(location)),
+                    None => format!("No functions to compare in {}", program(location)),
+                },
+                &[
+                    ("side", side(location).into_py(py)),
+                    ("name", name.into_py(py)),
+                ],
+            )
+        }
+        FormatError::Ambiguous(location, name, candidates) => with_attributes(
+            py,
+            ambiguous_function_error(py),
+            format!(
+                "{} is defined more than once in {}; select one of {}",
+                name,
+                program(location),
+                candidates.join(", ")
+            ),
+            &[
+                ("side", side(location).into_py(py)),
+                ("name", name.into_py(py)),
+                ("candidates", candidates.into_py(py)),
+            ],
+        ),
+        FormatError::ParseError(location, message) => {
+            let path = match location {
+                FunctionLocation::Right => right_path,
+                FunctionLocation::Left | FunctionLocation::Both => left_path,
+            };
+            with_attributes(
+                py,
+                parse_error(py),
+                match path {
+                    Some(path) => format!("{}: {}", path, message),
+                    None => format!("{} ({})", message, location.name()),
+                },
+                &[
+                    ("side", side(location).into_py(py)),
+                    ("path", path.into_py(py)),
+                    ("message", message.into_py(py)),
+                ],
+            )
+        }
+    }
+}
+
+/// Add the exceptions to the module
+pub fn register(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add("CtflgrdiffError", py.get_type::<CtflgrdiffError>())?;
+    m.add("UnknownFormatError", unknown_format_error(py))?;
+    m.add("FunctionNotFoundError", function_not_found_error(py))?;
+    m.add("AmbiguousFunctionError", ambiguous_function_error(py))?;
+    m.add("ParseError", parse_error(py))?;
+    Ok(())
+}
@@ -0,0 +1,112 @@
+//! HTML rendering of diffs for Jupyter
+//!
+//! Styles are inline since notebooks may strip `<style>` elements from untrusted output.
+use crate::result::{BlockPair, DiffResult, FunctionDiff, Row};
+use std::fmt::Write;
+
+const TABLE_STYLE: &str =
+    "width: 100%; table-layout: fixed; border-collapse: collapse; font-family: monospace";
+const CELL_STYLE: &str = "text-align: left; white-space: pre; overflow: hidden; text-overflow: ellipsis; padding: 0 0.5em";
+const BLOCK_STYLE: &str = "color: #1e63d0; font-family: monospace; cursor: pointer";
+
+/// The background colour for each kind of row, following the colours used by the command line tool
+fn background(kind: &str) -> &'static str {
+    match kind {
+        "mismatch" => "background-color: #cfe2ff",
+        "left" => "background-color: #cff4fc",
+        "right" => "background-color: #f7d6f2",
+        _ => "",
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            c => output.push(c),
+        }
+    }
+    output
+}
+
+fn cell(output: &mut String, text: &str, location: Option<&str>) {
+    output.push_str("<td style=\"");
+    output.push_str(CELL_STYLE);
+    output.push('"');
+    // The source location is shown when hovering over the instruction
+    if let Some(location) = location {
+        write!(output, " title=\"{}\"", escape(location)).unwrap();
+    }
+    write!(output, ">{}</td>", escape(text)).unwrap();
+}
+
+pub fn row(output: &mut String, row: &Row) {
+    match background(row.kind) {
+        "" => output.push_str("<tr>"),
+        style => write!(output, "<tr style=\"{}\">", style).unwrap(),
+    }
+    cell(output, &row.left, row.left_location.as_deref());
+    cell(output, &row.right, row.right_location.as_deref());
+    output.push_str("</tr>");
+}
+
+pub fn rows(output: &mut String, rows: &[Row]) {
+    write!(output, "<table style=\"{}\">", TABLE_STYLE).unwrap();
+    for r in rows {
+        row(output, r);
+    }
+    output.push_str("</table>");
+}
+
+pub fn block(output: &mut String, block: &BlockPair) {
+    write!(
+        output,
+        "<details open><summary style=\"{}\">{} | {}</summary>",
+        BLOCK_STYLE,
+        escape(&block.left),
+        escape(&block.right)
+    )
+    .unwrap();
+    rows(output, &block.rows);
+    output.push_str("</details>");
+}
+
+pub fn function(output: &mut String, function: &FunctionDiff) {
+    let has_diff = function.has_diff();
+    // Functions that are the same are collapsed so the differences stand out
+    write!(
+        output,
+        "<details{}><summary style=\"cursor: pointer\"><b>{}</b> vs <b>{}</b> ({})</summary><div style=\"margin-left: 1em\">",
+        if has_diff { " open" } else { "" },
+        escape(&function.left_name),
+        escape(&function.right_name),
+        if has_diff { "different" } else { "identical" }
+    )
+    .unwrap();
+    for b in &function.blocks {
+        block(output, b);
+    }
+    output.push_str("</div></details>");
+}
+
+pub fn result(output: &mut String, result: &DiffResult) {
+    write!(
+        output,
+        "<div><p>{} functions, {}</p>",
+        result.functions.len(),
+        if result.has_diff {
+            "with differences"
+        } else {
+            "identical"
+        }
+    )
+    .unwrap();
+    for f in &result.functions {
+        function(output, f);
+    }
+    output.push_str("</div>");
+}
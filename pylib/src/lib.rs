@@ -1,102 +1,361 @@
+// pyo3's `create_exception!` checks a `cfg` that is only set by its own build script
+#[allow(unexpected_cfgs)]
+mod errors;
+mod html;
+mod numba;
+mod result;
+
 use ctflgrdifflib::*;
-use pyo3::{
-    exceptions::{PyIndexError, PyValueError},
-    prelude::*,
-    types::IntoPyDict,
-};
+use errors::diff_error;
+use pyo3::{exceptions::PyValueError, prelude::*, types::IntoPyDict};
+use result::{BlockPair, DiffResult, FunctionDiff, Row};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-struct PyDiff(String, String, Vec<Row>);
-type Row = (Option<&'static str>, String, String);
+/// Compare two files, returning `(has_diff, {(left_name, right_name): [(kind, left, right), ...]})`, where a block starts with a row with no kind
+#[pyfunction]
+#[allow(clippy::too_many_arguments)]
+fn make_diff(
+    py: Python,
+    format: &str,
+    left_file: &str,
+    right_file: Option<&str>,
+    left_name: Option<String>,
+    right_name: Option<String>,
+    right_format: Option<&str>,
+    left_arch: Option<&str>,
+    right_arch: Option<&str>,
+) -> PyResult<PyObject> {
+    diff_files(
+        py,
+        format,
+        left_file,
+        right_file,
+        left_name,
+        right_name,
+        right_format,
+        left_arch,
+        right_arch,
+    )
+    .map(into_tuples)
+}
 
-impl IntoDiffResult for PyDiff {
-    type Row = Row;
+/// Compare two programs in memory, returning the same tuples as `make_diff`
+#[pyfunction]
+#[allow(clippy::too_many_arguments)]
+fn make_diff_from_bytes(
+    py: Python,
+    format: &str,
+    left: &[u8],
+    right: Option<&[u8]>,
+    left_name: Option<String>,
+    right_name: Option<String>,
+    right_format: Option<&str>,
+    left_arch: Option<&str>,
+    right_arch: Option<&str>,
+) -> PyResult<PyObject> {
+    diff_bytes(
+        py,
+        format,
+        left,
+        right,
+        left_name,
+        right_name,
+        right_format,
+        left_arch,
+        right_arch,
+    )
+    .map(into_tuples)
+}
 
-    fn block_row(left: std::borrow::Cow<str>, right: std::borrow::Cow<str>) -> Self::Row {
-        (None, left.to_string(), right.to_string())
-    }
+/// Compare two programs in text formats, returning the same tuples as `make_diff`
+#[pyfunction]
+fn make_diff_from_strings(
+    py: Python,
+    format: &str,
+    left: &str,
+    right: Option<&str>,
+    left_name: Option<String>,
+    right_name: Option<String>,
+    right_format: Option<&str>,
+) -> PyResult<PyObject> {
+    diff_strings(py, format, left, right, left_name, right_name, right_format).map(into_tuples)
+}
 
-    fn row(
-        left: std::borrow::Cow<str>,
-        right: std::borrow::Cow<str>,
-        kind: MatchDirection,
-    ) -> Self::Row {
-        (
-            Some(match kind {
-                MatchDirection::GapLeft => "left",
-                MatchDirection::GapRight => "right",
-                MatchDirection::Align(exact) => {
-                    if exact {
-                        "match"
-                    } else {
-                        "mismatch"
-                    }
-                }
-            }),
-            left.to_string(),
-            right.to_string(),
-        )
-    }
+/// Compare two files
+#[pyfunction]
+#[allow(clippy::too_many_arguments)]
+fn diff_files(
+    py: Python,
+    format: &str,
+    left_file: &str,
+    right_file: Option<&str>,
+    left_name: Option<String>,
+    right_name: Option<String>,
+    right_format: Option<&str>,
+    left_arch: Option<&str>,
+    right_arch: Option<&str>,
+) -> PyResult<DiffResult> {
+    // Without a right-hand file, two functions in the same file are compared
+    let right_file = right_file.unwrap_or(left_file);
+    run_diff(
+        py,
+        format,
+        right_format,
+        left_name,
+        right_name,
+        Some(left_file),
+        Some(right_file),
+        |right_format, function_name| {
+            compute_diff_with_formats::<FunctionDiff>(
+                format,
+                left_arch,
+                left_file,
+                right_format,
+                right_arch,
+                right_file,
+                function_name,
+            )
+        },
+    )
+}
 
-    fn function(
-        left_name: std::borrow::Cow<str>,
-        right_name: std::borrow::Cow<str>,
-        rows: Vec<Self::Row>,
-    ) -> Self {
-        PyDiff(left_name.to_string(), right_name.to_string(), rows)
-    }
+/// Compare two programs in memory
+#[pyfunction]
+#[allow(clippy::too_many_arguments)]
+fn diff_bytes(
+    py: Python,
+    format: &str,
+    left: &[u8],
+    right: Option<&[u8]>,
+    left_name: Option<String>,
+    right_name: Option<String>,
+    right_format: Option<&str>,
+    left_arch: Option<&str>,
+    right_arch: Option<&str>,
+) -> PyResult<DiffResult> {
+    let right = right.unwrap_or(left);
+    run_diff(
+        py,
+        format,
+        right_format,
+        left_name,
+        right_name,
+        None,
+        None,
+        |right_format, function_name| {
+            compute_diff_from_bytes_with_formats::<FunctionDiff>(
+                format,
+                left_arch,
+                left,
+                right_format,
+                right_arch,
+                right,
+                function_name,
+            )
+        },
+    )
 }
 
+/// Compare two programs in text formats
 #[pyfunction]
-fn make_diff(
+fn diff_strings(
+    py: Python,
     format: &str,
-    left_file: &str,
-    right_file: &str,
+    left: &str,
+    right: Option<&str>,
     left_name: Option<String>,
     right_name: Option<String>,
-) -> PyResult<PyObject> {
-    Python::with_gil(|py| {
-        let function_name = match (left_name, right_name) {
-            (None, None) => FunctionName::Unspecified,
-            (None, Some(_)) => {
-                return Err(PyErr::from_value(
-                    PyValueError::new_err("Right-hand function provided, but left is missing")
-                        .value(py),
-                ));
+    right_format: Option<&str>,
+) -> PyResult<DiffResult> {
+    let right = right.unwrap_or(left);
+    run_diff(
+        py,
+        format,
+        right_format,
+        left_name,
+        right_name,
+        None,
+        None,
+        |right_format, function_name| {
+            compute_diff_from_bytes_with_formats::<FunctionDiff>(
+                format,
+                None,
+                left.as_bytes(),
+                right_format,
+                None,
+                right.as_bytes(),
+                function_name,
+            )
+        },
+    )
+}
+
+/// Compare many pairs of files concurrently, returning the results in the same order as the pairs
+///
+/// By default, one thread is used per processor. If any pair fails, the error from the first failing pair is raised.
+#[pyfunction]
+#[allow(clippy::too_many_arguments)]
+fn diff_files_batch(
+    py: Python,
+    format: &str,
+    pairs: Vec<(String, String)>,
+    left_name: Option<String>,
+    right_name: Option<String>,
+    right_format: Option<&str>,
+    left_arch: Option<&str>,
+    right_arch: Option<&str>,
+    threads: Option<usize>,
+) -> PyResult<Vec<DiffResult>> {
+    let function_name = function_name(left_name, right_name)?;
+    let right_format = right_format.unwrap_or(format);
+    let threads = threads
+        .unwrap_or_else(|| {
+            std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+        })
+        .clamp(1, pairs.len().max(1));
+    let results = py.allow_threads(|| {
+        // Each thread takes the next pair to compare, so slow pairs don't hold up a whole share of the work
+        let next = AtomicUsize::new(0);
+        let mut results: Vec<_> = pairs.iter().map(|_| None).collect();
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut finished = Vec::new();
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            let (left_file, right_file) = match pairs.get(index) {
+                                Some(pair) => pair,
+                                None => break finished,
+                            };
+                            finished.push((
+                                index,
+                                compute_diff_with_formats::<FunctionDiff>(
+                                    format,
+                                    left_arch,
+                                    left_file,
+                                    right_format,
+                                    right_arch,
+                                    right_file,
+                                    function_name.clone(),
+                                ),
+                            ));
+                        }
+                    })
+                })
+                .collect();
+            for worker in workers {
+                for (index, result) in worker.join().expect("diff thread panicked") {
+                    results[index] = Some(result);
+                }
             }
-            (Some(v), None) => FunctionName::Same(v),
-            (Some(l), Some(r)) => FunctionName::Different(l, r),
-        };
-        let (has_diff, diffs) =
-            compute_diff_with_format::<PyDiff>(format, left_file, right_file, function_name)
-                .map_err(|e| match e {
-                    FormatError::BadFormat => PyErr::from_value(
-                        PyValueError::new_err(format!("Unknown assembly format {}", format))
-                            .value(py),
-                    ),
-                    FormatError::NoMatch(l) => {
-                        PyErr::from_value(PyIndexError::new_err(l.name()).value(py))
-                    }
-                    FormatError::ParseError(l, mut e) => {
-                        e.push_str(" (");
-                        e.push_str(l.name());
-                        e.push_str(")");
-                        PyErr::from_value(PyValueError::new_err(e).value(py))
-                    }
-                })?;
+        });
+        results
+    });
+    results
+        .into_iter()
+        .zip(&pairs)
+        .map(|(result, (left_file, right_file))| {
+            let (has_diff, functions) = result.expect("every pair is compared").map_err(|e| {
+                diff_error(
+                    py,
+                    format,
+                    right_format,
+                    &function_name,
+                    Some(left_file),
+                    Some(right_file),
+                    e,
+                )
+            })?;
+            Ok(DiffResult {
+                has_diff,
+                functions,
+            })
+        })
+        .collect()
+}
 
-        Ok((
-            has_diff,
-            diffs
+/// Select the functions to compare
+fn function_name(left_name: Option<String>, right_name: Option<String>) -> PyResult<FunctionName> {
+    match (left_name, right_name) {
+        (None, None) => Ok(FunctionName::Unspecified),
+        (None, Some(_)) => Err(PyValueError::new_err(
+            "Right-hand function provided, but left is missing",
+        )),
+        (Some(v), None) => Ok(FunctionName::Same(v)),
+        (Some(l), Some(r)) => Ok(FunctionName::Different(l, r)),
+    }
+}
+
+/// Select the functions to compare and run the diff
+///
+/// Parsing and aligning large programs can take a while, so other Python threads are allowed to run during the diff.
+#[allow(clippy::too_many_arguments)]
+fn run_diff(
+    py: Python,
+    format: &str,
+    right_format: Option<&str>,
+    left_name: Option<String>,
+    right_name: Option<String>,
+    left_path: Option<&str>,
+    right_path: Option<&str>,
+    diff: impl FnOnce(&str, FunctionName) -> Result<(bool, Vec<FunctionDiff>), FormatError> + Send,
+) -> PyResult<DiffResult> {
+    let function_name = function_name(left_name, right_name)?;
+    let right_format = right_format.unwrap_or(format);
+    let (has_diff, functions) = py
+        .allow_threads(|| diff(right_format, function_name.clone()))
+        .map_err(|e| {
+            diff_error(
+                py,
+                format,
+                right_format,
+                &function_name,
+                left_path,
+                right_path,
+                e,
+            )
+        })?;
+    Ok(DiffResult {
+        has_diff,
+        functions,
+    })
+}
+
+/// Convert a result to the nested tuples returned by `make_diff`
+fn into_tuples(result: DiffResult) -> PyObject {
+    Python::with_gil(|py| {
+        (
+            result.has_diff,
+            result
+                .functions
                 .into_iter()
-                .map(|PyDiff(l, r, rows)| ((l, r).to_object(py), rows.to_object(py)))
+                .map(|function| {
+                    (
+                        (function.left_name.clone(), function.right_name.clone()).to_object(py),
+                        function.into_tuples().to_object(py),
+                    )
+                })
                 .into_py_dict(py),
         )
-            .to_object(py))
+            .to_object(py)
     })
 }
 
 #[pymodule]
-fn pyctflgrdiff(_py: Python, m: &PyModule) -> PyResult<()> {
+fn pyctflgrdiff(py: Python, m: &PyModule) -> PyResult<()> {
+    errors::register(py, m)?;
     m.add_function(wrap_pyfunction!(make_diff, m)?)?;
+    m.add_function(wrap_pyfunction!(make_diff_from_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(make_diff_from_strings, m)?)?;
+    m.add_function(wrap_pyfunction!(diff_files, m)?)?;
+    m.add_function(wrap_pyfunction!(diff_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(diff_strings, m)?)?;
+    m.add_function(wrap_pyfunction!(diff_files_batch, m)?)?;
+    m.add_function(wrap_pyfunction!(numba::diff_numba, m)?)?;
+    m.add_class::<DiffResult>()?;
+    m.add_class::<FunctionDiff>()?;
+    m.add_class::<BlockPair>()?;
+    m.add_class::<Row>()?;
     Ok(())
 }
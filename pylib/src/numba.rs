@@ -0,0 +1,132 @@
+use crate::{
+    result::{DiffResult, FunctionDiff},
+    run_diff,
+};
+use ctflgrdifflib::{compute_diff_from_bytes_with_formats, demangle};
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+/// Select the signature to compare, which can be omitted if the function has only been compiled for one
+fn select_signature<'p>(
+    dispatcher: &'p PyAny,
+    signature: Option<&'p PyAny>,
+) -> PyResult<&'p PyAny> {
+    if let Some(signature) = signature {
+        return Ok(signature);
+    }
+    let signatures: Vec<&PyAny> = dispatcher.getattr("signatures")?.extract()?;
+    match signatures.as_slice() {
+        [signature] => Ok(signature),
+        [] => Err(PyValueError::new_err(
+            "Function has not been compiled; call it or compile it first",
+        )),
+        signatures => Err(PyValueError::new_err(format!(
+            "Function has been compiled for {} signatures; select one",
+            signatures.len()
+        ))),
+    }
+}
+
+/// Get the code generated for one signature in the form the format expects
+///
+/// LLVM IR and assembly are taken from `inspect_llvm` and `inspect_asm`; other formats are taken from the compiled library, as either bitcode or an object file.
+fn generated_code(dispatcher: &PyAny, signature: &PyAny, format: &str) -> PyResult<Vec<u8>> {
+    Ok(match format {
+        "ll" | "ll-ir" | "llir" | "ll-text" => dispatcher
+            .call_method1("inspect_llvm", (signature,))?
+            .extract::<String>()?
+            .into_bytes(),
+        format if format.starts_with("asm-") || matches!(format, "ptx" | "nvptx") => dispatcher
+            .call_method1("inspect_asm", (signature,))?
+            .extract::<String>()?
+            .into_bytes(),
+        format => {
+            let library = dispatcher
+                .getattr("overloads")?
+                .get_item(signature)?
+                .getattr("library")?;
+            let code = if matches!(format, "ll-bc" | "llbc") {
+                library.call_method0("emit_bitcode")?
+            } else {
+                library.call_method0("emit_native_object")?
+            };
+            code.extract::<&[u8]>()?.to_vec()
+        }
+    })
+}
+
+/// Find the symbol name Numba gave the compiled function, so the wrappers generated with it are not compared
+///
+/// Only LLVM IR keeps the mangled name; the other formats name functions by the demangled one.
+fn compiled_name(dispatcher: &PyAny, signature: &PyAny, format: &str) -> Option<String> {
+    let name: String = dispatcher
+        .getattr("overloads")
+        .and_then(|overloads| overloads.get_item(signature))
+        .and_then(|overload| overload.getattr("fndesc"))
+        .and_then(|fndesc| fndesc.getattr("mangled_name"))
+        .and_then(|name| name.extract())
+        .ok()?;
+    Some(
+        if matches!(
+            format,
+            "ll" | "ll-ir" | "llir" | "ll-text" | "ll-bc" | "llbc"
+        ) {
+            name
+        } else {
+            demangle(&name)
+        },
+    )
+}
+
+/// Compare the code Numba generated for two functions, or two signatures of one function
+///
+/// A signature is one of the dispatcher's `signatures`. The format is `ll` by default.
+#[pyfunction]
+#[allow(clippy::too_many_arguments)]
+pub fn diff_numba(
+    py: Python,
+    left: &PyAny,
+    right: Option<&PyAny>,
+    left_signature: Option<&PyAny>,
+    right_signature: Option<&PyAny>,
+    format: Option<&str>,
+    left_name: Option<String>,
+    right_name: Option<String>,
+) -> PyResult<DiffResult> {
+    let format = format.unwrap_or("ll");
+    let right = right.unwrap_or(left);
+    // The same signature is used on both sides unless specified
+    let right_signature = select_signature(right, right_signature.or(left_signature))?;
+    let left_signature = select_signature(left, left_signature)?;
+    let left_code = generated_code(left, left_signature, format)?;
+    let right_code = generated_code(right, right_signature, format)?;
+    let (left_name, right_name) = match (left_name, right_name) {
+        (None, None) => match (
+            compiled_name(left, left_signature, format),
+            compiled_name(right, right_signature, format),
+        ) {
+            (Some(left_name), Some(right_name)) => (Some(left_name), Some(right_name)),
+            _ => (None, None),
+        },
+        names => names,
+    };
+    run_diff(
+        py,
+        format,
+        None,
+        left_name,
+        right_name,
+        None,
+        None,
+        |right_format, function_name| {
+            compute_diff_from_bytes_with_formats::<FunctionDiff>(
+                format,
+                None,
+                &left_code,
+                right_format,
+                None,
+                &right_code,
+                function_name,
+            )
+        },
+    )
+}
@@ -0,0 +1,323 @@
+use crate::html;
+use ctflgrdifflib::{IntoDiffResult, MatchDirection};
+use pyo3::{
+    prelude::*,
+    types::{IntoPyDict, PyDict, PyList, PyString},
+};
+use std::borrow::Cow;
+
+/// Format a string the way Python's `repr` would
+fn py_repr(py: Python, text: &str) -> PyResult<String> {
+    Ok(PyString::new(py, text).repr()?.to_str()?.to_string())
+}
+
+/// Iterate over copies of some objects
+fn iterate<T: IntoPy<PyObject> + Clone>(py: Python, items: &[T]) -> PyResult<PyObject> {
+    let list = PyList::new(py, items.iter().map(|item| item.clone().into_py(py)));
+    Ok(list.as_ref().iter()?.to_object(py))
+}
+
+/// Convert a dictionary to JSON using Python's `json` module
+fn to_json(py: Python, dict: &PyDict, indent: Option<usize>) -> PyResult<String> {
+    py.import("json")?
+        .getattr("dumps")?
+        .call(
+            (dict,),
+            Some([("indent", indent.into_py(py))].into_py_dict(py)),
+        )?
+        .extract()
+}
+
+/// One aligned pair of instructions
+#[pyclass(module = "pyctflgrdiff")]
+#[derive(Clone)]
+pub struct Row {
+    /// How the instructions match: `match`, `mismatch`, `left` (there is no left-hand instruction), or `right` (there is no right-hand instruction)
+    #[pyo3(get)]
+    pub kind: &'static str,
+    #[pyo3(get)]
+    pub left: String,
+    #[pyo3(get)]
+    pub right: String,
+    /// The source location of the left-hand instruction, if debugging information is available
+    #[pyo3(get)]
+    pub left_location: Option<String>,
+    /// The source location of the right-hand instruction, if debugging information is available
+    #[pyo3(get)]
+    pub right_location: Option<String>,
+}
+#[pymethods]
+impl Row {
+    fn __repr__(&self, py: Python) -> PyResult<String> {
+        Ok(format!(
+            "Row({}, {}, {})",
+            py_repr(py, self.kind)?,
+            py_repr(py, &self.left)?,
+            py_repr(py, &self.right)?
+        ))
+    }
+    fn to_dict<'p>(&self, py: Python<'p>) -> PyResult<&'p PyDict> {
+        let dict = PyDict::new(py);
+        dict.set_item("kind", self.kind)?;
+        dict.set_item("left", &self.left)?;
+        dict.set_item("right", &self.right)?;
+        dict.set_item("left_location", &self.left_location)?;
+        dict.set_item("right_location", &self.right_location)?;
+        Ok(dict)
+    }
+    fn _repr_html_(&self) -> String {
+        let mut output = String::new();
+        html::rows(&mut output, std::slice::from_ref(self));
+        output
+    }
+    fn to_json(&self, py: Python, indent: Option<usize>) -> PyResult<String> {
+        to_json(py, self.to_dict(py)?, indent)
+    }
+}
+
+/// A pair of aligned blocks and the instructions in them
+#[pyclass(module = "pyctflgrdiff")]
+#[derive(Clone)]
+pub struct BlockPair {
+    /// The name of the left-hand block; empty if the block is only in the right-hand function
+    #[pyo3(get)]
+    pub left: String,
+    /// The name of the right-hand block; empty if the block is only in the left-hand function
+    #[pyo3(get)]
+    pub right: String,
+    #[pyo3(get)]
+    pub rows: Vec<Row>,
+}
+#[pymethods]
+impl BlockPair {
+    fn __repr__(&self, py: Python) -> PyResult<String> {
+        Ok(format!(
+            "<BlockPair {} vs {}, {} rows>",
+            py_repr(py, &self.left)?,
+            py_repr(py, &self.right)?,
+            self.rows.len()
+        ))
+    }
+    fn __len__(&self) -> usize {
+        self.rows.len()
+    }
+    fn __iter__(&self, py: Python) -> PyResult<PyObject> {
+        iterate(py, &self.rows)
+    }
+    fn to_dict<'p>(&self, py: Python<'p>) -> PyResult<&'p PyDict> {
+        let dict = PyDict::new(py);
+        dict.set_item("left", &self.left)?;
+        dict.set_item("right", &self.right)?;
+        dict.set_item(
+            "rows",
+            self.rows
+                .iter()
+                .map(|row| row.to_dict(py))
+                .collect::<PyResult<Vec<_>>>()?,
+        )?;
+        Ok(dict)
+    }
+    fn _repr_html_(&self) -> String {
+        let mut output = String::new();
+        html::block(&mut output, self);
+        output
+    }
+    fn to_json(&self, py: Python, indent: Option<usize>) -> PyResult<String> {
+        to_json(py, self.to_dict(py)?, indent)
+    }
+}
+
+/// The comparison of one pair of functions
+#[pyclass(module = "pyctflgrdiff")]
+#[derive(Clone)]
+pub struct FunctionDiff {
+    #[pyo3(get)]
+    pub left_name: String,
+    #[pyo3(get)]
+    pub right_name: String,
+    #[pyo3(get)]
+    pub blocks: Vec<BlockPair>,
+}
+impl FunctionDiff {
+    /// Convert to the `(kind, left, right)` rows returned by `make_diff`, where a block is a row with no kind
+    pub fn into_tuples(self) -> Vec<(Option<&'static str>, String, String)> {
+        let mut rows = Vec::new();
+        for block in self.blocks {
+            rows.push((None, block.left, block.right));
+            rows.extend(
+                block
+                    .rows
+                    .into_iter()
+                    .map(|row| (Some(row.kind), row.left, row.right)),
+            );
+        }
+        rows
+    }
+}
+#[pymethods]
+impl FunctionDiff {
+    /// Whether any instructions in the functions differ
+    #[getter]
+    pub(crate) fn has_diff(&self) -> bool {
+        self.blocks
+            .iter()
+            .flat_map(|block| block.rows.iter())
+            .any(|row| row.kind != "match")
+    }
+    fn __repr__(&self, py: Python) -> PyResult<String> {
+        Ok(format!(
+            "<FunctionDiff {} vs {}, {} blocks>",
+            py_repr(py, &self.left_name)?,
+            py_repr(py, &self.right_name)?,
+            self.blocks.len()
+        ))
+    }
+    fn __len__(&self) -> usize {
+        self.blocks.len()
+    }
+    fn __iter__(&self, py: Python) -> PyResult<PyObject> {
+        iterate(py, &self.blocks)
+    }
+    fn to_dict<'p>(&self, py: Python<'p>) -> PyResult<&'p PyDict> {
+        let dict = PyDict::new(py);
+        dict.set_item("left_name", &self.left_name)?;
+        dict.set_item("right_name", &self.right_name)?;
+        dict.set_item("has_diff", self.has_diff())?;
+        dict.set_item(
+            "blocks",
+            self.blocks
+                .iter()
+                .map(|block| block.to_dict(py))
+                .collect::<PyResult<Vec<_>>>()?,
+        )?;
+        Ok(dict)
+    }
+    fn _repr_html_(&self) -> String {
+        let mut output = String::new();
+        html::function(&mut output, self);
+        output
+    }
+    fn to_json(&self, py: Python, indent: Option<usize>) -> PyResult<String> {
+        to_json(py, self.to_dict(py)?, indent)
+    }
+}
+
+/// A row produced by the diff, before the rows are grouped into blocks
+pub enum Entry {
+    Block(String, String),
+    Instruction(Row),
+}
+
+impl IntoDiffResult for FunctionDiff {
+    type Row = Entry;
+
+    fn block_row(left: Cow<str>, right: Cow<str>) -> Self::Row {
+        Entry::Block(left.to_string(), right.to_string())
+    }
+
+    fn row(left: Cow<str>, right: Cow<str>, kind: MatchDirection) -> Self::Row {
+        Self::row_with_location(left, right, kind, None, None)
+    }
+
+    fn row_with_location(
+        left: Cow<str>,
+        right: Cow<str>,
+        kind: MatchDirection,
+        left_location: Option<Cow<str>>,
+        right_location: Option<Cow<str>>,
+    ) -> Self::Row {
+        Entry::Instruction(Row {
+            kind: match kind {
+                MatchDirection::GapLeft => "left",
+                MatchDirection::GapRight => "right",
+                MatchDirection::Align(exact) => {
+                    if exact {
+                        "match"
+                    } else {
+                        "mismatch"
+                    }
+                }
+            },
+            left: left.to_string(),
+            right: right.to_string(),
+            left_location: left_location.map(Cow::into_owned),
+            right_location: right_location.map(Cow::into_owned),
+        })
+    }
+
+    fn function(left_name: Cow<str>, right_name: Cow<str>, rows: Vec<Self::Row>) -> Self {
+        let mut blocks: Vec<BlockPair> = Vec::new();
+        for row in rows {
+            match row {
+                Entry::Block(left, right) => blocks.push(BlockPair {
+                    left,
+                    right,
+                    rows: Vec::new(),
+                }),
+                Entry::Instruction(row) => match blocks.last_mut() {
+                    Some(block) => block.rows.push(row),
+                    None => blocks.push(BlockPair {
+                        left: String::new(),
+                        right: String::new(),
+                        rows: vec![row],
+                    }),
+                },
+            }
+        }
+        FunctionDiff {
+            left_name: left_name.to_string(),
+            right_name: right_name.to_string(),
+            blocks,
+        }
+    }
+}
+
+/// The result of comparing two programs
+#[pyclass(module = "pyctflgrdiff")]
+pub struct DiffResult {
+    /// Whether any of the functions differ
+    #[pyo3(get)]
+    pub has_diff: bool,
+    #[pyo3(get)]
+    pub functions: Vec<FunctionDiff>,
+}
+#[pymethods]
+impl DiffResult {
+    fn __repr__(&self) -> String {
+        format!(
+            "<DiffResult {} functions, {}>",
+            self.functions.len(),
+            if self.has_diff {
+                "with differences"
+            } else {
+                "identical"
+            }
+        )
+    }
+    fn __len__(&self) -> usize {
+        self.functions.len()
+    }
+    fn __iter__(&self, py: Python) -> PyResult<PyObject> {
+        iterate(py, &self.functions)
+    }
+    fn to_dict<'p>(&self, py: Python<'p>) -> PyResult<&'p PyDict> {
+        let dict = PyDict::new(py);
+        dict.set_item("has_diff", self.has_diff)?;
+        dict.set_item(
+            "functions",
+            self.functions
+                .iter()
+                .map(|function| function.to_dict(py))
+                .collect::<PyResult<Vec<_>>>()?,
+        )?;
+        Ok(dict)
+    }
+    fn _repr_html_(&self) -> String {
+        let mut output = String::new();
+        html::result(&mut output, self);
+        output
+    }
+    fn to_json(&self, py: Python, indent: Option<usize>) -> PyResult<String> {
+        to_json(py, self.to_dict(py)?, indent)
+    }
+}
@@ -0,0 +1,60 @@
+"""Tests for `diff_files_batch`, which compares pairs of files on several threads"""
+
+import os
+import tempfile
+
+import pyctflgrdiff
+import pytest
+
+
+def write_programs(directory, count):
+    """Write assembly files with a differently named function in each, with more instructions in later files"""
+    paths = []
+    for index in range(count):
+        path = os.path.join(directory, "f{}.s".format(index))
+        with open(path, "w") as f:
+            f.write("f{}:\n".format(index))
+            f.write("\taddl\t$1, %edi\n" * (index * 10))
+            f.write("\tmovl\t%edi, %eax\n\tretq\n")
+        paths.append(path)
+    return paths
+
+
+def test_order():
+    with tempfile.TemporaryDirectory() as directory:
+        paths = write_programs(directory, 12)
+        # The slowest pairs are first, so they finish last
+        pairs = [(path, path) for path in reversed(paths)]
+        for threads in (None, 1, 4, 100):
+            results = pyctflgrdiff.diff_files_batch(
+                "asm-x86-64", pairs, threads=threads
+            )
+            names = [result.functions[0].left_name for result in results]
+            assert names == ["f{}".format(index) for index in reversed(range(12))]
+            assert not any(result.has_diff for result in results)
+
+
+def test_empty():
+    assert pyctflgrdiff.diff_files_batch("asm-x86-64", []) == []
+
+
+def test_first_error_is_raised():
+    with tempfile.TemporaryDirectory() as directory:
+        paths = write_programs(directory, 8)
+        empty = os.path.join(directory, "empty")
+        os.mkdir(empty)
+        pairs = [(path, path) for path in paths]
+        # The first failing pair is missing a file and the second has nothing to parse
+        pairs[5] = (paths[5], empty)
+        pairs[2] = (os.path.join(directory, "missing.s"), paths[2])
+        for threads in (1, 4):
+            with pytest.raises(ValueError) as error:
+                pyctflgrdiff.diff_files_batch("asm-x86-64", pairs, threads=threads)
+            assert "os error" in str(error.value)
+
+
+def test_unknown_format():
+    with tempfile.TemporaryDirectory() as directory:
+        [path] = write_programs(directory, 1)
+        with pytest.raises(ValueError):
+            pyctflgrdiff.diff_files_batch("no-such-format", [(path, path)])
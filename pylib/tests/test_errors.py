@@ -0,0 +1,46 @@
+"""Tests that errors are raised as the module's exceptions and the built-in exceptions raised before they existed"""
+
+import os
+
+import pyctflgrdiff
+import pytest
+
+DUPLICATE = os.path.join(
+    os.path.dirname(__file__), "..", "..", "difflib", "testdata", "duplicate"
+)
+
+SQUARE = """
+square:
+	imull	%edi, %edi
+	movl	%edi, %eax
+	retq
+"""
+
+
+def test_unknown_format():
+    with pytest.raises(ValueError):
+        pyctflgrdiff.diff_strings("no-such-format", SQUARE)
+    with pytest.raises(pyctflgrdiff.UnknownFormatError):
+        pyctflgrdiff.diff_strings("no-such-format", SQUARE)
+
+
+def test_function_not_found():
+    for exception in (IndexError, LookupError, pyctflgrdiff.FunctionNotFoundError):
+        with pytest.raises(exception):
+            pyctflgrdiff.diff_strings("asm-x86-64", SQUARE, left_name="cube")
+
+
+def test_hierarchy():
+    assert issubclass(pyctflgrdiff.ParseError, pyctflgrdiff.CtflgrdiffError)
+    assert issubclass(pyctflgrdiff.ParseError, ValueError)
+    assert issubclass(pyctflgrdiff.UnknownFormatError, pyctflgrdiff.CtflgrdiffError)
+    assert issubclass(pyctflgrdiff.FunctionNotFoundError, pyctflgrdiff.CtflgrdiffError)
+
+
+def test_ambiguous_function():
+    with pytest.raises(pyctflgrdiff.AmbiguousFunctionError) as error:
+        pyctflgrdiff.diff_files("asm-x86-64", DUPLICATE, DUPLICATE, left_name="square")
+    assert error.value.side == "left"
+    assert error.value.name == "square"
+    assert sorted(error.value.candidates) == ["first.s:square", "second.s:square"]
+    assert isinstance(error.value, pyctflgrdiff.FunctionNotFoundError)
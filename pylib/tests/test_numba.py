@@ -0,0 +1,120 @@
+"""Tests for `diff_numba` using a stand-in for Numba's dispatcher, so Numba is not needed"""
+
+import os
+
+import pyctflgrdiff
+import pytest
+
+RELOCATIONS = os.path.join(
+    os.path.dirname(__file__),
+    "..",
+    "..",
+    "difflib",
+    "testdata",
+    "x86_64-relocations.o",
+)
+
+ADD_INT = """
+define i32 @_Z3addii(i32 %a, i32 %b) {
+entry:
+  %c = add i32 %a, %b
+  ret i32 %c
+}
+define i32 @cpython_wrapper(i32 %a) {
+entry:
+  ret i32 %a
+}
+"""
+
+ADD_FLOAT = """
+define double @_Z3adddd(double %a, double %b) {
+entry:
+  %c = fadd double %a, %b
+  ret double %c
+}
+define i32 @cpython_wrapper(i32 %a) {
+entry:
+  ret i32 %a
+}
+"""
+
+
+class FunctionDescriptor:
+    def __init__(self, mangled_name):
+        self.mangled_name = mangled_name
+
+
+class Library:
+    def __init__(self, native_object):
+        self.native_object = native_object
+
+    def emit_native_object(self):
+        return self.native_object
+
+
+class Overload:
+    def __init__(self, mangled_name, native_object=None):
+        self.fndesc = FunctionDescriptor(mangled_name)
+        self.library = Library(native_object)
+
+
+class Dispatcher:
+    """The parts of `numba.core.dispatcher.Dispatcher` used to get the generated code"""
+
+    def __init__(self, compiled, native_objects=None):
+        native_objects = native_objects or {}
+        self.llvm = {signature: ir for signature, (_, ir) in compiled.items()}
+        self.overloads = {
+            signature: Overload(name, native_objects.get(signature))
+            for signature, (name, _) in compiled.items()
+        }
+
+    @property
+    def signatures(self):
+        return list(self.llvm)
+
+    def inspect_llvm(self, signature=None):
+        if signature is None:
+            return dict(self.llvm)
+        return self.llvm[signature]
+
+
+INT = ("int32", "int32")
+FLOAT = ("float64", "float64")
+
+
+def test_two_signatures():
+    add = Dispatcher({INT: ("_Z3addii", ADD_INT), FLOAT: ("_Z3adddd", ADD_FLOAT)})
+    result = pyctflgrdiff.diff_numba(
+        add, left_signature=INT, right_signature=FLOAT, format="ll-text"
+    )
+    assert result.has_diff
+    [function] = result.functions
+    assert (function.left_name, function.right_name) == ("_Z3addii", "_Z3adddd")
+    kinds = {row.kind for block in function for row in block}
+    assert "mismatch" in kinds
+
+
+def test_two_dispatchers():
+    before = Dispatcher({INT: ("_Z3addii", ADD_INT)})
+    after = Dispatcher({INT: ("_Z3addii", ADD_INT)})
+    result = pyctflgrdiff.diff_numba(before, after, format="ll-text")
+    [function] = result.functions
+    assert not function.has_diff
+
+
+def test_ambiguous_signature():
+    add = Dispatcher({INT: ("_Z3addii", ADD_INT), FLOAT: ("_Z3adddd", ADD_FLOAT)})
+    with pytest.raises(ValueError):
+        pyctflgrdiff.diff_numba(add, format="ll-text")
+
+
+def test_native_object():
+    with open(RELOCATIONS, "rb") as f:
+        native_object = f.read()
+    before = Dispatcher({INT: ("_Z3bazi", None)}, {INT: native_object})
+    after = Dispatcher({INT: ("_Z3bazi", None)}, {INT: native_object})
+    result = pyctflgrdiff.diff_numba(before, after, format="x64")
+    [function] = result.functions
+    assert (function.left_name, function.right_name) == ("baz(int)", "baz(int)")
+    assert not function.has_diff
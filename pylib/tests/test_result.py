@@ -0,0 +1,68 @@
+"""Tests for the result classes returned by the diff functions"""
+
+import pyctflgrdiff
+
+SQUARE = """
+square:
+	imull	%edi, %edi
+	movl	%edi, %eax
+	retq
+"""
+
+CUBE = """
+square:
+	movl	%edi, %eax
+	imull	%edi, %eax
+	imull	%edi, %eax
+	retq
+"""
+
+
+def test_same_program():
+    result = pyctflgrdiff.diff_strings("asm-x86-64", SQUARE)
+    assert not result.has_diff
+    [function] = result.functions
+    assert not function.has_diff
+
+
+def test_different_program():
+    result = pyctflgrdiff.diff_strings("asm-x86-64", SQUARE, CUBE)
+    assert result.has_diff
+    [function] = result.functions
+    assert function.has_diff
+
+
+def test_html():
+    result = pyctflgrdiff.diff_strings("asm-x86-64", SQUARE, CUBE)
+    html = result._repr_html_()
+    assert html.startswith("<div><p>1 functions, with differences</p>")
+    assert html.endswith("</div>")
+    [function] = result.functions
+    # Functions with differences are expanded
+    assert function._repr_html_().startswith("<details open>")
+    assert function._repr_html_() in html
+    [block] = function.blocks
+    assert block._repr_html_() in function._repr_html_()
+    # A row on its own is a table of one row
+    kinds = {row.kind: row._repr_html_() for row in block}
+    assert "background-color" in kinds["left"]
+    assert kinds["match"].startswith("<table")
+    assert "background-color" not in kinds["match"]
+
+
+def test_html_identical():
+    result = pyctflgrdiff.diff_strings("asm-x86-64", SQUARE)
+    html = result._repr_html_()
+    assert "1 functions, identical" in html
+    # Identical functions are collapsed
+    [function] = result.functions
+    assert function._repr_html_().startswith("<details><summary")
+
+
+def test_html_escapes_text():
+    result = pyctflgrdiff.diff_strings(
+        "asm-x86-64", "shift:\n\tmovl\t$(1<<2)&7, %eax\n\tretq\n"
+    )
+    html = result._repr_html_()
+    assert "movl $(1&lt;&lt;2)&amp;7, %eax" in html
+    assert "1<<2" not in html